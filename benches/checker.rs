@@ -0,0 +1,170 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Benchmarks for the parts of the checker that changes to performance sensitive code, like name
+//! interning or how types are owned, should be measured against. Run with `cargo bench`.
+
+use std::{fmt::Write, hint::black_box, path::PathBuf, thread};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pycavalry::{
+    error_check_file, synth_annotation, union, Info, Scope, Symbol, Type, TypeLiteral,
+};
+use ruff_python_ast::{Expr, Mod};
+use ruff_python_parser::{parse, Mode};
+
+/// Files checked from start to end, by name.
+const CORPUS: [(&str, &str); 2] = [
+    ("inventory.py", include_str!("corpus/inventory.py")),
+    ("test.py", include_str!("../test.py")),
+];
+
+fn parse_annotation(source: &str) -> Expr {
+    match parse(source, Mode::Expression).unwrap().into_syntax() {
+        Mod::Module(_) => unreachable!(),
+        Mod::Expression(e) => *e.body,
+    }
+}
+
+fn annotations(c: &mut Criterion) {
+    let mut group = c.benchmark_group("annotation");
+    for source in [
+        "int",
+        "Optional[Union[int, str, None]]",
+        "Callable[[tuple[int, ...], Literal[1, 2, 3]], Dict[str, List[float]]]",
+    ] {
+        let expr = parse_annotation(source);
+        group.bench_with_input(BenchmarkId::from_parameter(source), &expr, |b, expr| {
+            b.iter(|| {
+                let info = Info::default();
+                synth_annotation(&info, &mut Scope::new(), Some(black_box(expr.clone())))
+            })
+        });
+    }
+    group.finish();
+}
+
+fn unions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("union");
+    for size in [4, 32, 256] {
+        // Reversed literals with their supertypes mixed in, so every step of normalization has
+        // something to do
+        let types: Vec<Type> = (0..size)
+            .rev()
+            .map(|i| match i % 4 {
+                0 => Type::Int,
+                1 => Type::None,
+                _ => Type::Literal(TypeLiteral::IntLiteral(i)),
+            })
+            .collect();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &types, |b, types| {
+            b.iter(|| union(black_box(types.clone())))
+        });
+    }
+    group.finish();
+}
+
+/// A function with a body of `statements` annotated assignments and calls.
+fn large_function(statements: usize) -> String {
+    let mut source = "def helper(a: int, b: str) -> int:\n    return a\n\n".to_owned();
+    source.push_str("def large(a: int, b: str) -> int:\n");
+    for i in 0..statements {
+        match i % 3 {
+            0 => writeln!(source, "    x{}: int = helper(a, b)", i),
+            1 => writeln!(source, "    y{}: str = b", i),
+            _ => writeln!(source, "    z{} = (a, b, x{})", i, i - 2),
+        }
+        .unwrap();
+    }
+    source.push_str("    return a\n");
+    source
+}
+
+fn functions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("function");
+    for statements in [10, 100, 1000] {
+        let source = large_function(statements);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(statements),
+            &source,
+            |b, source| {
+                b.iter(|| error_check_file(PathBuf::from("large.py"), black_box(source.clone())))
+            },
+        );
+    }
+    group.finish();
+}
+
+fn files(c: &mut Criterion) {
+    let mut group = c.benchmark_group("file");
+    for (name, source) in CORPUS {
+        group.bench_with_input(BenchmarkId::from_parameter(name), source, |b, source| {
+            b.iter(|| error_check_file(PathBuf::from(name), black_box(source.to_owned())))
+        });
+    }
+    group.finish();
+}
+
+fn symbols(c: &mut Criterion) {
+    let names: Vec<String> = (0..1000).map(|i| format!("name{}", i)).collect();
+    for name in &names {
+        Symbol::new(name);
+    }
+    c.bench_function("symbol/existing", |b| {
+        b.iter(|| {
+            for name in &names {
+                black_box(Symbol::new(black_box(name)));
+            }
+        })
+    });
+}
+
+/// The corpus checked from several threads at once, which is where shared state like the name
+/// interner is contended.
+fn parallel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parallel");
+    for threads in [1, 4] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    thread::scope(|s| {
+                        for _ in 0..threads {
+                            s.spawn(|| {
+                                for (name, source) in CORPUS {
+                                    error_check_file(PathBuf::from(name), source.to_owned())
+                                        .unwrap();
+                                }
+                            });
+                        }
+                    })
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    annotations,
+    unions,
+    functions,
+    files,
+    symbols,
+    parallel
+);
+criterion_main!(benches);
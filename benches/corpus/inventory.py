@@ -0,0 +1,95 @@
+# This file is part of pycavalry.
+#
+# pycavalry is free software: you can redistribute it and/or modify
+# it under the terms of the GNU Affero General Public License as published
+# by the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+#
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+# GNU Affero General Public License for more details.
+#
+# You should have received a copy of the GNU Affero General Public License
+# along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+# A small, fully typed module in the style of typical application code, used to benchmark
+# checking a whole file.
+
+import sys
+from typing import Callable, Dict, List, Literal, Optional, Sequence, Union
+
+VERSION = (1, 4, 2)
+DEBUG = False
+Unit = Literal["kg", "g", "piece"]
+
+if sys.version_info >= (3, 10):
+    NEW_PYTHON = True
+else:
+    NEW_PYTHON = False
+
+
+class Item:
+    name: str
+    unit: Unit
+    count: int
+
+
+class OutOfStock(Exception):
+    pass
+
+
+def normalize(name: str, unit: Unit) -> str:
+    if unit == "g":
+        return name + " (g)"
+    return name
+
+
+def total(counts: Sequence[int]) -> int:
+    result = 0
+    for count in counts:
+        result = result + count
+    return result
+
+
+def lookup(stock: Dict[str, int], name: str, default: Optional[int] = None) -> Optional[int]:
+    try:
+        value = default
+    except KeyError as e:
+        value = None
+    return value
+
+
+def scale(amount: Union[int, float], factor: float) -> float:
+    return amount * factor
+
+
+def apply(f: Callable[[int], int], values: tuple[int, ...]) -> tuple[int, ...]:
+    first = values[0]
+    doubled = f(first)
+    return (doubled, *values)
+
+
+def describe(count: int) -> str:
+    if count < 0:
+        return "negative"
+    elif count == 0:
+        return "empty"
+    else:
+        return "some"
+
+
+def report(names: List[str], limit: int = 10) -> int:
+    shown = 0
+    for name in names:
+        if shown >= limit:
+            break
+        shown = shown + 1
+    while shown > limit:
+        shown = shown - 1
+    return shown
+
+
+LABELS = ("small", "medium", "large")
+FIRST = LABELS[0]
+LAST = LABELS[-1]
@@ -0,0 +1,59 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Embeds the standard library stubs of the typeshed submodule into the binary, so an installed
+//! binary or the wasm build doesn't depend on where the source was when it was built.
+
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Every `.pyi` file under the directory, with its path relative to `root` using `/`.
+fn collect(dir: &Path, root: &Path, stubs: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect(&path, root, stubs)?;
+        } else if path.extension().is_some_and(|e| e == "pyi") {
+            let relative: Vec<_> = path
+                .strip_prefix(root)
+                .unwrap()
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            stubs.push((relative.join("/"), path));
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let stdlib = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("typeshed/stdlib");
+    println!("cargo:rerun-if-changed=typeshed/stdlib");
+
+    // Without the submodule checked out there are no bundled stubs
+    let mut stubs = vec![];
+    let _ = collect(&stdlib, &stdlib, &mut stubs);
+    stubs.sort();
+
+    let mut out = String::from("pub const BUNDLED_STUBS: &[(&str, &str)] = &[\n");
+    for (name, path) in stubs {
+        out += &format!("    ({:?}, include_str!({:?})),\n", name, path);
+    }
+    out += "];\n";
+    let dest = PathBuf::from(env::var("OUT_DIR").unwrap()).join("bundled_stubs.rs");
+    fs::write(dest, out).unwrap();
+}
@@ -0,0 +1,559 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Facts recorded while checking a file, used to answer editor queries afterwards.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use ruff_python_ast::Expr;
+use ruff_text_size::{TextRange, TextSize};
+
+use crate::{
+    scope::{Binding, ScopedType},
+    state::Info,
+    symbol::Symbol,
+    types::{widen, with_union_style, Function, UnionStyle},
+    Type,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CallSite {
+    pub name: Symbol,
+    pub callee: Function,
+    /// The parentheses of the call and everything between them
+    pub arguments: TextRange,
+    pub argument_ranges: Vec<TextRange>,
+    /// Types of the positional arguments, empty if they couldn't be checked against the callee
+    pub argument_types: Vec<Type>,
+}
+
+/// The variables visible somewhere in the file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScopeSnapshot {
+    pub range: TextRange,
+    pub names: Vec<(Symbol, ScopedType)>,
+}
+
+/// An attribute access on a value with known members.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttributeSite {
+    /// From the dot to the end of the attribute name
+    pub range: TextRange,
+    pub members: Vec<(Symbol, ScopedType)>,
+}
+
+/// An `Annotated[T, ...]` annotation, checked as T with the metadata kept for plugins.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotatedSite {
+    pub range: TextRange,
+    pub typ: Type,
+    pub metadata: Vec<Expr>,
+}
+
+/// A parameter without an annotation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnannotatedParameter {
+    pub index: usize,
+    /// From the end of the name to the start of the default value, replaced when annotating
+    pub range: TextRange,
+    /// Type of the default value
+    pub default: Option<Type>,
+}
+
+/// A function missing some of its annotations, with what could be inferred for them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnannotatedFunction {
+    pub name: Symbol,
+    pub parameters: Vec<UnannotatedParameter>,
+    /// Where `-> T` goes and the inferred return type, if the return type isn't annotated
+    pub returns: Option<(TextSize, Type)>,
+}
+
+/// The inferred type of an expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpressionType {
+    pub range: TextRange,
+    pub typ: Type,
+}
+
+#[derive(Clone, Debug, Default)]
+struct AnalysisData {
+    calls: Vec<CallSite>,
+    scopes: Vec<ScopeSnapshot>,
+    attributes: Vec<AttributeSite>,
+    references: Vec<Reference>,
+    annotated: Vec<AnnotatedSite>,
+    unannotated: Vec<UnannotatedFunction>,
+    /// Only recorded when the config asks for it
+    types: Vec<ExpressionType>,
+    /// Variables whose value is used somewhere
+    reads: HashSet<Binding>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Reference {
+    /// Attributes don't bind anything in the file
+    binding: Option<Binding>,
+    range: TextRange,
+    kind: TokenKind,
+}
+
+#[derive(Clone, Default)]
+pub struct Analysis(Arc<Mutex<AnalysisData>>);
+
+impl fmt::Debug for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Analysis")
+    }
+}
+
+impl Analysis {
+    /// Record a call, returning its index for adding the argument types once they are known
+    pub fn add_call(&self, call: CallSite) -> usize {
+        let mut data = self.0.lock().unwrap();
+        data.calls.push(call);
+        data.calls.len() - 1
+    }
+    pub fn set_argument_types(&self, call: usize, types: Vec<Type>) {
+        let mut data = self.0.lock().unwrap();
+        data.calls[call].argument_types = types;
+    }
+    pub fn add_scope(&self, scope: ScopeSnapshot) {
+        let mut data = self.0.lock().unwrap();
+        data.scopes.push(scope);
+    }
+    pub fn add_attribute(&self, attribute: AttributeSite) {
+        let mut data = self.0.lock().unwrap();
+        data.attributes.push(attribute);
+    }
+    pub fn add_unannotated(&self, function: UnannotatedFunction) {
+        let mut data = self.0.lock().unwrap();
+        data.unannotated.push(function);
+    }
+    pub fn add_annotated(&self, annotated: AnnotatedSite) {
+        let mut data = self.0.lock().unwrap();
+        data.annotated.push(annotated);
+    }
+    pub fn add_type(&self, range: TextRange, typ: Type) {
+        let mut data = self.0.lock().unwrap();
+        data.types.push(ExpressionType { range, typ });
+    }
+    /// Record a read or write of a variable at the range of its name
+    pub fn add_reference(&self, binding: Binding, range: TextRange, kind: TokenKind) {
+        let mut data = self.0.lock().unwrap();
+        data.references.push(Reference {
+            binding: Some(binding),
+            range,
+            kind,
+        });
+    }
+    /// Record a use of a variable's value, which is also a reference to it
+    pub fn add_read(&self, binding: Binding, range: TextRange, kind: TokenKind) {
+        let mut data = self.0.lock().unwrap();
+        data.reads.insert(binding.clone());
+        data.references.push(Reference {
+            binding: Some(binding),
+            range,
+            kind,
+        });
+    }
+    /// Whether the value of the variable has been used so far
+    pub fn is_read(&self, binding: &Binding) -> bool {
+        let data = self.0.lock().unwrap();
+        data.reads.contains(binding)
+    }
+    /// Record a name that isn't a variable in the file, like a module attribute
+    pub fn add_token(&self, range: TextRange, kind: TokenKind) {
+        let mut data = self.0.lock().unwrap();
+        data.references.push(Reference {
+            binding: None,
+            range,
+            kind,
+        });
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParameterHelp {
+    pub name: Symbol,
+    pub typ: Type,
+    pub default: Option<Arc<String>>,
+}
+
+impl fmt::Display for ParameterHelp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.typ)?;
+        if let Some(default) = &self.default {
+            write!(f, " = {}", default)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignatureHelp {
+    /// The whole signature, like `f(a: int, b: str = "") -> None`
+    pub label: String,
+    pub parameters: Vec<ParameterHelp>,
+    /// Index of the parameter the offset is in
+    pub active_parameter: usize,
+    pub docstring: Option<Arc<String>>,
+}
+
+/// Signature of the innermost call whose parentheses contain the offset.
+pub fn signature_at(info: &Info, offset: TextSize) -> Option<SignatureHelp> {
+    let data = info.analysis.0.lock().unwrap();
+    let call = data
+        .calls
+        .iter()
+        .filter(|c| c.arguments.start() < offset && offset < c.arguments.end())
+        .min_by_key(|c| c.arguments.len())?;
+
+    let parameters: Vec<ParameterHelp> = call
+        .callee
+        .arg_names
+        .iter()
+        .zip(call.callee.args.iter())
+        .enumerate()
+        .map(|(i, (name, typ))| ParameterHelp {
+            name: name.clone(),
+            typ: typ.clone(),
+            default: call.callee.defaults.get(i).cloned().flatten(),
+        })
+        .collect();
+    let label = format!(
+        "{}({}) -> {}",
+        call.name,
+        parameters
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<String>>()
+            .join(", "),
+        call.callee.ret
+    );
+    let active_parameter = call
+        .argument_ranges
+        .iter()
+        .filter(|r| r.end() < offset)
+        .count();
+
+    Some(SignatureHelp {
+        label,
+        parameters,
+        active_parameter,
+        docstring: call.callee.docstring.clone(),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompletionKind {
+    Variable,
+    Function,
+    Class,
+    Module,
+    Keyword,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Completion {
+    pub label: Symbol,
+    pub kind: CompletionKind,
+    pub typ: Option<Type>,
+}
+
+impl Completion {
+    fn from_scoped(name: &Symbol, scoped: &ScopedType) -> Completion {
+        let kind = match scoped.typ {
+            Type::Function(_) | Type::PartialFunction(_) => CompletionKind::Function,
+            Type::Class(_) => CompletionKind::Class,
+            Type::Module(_, _) => CompletionKind::Module,
+            _ => CompletionKind::Variable,
+        };
+        Completion {
+            label: name.clone(),
+            kind,
+            typ: Some(scoped.typ.clone()),
+        }
+    }
+}
+
+const KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// Completions for the identifier ending at the offset, sorted by label.
+///
+/// After a dot these are the members of the value, otherwise the variables visible at the offset
+/// and the Python keywords.
+pub fn completions_at(info: &Info, offset: TextSize) -> Vec<Completion> {
+    let data = info.analysis.0.lock().unwrap();
+    let before = &info.file_content[..offset.to_usize().min(info.file_content.len())];
+    let prefix_start = before
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let prefix = &before[prefix_start..];
+
+    let attribute = data
+        .attributes
+        .iter()
+        .find(|a| a.range.start() < offset && offset <= a.range.end());
+    let mut completions: Vec<Completion> = match attribute {
+        Some(attribute) => attribute
+            .members
+            .iter()
+            .map(|(name, scoped)| Completion::from_scoped(name, scoped))
+            .collect(),
+        None => {
+            // Names from enclosing scopes are looked up when the code runs, so they are taken
+            // from the snapshots of the outer scopes, with inner scopes shadowing them.
+            let mut scopes: Vec<&ScopeSnapshot> = data
+                .scopes
+                .iter()
+                .filter(|s| s.range.contains_inclusive(offset))
+                .collect();
+            scopes.sort_by_key(|s| s.range.len());
+            let mut names: HashMap<&Symbol, &ScopedType> = HashMap::new();
+            for (name, scoped) in scopes.iter().flat_map(|s| s.names.iter()) {
+                names.entry(name).or_insert(scoped);
+            }
+            names
+                .into_iter()
+                .map(|(name, scoped)| Completion::from_scoped(name, scoped))
+                .chain(KEYWORDS.iter().map(|k| Completion {
+                    label: Symbol::new(k),
+                    kind: CompletionKind::Keyword,
+                    typ: None,
+                }))
+                .collect()
+        }
+    };
+    completions.retain(|c| c.label.starts_with(prefix));
+    completions.sort_by(|a, b| a.label.cmp(&b.label));
+    completions
+}
+
+/// Every read and write of the variable at the offset, sorted by position.
+pub fn references_of(info: &Info, offset: TextSize) -> Vec<TextRange> {
+    let data = info.analysis.0.lock().unwrap();
+    let Some(binding) = data
+        .references
+        .iter()
+        .find(|r| r.binding.is_some() && r.range.contains_inclusive(offset))
+        .and_then(|r| r.binding.as_ref())
+    else {
+        return vec![];
+    };
+    let mut references: Vec<TextRange> = data
+        .references
+        .iter()
+        .filter(|r| r.binding.as_ref() == Some(binding))
+        .map(|r| r.range)
+        .collect();
+    references.sort_by_key(|r| r.start());
+    references.dedup();
+    references
+}
+
+/// Every `Annotated[...]` annotation in the file, in the order they were checked.
+pub fn annotated_metadata(info: &Info) -> Vec<AnnotatedSite> {
+    info.analysis.0.lock().unwrap().annotated.clone()
+}
+
+/// The type of every expression in the file, sorted by where they start with outer expressions
+/// before the ones inside them. Empty unless `Config::record_types` was set when checking.
+pub fn type_map(info: &Info) -> Vec<ExpressionType> {
+    let mut types = info.analysis.0.lock().unwrap().types.clone();
+    types.sort_by_key(|t| (t.range.start(), std::cmp::Reverse(t.range.end())));
+    // An expression checked more than once keeps the type from the last time
+    let mut map: Vec<ExpressionType> = Vec::with_capacity(types.len());
+    for typ in types {
+        match map.last_mut() {
+            Some(last) if last.range == typ.range => *last = typ,
+            _ => map.push(typ),
+        }
+    }
+    map
+}
+
+/// A replacement of the text in a range.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextEdit {
+    pub range: TextRange,
+    pub new_text: String,
+}
+
+/// The edits renaming the variable at the offset.
+pub fn rename_edits(info: &Info, offset: TextSize, new_name: &str) -> Vec<TextEdit> {
+    references_of(info, offset)
+        .into_iter()
+        .map(|range| TextEdit {
+            range,
+            new_text: new_name.to_owned(),
+        })
+        .collect()
+}
+
+/// Whether the type can be written as an annotation without any imports.
+fn is_writable(typ: &Type) -> bool {
+    match typ {
+        Type::String | Type::Int | Type::Float | Type::Bool | Type::None | Type::Instance(_) => {
+            true
+        }
+        Type::Tuple(types) | Type::Union(types) | Type::Generic(_, types) => {
+            !types.is_empty() && types.iter().all(is_writable)
+        }
+        _ => false,
+    }
+}
+
+/// The annotation for an inferred type, if it is known well enough to write down.
+fn annotation_text(typ: &Type) -> Option<String> {
+    let typ = widen(typ);
+    is_writable(&typ).then(|| with_union_style(UnionStyle::Pipe, || typ.to_string()))
+}
+
+/// The edits adding annotations to functions that are missing them.
+///
+/// Return types are the inferred ones, and a parameter is annotated when its default value and
+/// every call to the function by name in the file agree on its type.
+pub fn annotation_edits(info: &Info) -> Vec<TextEdit> {
+    let data = info.analysis.0.lock().unwrap();
+    let mut edits = vec![];
+    for function in &data.unannotated {
+        // Calls can't be told apart when several functions share the name
+        let defined = data
+            .unannotated
+            .iter()
+            .filter(|f| f.name == function.name)
+            .count();
+        let calls: Vec<&CallSite> = data
+            .calls
+            .iter()
+            .filter(|c| c.name == function.name && !c.argument_types.is_empty())
+            .collect();
+        for param in &function.parameters {
+            let mut seen = param.default.iter().map(widen).collect::<Vec<_>>();
+            if defined == 1 {
+                seen.extend(
+                    calls
+                        .iter()
+                        .filter_map(|c| c.argument_types.get(param.index))
+                        .map(widen),
+                );
+            }
+            let Some(first) = seen.first() else {
+                continue;
+            };
+            if seen.iter().any(|t| t != first) {
+                continue;
+            }
+            let Some(annotation) = annotation_text(first) else {
+                continue;
+            };
+            edits.push(TextEdit {
+                range: param.range,
+                new_text: match param.default {
+                    Some(_) => format!(": {} = ", annotation),
+                    None => format!(": {}", annotation),
+                },
+            });
+        }
+        if let Some((offset, ret)) = &function.returns {
+            if let Some(annotation) = annotation_text(ret) {
+                edits.push(TextEdit {
+                    range: TextRange::empty(*offset),
+                    new_text: format!(" -> {}", annotation),
+                });
+            }
+        }
+    }
+    edits
+}
+
+/// Apply non overlapping edits to the source code.
+pub fn apply_edits(source: &str, edits: &[TextEdit]) -> String {
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|e| e.range.start());
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+    for edit in edits {
+        result.push_str(&source[last..edit.range.start().to_usize()]);
+        result.push_str(&edit.new_text);
+        last = edit.range.end().to_usize();
+    }
+    result.push_str(&source[last..]);
+    result
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Variable,
+    Parameter,
+    Function,
+    Class,
+    Module,
+}
+
+impl TokenKind {
+    pub fn of(typ: &Type) -> TokenKind {
+        match typ {
+            Type::Function(_) | Type::PartialFunction(_) => TokenKind::Function,
+            Type::Class(_) => TokenKind::Class,
+            Type::Module(_, _) => TokenKind::Module,
+            _ => TokenKind::Variable,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SemanticToken {
+    pub range: TextRange,
+    pub kind: TokenKind,
+}
+
+/// The kind of every name in the file, sorted by position.
+///
+/// Every use of a parameter is classified as a parameter, not only its definition.
+pub fn semantic_tokens(info: &Info) -> Vec<SemanticToken> {
+    let data = info.analysis.0.lock().unwrap();
+    let parameters: HashSet<&Binding> = data
+        .references
+        .iter()
+        .filter(|r| r.kind == TokenKind::Parameter)
+        .filter_map(|r| r.binding.as_ref())
+        .collect();
+    let mut tokens: Vec<SemanticToken> = data
+        .references
+        .iter()
+        .map(|r| SemanticToken {
+            range: r.range,
+            kind: match &r.binding {
+                Some(binding) if parameters.contains(binding) => TokenKind::Parameter,
+                _ => r.kind,
+            },
+        })
+        .collect();
+    tokens.sort_by_key(|t| t.range.start());
+    tokens.dedup_by_key(|t| t.range);
+    tokens
+}
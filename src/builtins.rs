@@ -0,0 +1,390 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    scope::ScopedType,
+    symbol::Symbol,
+    types::{generic_arity, generic_name, is_subtype, union, Class, Function, Type},
+};
+
+/// The builtin classes with their own types, see `builtin_instance`.
+const CLASSES: &[&str] = &[
+    "object", "int", "str", "float", "bool", "bytes", "list", "dict", "set", "tuple",
+];
+
+/// The built in exception classes and their base class, every base comes before its subclasses.
+const EXCEPTIONS: &[(&str, Option<&str>)] = &[
+    ("BaseException", None),
+    ("Exception", Some("BaseException")),
+    ("KeyboardInterrupt", Some("BaseException")),
+    ("SystemExit", Some("BaseException")),
+    ("ArithmeticError", Some("Exception")),
+    ("ZeroDivisionError", Some("ArithmeticError")),
+    ("AssertionError", Some("Exception")),
+    ("AttributeError", Some("Exception")),
+    ("ImportError", Some("Exception")),
+    ("ModuleNotFoundError", Some("ImportError")),
+    ("LookupError", Some("Exception")),
+    ("IndexError", Some("LookupError")),
+    ("KeyError", Some("LookupError")),
+    ("NameError", Some("Exception")),
+    ("OSError", Some("Exception")),
+    ("FileNotFoundError", Some("OSError")),
+    ("RuntimeError", Some("Exception")),
+    ("NotImplementedError", Some("RuntimeError")),
+    ("StopIteration", Some("Exception")),
+    ("TypeError", Some("Exception")),
+    ("ValueError", Some("Exception")),
+];
+
+/// A builtin function from its parameters, with the source of their default values, and return
+/// type.
+fn function(params: &[(&str, Type, Option<&str>)], ret: Type) -> Function {
+    Function::new(
+        params.iter().map(|p| p.1.clone()).collect(),
+        params.iter().map(|p| Symbol::new(p.0)).collect(),
+        Box::new(ret),
+    )
+    .with_defaults(
+        params
+            .iter()
+            .map(|p| p.2.map(|d| Arc::new(d.to_owned())))
+            .collect(),
+    )
+}
+
+/// Keyword only parameters from their name, type and the source of their default value.
+fn keyword_only(params: &[(&str, Type, &str)]) -> Vec<(Symbol, Type, Option<Arc<String>>)> {
+    params
+        .iter()
+        .map(|(name, typ, default)| {
+            (
+                Symbol::new(name),
+                typ.clone(),
+                Some(Arc::new(default.to_string())),
+            )
+        })
+        .collect()
+}
+
+/// The builtin functions, parameters that take any object or a protocol that can't be expressed
+/// yet are Any.
+fn functions() -> Vec<(&'static str, Function)> {
+    let iterable = Type::Generic(Symbol::new("Iterable"), vec![Type::Any]);
+    let optional_str = union(vec![Type::String, Type::None]);
+    vec![
+        ("len", function(&[("obj", Type::Any, None)], Type::Int)),
+        (
+            "print",
+            function(&[], Type::None)
+                .with_variadic(Symbol::new("values"), Type::Any)
+                .with_keyword_only(keyword_only(&[
+                    ("sep", optional_str.clone(), "\" \""),
+                    ("end", optional_str.clone(), "\"\\n\""),
+                    ("file", Type::Any, "None"),
+                    ("flush", Type::Bool, "False"),
+                ])),
+        ),
+        (
+            "isinstance",
+            function(
+                &[
+                    ("obj", Type::Any, None),
+                    ("class_or_tuple", Type::Any, None),
+                ],
+                Type::Bool,
+            ),
+        ),
+        (
+            "issubclass",
+            function(
+                &[
+                    ("cls", Type::Any, None),
+                    ("class_or_tuple", Type::Any, None),
+                ],
+                Type::Bool,
+            ),
+        ),
+        (
+            "range",
+            function(
+                &[
+                    ("start", Type::Int, None),
+                    ("stop", Type::Int, Some("...")),
+                    ("step", Type::Int, Some("1")),
+                ],
+                Type::Generic(Symbol::new("Sequence"), vec![Type::Int]),
+            ),
+        ),
+        (
+            "input",
+            function(&[("prompt", Type::String, Some("\"\""))], Type::String),
+        ),
+        ("repr", function(&[("obj", Type::Any, None)], Type::String)),
+        ("ascii", function(&[("obj", Type::Any, None)], Type::String)),
+        ("hash", function(&[("obj", Type::Any, None)], Type::Int)),
+        ("id", function(&[("obj", Type::Any, None)], Type::Int)),
+        ("chr", function(&[("i", Type::Int, None)], Type::String)),
+        ("ord", function(&[("c", Type::String, None)], Type::Int)),
+        (
+            "callable",
+            function(&[("obj", Type::Any, None)], Type::Bool),
+        ),
+        (
+            "hasattr",
+            function(
+                &[("obj", Type::Any, None), ("name", Type::String, None)],
+                Type::Bool,
+            ),
+        ),
+        (
+            "getattr",
+            function(
+                &[
+                    ("obj", Type::Any, None),
+                    ("name", Type::String, None),
+                    ("default", Type::Any, Some("...")),
+                ],
+                Type::Any,
+            ),
+        ),
+        (
+            "setattr",
+            function(
+                &[
+                    ("obj", Type::Any, None),
+                    ("name", Type::String, None),
+                    ("value", Type::Any, None),
+                ],
+                Type::None,
+            ),
+        ),
+        (
+            "sorted",
+            function(
+                &[("iterable", iterable.clone(), None)],
+                Type::Generic(Symbol::new("list"), vec![Type::Any]),
+            )
+            .with_keyword_only(keyword_only(&[
+                ("key", Type::Any, "None"),
+                ("reverse", Type::Bool, "False"),
+            ])),
+        ),
+        (
+            "any",
+            function(&[("iterable", iterable.clone(), None)], Type::Bool),
+        ),
+        (
+            "all",
+            function(&[("iterable", iterable.clone(), None)], Type::Bool),
+        ),
+        ("iter", function(&[("obj", iterable, None)], Type::Any)),
+        (
+            "next",
+            function(
+                &[
+                    ("iterator", Type::Any, None),
+                    ("default", Type::Any, Some("...")),
+                ],
+                Type::Any,
+            ),
+        ),
+        (
+            "open",
+            function(
+                &[
+                    ("file", Type::String, None),
+                    ("mode", Type::String, Some("\"r\"")),
+                    ("encoding", optional_str, Some("None")),
+                ],
+                Type::Any,
+            ),
+        ),
+    ]
+}
+
+pub type Builtins = Arc<HashMap<Symbol, ScopedType>>;
+
+/// The names available in every module without an import, shared between all scopes.
+pub fn builtins() -> Builtins {
+    static BUILTINS: OnceLock<Builtins> = OnceLock::new();
+    BUILTINS.get_or_init(|| Arc::new(load_builtins())).clone()
+}
+
+fn load_builtins() -> HashMap<Symbol, ScopedType> {
+    let mut builtins = HashMap::new();
+    builtins.insert(
+        Symbol::new("reveal_type"),
+        ScopedType::new(Type::Function(Function::new(
+            vec![Type::Any],
+            vec![Symbol::new("obj")],
+            Box::new(Type::Any),
+        ))),
+    );
+    builtins.insert(Symbol::new("__debug__"), ScopedType::new(Type::Bool));
+    for (name, function) in functions() {
+        builtins.insert(Symbol::new(name), ScopedType::new(Type::Function(function)));
+    }
+    let module = Symbol::new("builtins");
+    for name in CLASSES {
+        let class = Class::new(module.clone(), Symbol::new(name), vec![], vec![]);
+        builtins.insert(Symbol::new(name), ScopedType::new(Type::Class(class)));
+    }
+    let mut exceptions: HashMap<&str, Class> = HashMap::new();
+    for (name, base) in EXCEPTIONS {
+        let bases = base
+            .map(|base| vec![Type::Instance(exceptions[base].clone())])
+            .unwrap_or_default();
+        let class = Class::new(module.clone(), Symbol::new(name), vec![], vec![]).with_bases(bases);
+        exceptions.insert(name, class.clone());
+        builtins.insert(Symbol::new(name), ScopedType::new(Type::Class(class)));
+    }
+    builtins
+}
+
+/// The type of the instances of a builtin class like int, which have their own types instead of
+/// being a `Type::Instance`.
+pub fn builtin_instance(cls: &Class) -> Option<Type> {
+    if cls.module.as_str() != "builtins" {
+        return None;
+    }
+    Some(match cls.name.as_str() {
+        // Everything is an object
+        "object" => Type::Any,
+        "int" => Type::Int,
+        "str" => Type::String,
+        "float" => Type::Float,
+        "bool" => Type::Bool,
+        // TODO: Use a bytes type once there is one
+        "bytes" => Type::Unknown,
+        name @ ("list" | "dict" | "set" | "tuple") => {
+            Type::Generic(Symbol::new(name), vec![Type::Unknown; generic_arity(name)])
+        }
+        _ => return None,
+    })
+}
+
+/// A method of a list, set or dict, with the element types of the container filled in so the
+/// methods that add to it only accept the right elements.
+pub fn container_method(container: &Type, name: &str) -> Option<Function> {
+    let Type::Generic(class, args) = container else {
+        return None;
+    };
+    let generic = |name: &str, args: &[&Type]| {
+        Type::Generic(Symbol::new(name), args.iter().map(|&a| a.clone()).collect())
+    };
+    let method = match (generic_name(class)?, args.as_slice()) {
+        ("list", [element]) => match name {
+            "append" => function(&[("object", element.clone(), None)], Type::None),
+            "extend" => function(
+                &[("iterable", generic("Iterable", &[element]), None)],
+                Type::None,
+            ),
+            "insert" => function(
+                &[
+                    ("index", Type::Int, None),
+                    ("object", element.clone(), None),
+                ],
+                Type::None,
+            ),
+            "remove" => function(&[("value", element.clone(), None)], Type::None),
+            "pop" => function(&[("index", Type::Int, Some("-1"))], element.clone()),
+            "index" | "count" => function(&[("value", element.clone(), None)], Type::Int),
+            "clear" | "reverse" => function(&[], Type::None),
+            "copy" => function(&[], container.clone()),
+            "sort" => function(&[], Type::None).with_keyword_only(keyword_only(&[
+                ("key", Type::Any, "None"),
+                ("reverse", Type::Bool, "False"),
+            ])),
+            _ => return None,
+        },
+        ("set", [element]) => match name {
+            "add" | "discard" | "remove" => {
+                function(&[("element", element.clone(), None)], Type::None)
+            }
+            "update" => function(&[], Type::None)
+                .with_variadic(Symbol::new("s"), generic("Iterable", &[element])),
+            "pop" => function(&[], element.clone()),
+            "clear" => function(&[], Type::None),
+            "copy" => function(&[], container.clone()),
+            _ => return None,
+        },
+        ("dict", [key, value]) => match name {
+            "update" => function(
+                &[("m", generic("Mapping", &[key, value]), Some("{}"))],
+                Type::None,
+            )
+            .with_keywords(Symbol::new("kwargs"), value.clone()),
+            // What these give back with a default is worked out by refine_method
+            "setdefault" => function(
+                &[
+                    ("key", key.clone(), None),
+                    ("default", value.clone(), Some("None")),
+                ],
+                union(vec![value.clone(), Type::None]),
+            ),
+            "get" => function(
+                &[
+                    ("key", key.clone(), None),
+                    ("default", Type::Any, Some("None")),
+                ],
+                union(vec![value.clone(), Type::None]),
+            ),
+            "pop" => function(
+                &[
+                    ("key", key.clone(), None),
+                    ("default", Type::Any, Some("None")),
+                ],
+                value.clone(),
+            ),
+            "popitem" => function(&[], Type::Tuple(vec![key.clone(), value.clone()])),
+            "clear" => function(&[], Type::None),
+            "copy" => function(&[], container.clone()),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// A more precise return type for a call to a method of a builtin container, based on the types of
+/// the arguments it was called with.
+pub fn refine_method(container: &Type, name: &str, arg_types: &[Type]) -> Option<Type> {
+    let Type::Generic(class, args) = container else {
+        return None;
+    };
+    match (generic_name(class)?, args.as_slice(), name, arg_types) {
+        // The default is given back instead of None when the key is missing
+        ("dict", [_, value], "get" | "pop" | "setdefault", [_, default]) => {
+            Some(union(vec![value.clone(), default.clone()]))
+        }
+        _ => None,
+    }
+}
+
+/// A more precise return type for a call to a standard library function, based on the types of
+/// the arguments it was called with.
+pub fn refine_call(module: &str, name: &str, arg_types: &[Type]) -> Option<Type> {
+    match (module, name, arg_types) {
+        // A str default means the result is never None
+        ("os", "getenv", [_, default]) if is_subtype(default, &Type::String) => Some(Type::String),
+        _ => None,
+    }
+}
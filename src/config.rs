@@ -0,0 +1,124 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use crate::{sql::SqlValidator, types::UnionStyle};
+
+/// What kind of file is being checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Language {
+    Python,
+    /// Python stubs
+    Pyi,
+    /// Jinja templates
+    Jinja,
+}
+
+impl Language {
+    /// The language of a file from its extension, extensionless files are Python if they start
+    /// with a python shebang.
+    pub fn detect(path: &Path, content: &str) -> Option<Language> {
+        let Some(extension) = path.extension() else {
+            let shebang = content.lines().next().filter(|l| l.starts_with("#!"));
+            return shebang
+                .is_some_and(|l| l.contains("python"))
+                .then_some(Language::Python);
+        };
+        match extension.to_str()? {
+            "py" => Some(Language::Python),
+            "pyi" => Some(Language::Pyi),
+            "jinja" | "jinja2" | "j2" => Some(Language::Jinja),
+            _ => None,
+        }
+    }
+}
+
+/// Diagnostics to ignore in the files matching a glob, so stricter settings can be turned on for a
+/// project one part at a time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathRule {
+    /// Glob matched against the path of the file from the working directory, like the exclude
+    /// globs
+    pub glob: String,
+    /// Codes of the diagnostics to ignore, every diagnostic is ignored if there are none
+    pub codes: Vec<String>,
+}
+
+/// Parses `GLOB` to ignore everything or `GLOB:CODE,CODE` to ignore only those codes.
+impl FromStr for PathRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<PathRule, String> {
+        let (glob, codes) = match rule.rsplit_once(':') {
+            Some((glob, codes)) => (glob, codes.split(',').map(str::trim).collect()),
+            None => (rule, vec![]),
+        };
+        if glob.trim().is_empty() {
+            return Err(format!("\"{}\" has no glob before the codes", rule));
+        }
+        if let Some(code) = codes.iter().find(|code| !is_code(code)) {
+            return Err(format!("\"{}\" is not a diagnostic code like PCV001", code));
+        }
+        Ok(PathRule {
+            glob: glob.trim().to_owned(),
+            codes: codes.into_iter().map(str::to_owned).collect(),
+        })
+    }
+}
+
+fn is_code(code: &str) -> bool {
+    code.strip_prefix("PCV")
+        .is_some_and(|n| n.len() == 3 && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Settings that change how files are checked.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Files larger than this many bytes are skipped with a FileTooLargeDiag.
+    pub max_file_size: Option<usize>,
+    /// How unions are displayed in diagnostics
+    pub union_style: UnionStyle,
+    /// Warn about unannotated function parameters and return types
+    pub strict: bool,
+    /// Functions with one of these decorators are exempt from strict mode
+    pub strict_exempt_decorators: Vec<String>,
+    /// Warn when imported names or module level constants are reassigned
+    pub warn_reassign: bool,
+    /// Warn about function parameters that are never used
+    pub warn_unused_parameters: bool,
+    /// Warn about branches that are never run because their condition is constant
+    pub warn_unreachable: bool,
+    /// Checks for the SQL queries passed to `execute` calls
+    pub sql_validators: Vec<Arc<dyn SqlValidator>>,
+    /// Directories with `.pyi` stubs, searched before the bundled standard library stubs
+    pub stub_paths: Vec<PathBuf>,
+    /// Check every file as this language instead of going by the file extension
+    pub language: Option<Language>,
+    /// Skip files ignored by git when searching directories
+    pub respect_gitignore: bool,
+    /// Globs of files and directories to skip when searching directories, matched like the lines
+    /// of a `.gitignore` in the searched directory
+    pub exclude: Vec<String>,
+    /// Record the type of every expression for `type_map`
+    pub record_types: bool,
+    /// Diagnostics to ignore in some of the files, when more than one rule matches a file all of
+    /// them apply
+    pub path_rules: Vec<PathRule>,
+}
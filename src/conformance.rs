@@ -0,0 +1,226 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Running the typing conformance test suite, where each file marks the lines a checker has to
+//! report an error on with `# E` comments, and files are named `<area>_<name>.py` after the part of
+//! the typing spec they test.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{corpus::panic_message, error_check_bytes_with_config, Config, DiagnosticType};
+
+/// Whether the checker agreed with the `# E` comments of a file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConformanceOutcome {
+    Passed,
+    /// The 1 based lines with a required error that wasn't reported, and the lines with an error
+    /// that isn't expected.
+    Failed {
+        missing: Vec<usize>,
+        unexpected: Vec<usize>,
+    },
+    /// The file couldn't be checked, or the checker crashed on it.
+    Broken(String),
+}
+
+/// The outcome of every file in the suite, by file name.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConformanceReport {
+    pub files: BTreeMap<String, ConformanceOutcome>,
+}
+
+/// The errors a file expects, `# E` is required, `# E?` is allowed but optional and any one line of
+/// the lines marked `# E[tag]` has to have an error.
+#[derive(Debug, Default)]
+struct Expected {
+    required: BTreeSet<usize>,
+    optional: BTreeSet<usize>,
+    groups: BTreeMap<String, BTreeSet<usize>>,
+}
+
+fn parse_expected(content: &str) -> Expected {
+    let mut expected = Expected::default();
+    for (i, text) in content.lines().enumerate() {
+        let line = i + 1;
+        let Some(comment) = text.split('#').skip(1).map(str::trim).find(|c| {
+            c.strip_prefix('E')
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', ':', '?', '[']))
+        }) else {
+            continue;
+        };
+        let rest = &comment[1..];
+        if let Some((tag, _)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+            expected
+                .groups
+                .entry(tag.to_owned())
+                .or_default()
+                .insert(line);
+        } else if rest.starts_with('?') {
+            expected.optional.insert(line);
+        } else {
+            expected.required.insert(line);
+        }
+    }
+    expected
+}
+
+/// Check one conformance file against its `# E` comments.
+pub fn check_conformance_file(path: &Path, config: Arc<Config>) -> io::Result<ConformanceOutcome> {
+    let bytes = fs::read(path)?;
+    let checked = catch_unwind(AssertUnwindSafe(|| {
+        error_check_bytes_with_config(path.to_owned(), bytes, config)
+    }));
+    let info = match checked {
+        Ok(Ok(info)) => info,
+        Ok(Err(_)) => return Ok(ConformanceOutcome::Broken("failed to check".to_owned())),
+        Err(panic) => return Ok(ConformanceOutcome::Broken(panic_message(panic))),
+    };
+
+    let content = info.file_content.as_str();
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    // Notes like reveal_type and warnings aren't what the `# E` comments mark
+    let reported: BTreeSet<usize> = info
+        .reporter
+        .errors()
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|error| error.typ() == DiagnosticType::Error)
+        .map(|error| line_starts.partition_point(|&s| s <= error.range().start().to_usize()))
+        .collect();
+
+    let expected = parse_expected(content);
+    let mut missing: Vec<usize> = expected.required.difference(&reported).copied().collect();
+    for lines in expected.groups.values() {
+        if lines.is_disjoint(&reported) {
+            missing.extend(lines.iter().copied());
+        }
+    }
+    missing.sort();
+    let unexpected: Vec<usize> = reported
+        .iter()
+        .filter(|line| {
+            !expected.required.contains(line)
+                && !expected.optional.contains(line)
+                && !expected.groups.values().any(|g| g.contains(line))
+        })
+        .copied()
+        .collect();
+
+    Ok(if missing.is_empty() && unexpected.is_empty() {
+        ConformanceOutcome::Passed
+    } else {
+        ConformanceOutcome::Failed {
+            missing,
+            unexpected,
+        }
+    })
+}
+
+/// Check every Python file directly in the directory.
+pub fn check_conformance(dir: &Path, config: Arc<Config>) -> io::Result<ConformanceReport> {
+    let mut report = ConformanceReport::default();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "py") {
+            continue;
+        }
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let outcome = check_conformance_file(&path, config.clone())?;
+        report.files.insert(name, outcome);
+    }
+    Ok(report)
+}
+
+impl ConformanceReport {
+    /// The number of passed and total files in each feature area.
+    pub fn areas(&self) -> BTreeMap<&str, (usize, usize)> {
+        let mut areas = BTreeMap::new();
+        for (file, outcome) in &self.files {
+            let stem = file.trim_end_matches(".py");
+            let area = stem.split_once('_').map_or(stem, |(area, _)| area);
+            let (passed, total) = areas.entry(area).or_insert((0, 0));
+            *passed += (*outcome == ConformanceOutcome::Passed) as usize;
+            *total += 1;
+        }
+        areas
+    }
+
+    pub fn passed(&self) -> usize {
+        self.files
+            .values()
+            .filter(|outcome| **outcome == ConformanceOutcome::Passed)
+            .count()
+    }
+}
+
+fn join_lines(lines: &[usize]) -> String {
+    lines
+        .iter()
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A pass/fail matrix of the feature areas followed by the reason each failing file failed.
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let areas = self.areas();
+        let width = areas.keys().map(|a| a.len()).max().unwrap_or(0).max(5);
+        writeln!(f, "{:<width$}  passed  total", "area")?;
+        for (area, (passed, total)) in &areas {
+            writeln!(f, "{:<width$}  {:>6}  {:>5}", area, passed, total)?;
+        }
+        writeln!(
+            f,
+            "{:<width$}  {:>6}  {:>5}",
+            "total",
+            self.passed(),
+            self.files.len()
+        )?;
+
+        for (file, outcome) in &self.files {
+            match outcome {
+                ConformanceOutcome::Passed => {}
+                ConformanceOutcome::Broken(reason) => writeln!(f, "{}: {}", file, reason)?,
+                ConformanceOutcome::Failed {
+                    missing,
+                    unexpected,
+                } => {
+                    let mut reasons = vec![];
+                    if !missing.is_empty() {
+                        reasons.push(format!("missing errors on lines {}", join_lines(missing)));
+                    }
+                    if !unexpected.is_empty() {
+                        reasons.push(format!(
+                            "unexpected errors on lines {}",
+                            join_lines(unexpected)
+                        ));
+                    }
+                    writeln!(f, "{}: {}", file, reasons.join(", "))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Running the checker over a corpus of real world projects and comparing the results against a
+//! stored snapshot, to see how a change affects the diagnostics on code it wasn't written for.
+
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    fmt, fs, io,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{error_check_bytes_with_config, project::python_files, Config};
+
+/// What happened when checking a single file of the corpus.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FileOutcome {
+    /// Number of diagnostics reported for each code, diagnostics without a code are under `-`.
+    Checked(BTreeMap<String, usize>),
+    /// The file couldn't be checked.
+    Failed,
+    /// The checker panicked, with the panic message.
+    Crashed(String),
+}
+
+/// The outcome of every file in a corpus, by path relative to the corpus directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CorpusReport {
+    pub files: BTreeMap<String, FileOutcome>,
+}
+
+impl FileOutcome {
+    /// The outcome as it is stored in a snapshot, panic messages often contain addresses or line
+    /// numbers of the checker itself, so only the fact that it crashed is kept.
+    fn summary(&self) -> String {
+        match self {
+            FileOutcome::Checked(counts) if counts.is_empty() => "ok".to_owned(),
+            FileOutcome::Checked(counts) => counts
+                .iter()
+                .map(|(code, count)| format!("{}={}", code, count))
+                .collect::<Vec<_>>()
+                .join(" "),
+            FileOutcome::Failed => "failed".to_owned(),
+            FileOutcome::Crashed(_) => "crashed".to_owned(),
+        }
+    }
+
+    fn parse(summary: &str) -> Option<FileOutcome> {
+        Some(match summary {
+            "ok" => FileOutcome::Checked(BTreeMap::new()),
+            "failed" => FileOutcome::Failed,
+            "crashed" => FileOutcome::Crashed(String::new()),
+            counts => FileOutcome::Checked(
+                counts
+                    .split(' ')
+                    .map(|count| {
+                        let (code, count) = count.split_once('=')?;
+                        Some((code.to_owned(), count.parse().ok()?))
+                    })
+                    .collect::<Option<_>>()?,
+            ),
+        })
+    }
+}
+
+impl CorpusReport {
+    /// Read a report back from its snapshot format, returns None if a line is malformed.
+    pub fn parse(snapshot: &str) -> Option<CorpusReport> {
+        let files = snapshot
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let (file, summary) = line.rsplit_once(": ")?;
+                Some((file.to_owned(), FileOutcome::parse(summary)?))
+            })
+            .collect::<Option<_>>()?;
+        Some(CorpusReport { files })
+    }
+
+    pub fn crashes(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.files
+            .iter()
+            .filter_map(|(file, outcome)| match outcome {
+                FileOutcome::Crashed(message) => Some((file, message)),
+                _ => None,
+            })
+    }
+
+    pub fn diagnostic_count(&self) -> usize {
+        self.files
+            .values()
+            .map(|outcome| match outcome {
+                FileOutcome::Checked(counts) => counts.values().sum(),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Every file whose outcome differs from the snapshot, as a line describing the change.
+    pub fn diff(&self, snapshot: &CorpusReport) -> Vec<String> {
+        let mut changes = vec![];
+        for (file, outcome) in &snapshot.files {
+            match self.files.get(file) {
+                None => changes.push(format!("- {}: {}", file, outcome.summary())),
+                Some(new) if new.summary() != outcome.summary() => changes.push(format!(
+                    "~ {}: {} -> {}",
+                    file,
+                    outcome.summary(),
+                    new.summary()
+                )),
+                Some(_) => {}
+            }
+        }
+        for (file, outcome) in &self.files {
+            if !snapshot.files.contains_key(file) {
+                changes.push(format!("+ {}: {}", file, outcome.summary()));
+            }
+        }
+        changes
+    }
+}
+
+/// The snapshot format, one `path: outcome` line per file sorted by path.
+impl fmt::Display for CorpusReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (file, outcome) in &self.files {
+            writeln!(f, "{}: {}", file, outcome.summary())?;
+        }
+        Ok(())
+    }
+}
+
+/// Check a single file, catching any panic in the checker.
+pub fn check_corpus_file(path: &Path, config: Arc<Config>) -> io::Result<FileOutcome> {
+    let bytes = fs::read(path)?;
+    let checked = catch_unwind(AssertUnwindSafe(|| {
+        error_check_bytes_with_config(path.to_owned(), bytes, config)
+    }));
+    Ok(match checked {
+        Ok(Ok(info)) => {
+            let mut counts = BTreeMap::new();
+            for error in info.reporter.errors().lock().unwrap().iter() {
+                *counts
+                    .entry(error.code().unwrap_or("-").to_owned())
+                    .or_insert(0) += 1;
+            }
+            FileOutcome::Checked(counts)
+        }
+        Ok(Err(_)) => FileOutcome::Failed,
+        Err(panic) => FileOutcome::Crashed(panic_message(panic)),
+    })
+}
+
+/// The message a panic was started with.
+pub(crate) fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_owned())
+}
+
+/// Check every Python file under the directory, skipping hidden files and directories.
+pub fn check_corpus(dir: &Path, config: Arc<Config>) -> io::Result<CorpusReport> {
+    let mut report = CorpusReport::default();
+    for path in python_files(&[dir.to_owned()], &config)? {
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        let name = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let outcome = check_corpus_file(&path, config.clone())?;
+        report.files.insert(name, outcome);
+    }
+    Ok(report)
+}
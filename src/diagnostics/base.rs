@@ -14,10 +14,9 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use core::fmt;
-use std::{borrow::Borrow, fmt::Debug, io, ops::Range, path::Path};
+use std::{borrow::Borrow, fmt::Debug, io, io::Write, ops::Range, path::Path};
 
 use ariadne::{Color, Config, Label, Report, ReportKind, Source};
-use clio::Output;
 use ruff_text_size::TextRange;
 
 use super::{dyn_compare::DynCompare, macros};
@@ -40,14 +39,48 @@ pub fn type_to_kind(diagnostic_type: &DiagnosticType) -> ReportKind<'static> {
 
 pub type DiagReport<'a> = Report<'a, (&'a str, std::ops::Range<usize>)>;
 
+/// Page containing an explanation for every diagnostic code.
+pub const DIAGNOSTICS_DOCS_URL: &str =
+    "https://github.com/hrolfurgylfa/pycavalry/blob/main/docs/diagnostics.md";
+
 pub trait Diag: DynCompare + Debug {
     fn print<'a>(&'a self, file_name: &'a str) -> DiagReport<'a>;
 
-    fn write(&self, f: &mut Output, file_name: &Path, file: &str) -> io::Result<()> {
+    /// Where in the file the diagnostic points to.
+    fn range(&self) -> TextRange;
+    /// The main message of the diagnostic, without any colors.
+    fn message(&self) -> String;
+    /// How severe the diagnostic is.
+    fn typ(&self) -> DiagnosticType;
+
+    /// The stable code identifying this kind of diagnostic, such as PCV001.
+    fn code(&self) -> Option<&'static str> {
+        None
+    }
+    /// Link to the explanation of this diagnostic's code.
+    fn url(&self) -> Option<String> {
+        self.code()
+            .map(|code| format!("{}#{}", DIAGNOSTICS_DOCS_URL, code.to_lowercase()))
+    }
+
+    /// Extra context explaining why the diagnostic was reported.
+    fn note(&self) -> Option<String> {
+        None
+    }
+    /// A suggestion for how to fix the diagnostic.
+    fn help(&self) -> Option<String> {
+        None
+    }
+
+    fn write(&self, f: &mut dyn Write, file_name: &Path, file: &str) -> io::Result<()> {
         let file_name_cow = file_name.to_string_lossy();
         let file_name: &str = file_name_cow.borrow();
         self.print(file_name)
-            .write((file_name, Source::from(file)), f)
+            .write((file_name, Source::from(file)), &mut *f)?;
+        if let Some(url) = self.url() {
+            writeln!(f, "For more information see {}", url)?;
+        }
+        Ok(())
     }
 }
 
@@ -57,7 +90,7 @@ impl PartialEq<dyn Diag> for dyn Diag {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DiagnosticType {
     Info,
     Warning,
@@ -74,16 +107,52 @@ impl fmt::Display for DiagnosticType {
     }
 }
 
+/// What is known about a kind of diagnostic without having one, registered by
+/// `custom_diagnostic!` as the `INFO` constant of each diagnostic. The explanation is what
+/// `pycavalry explain` prints and what docs/diagnostics.md is generated from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiagnosticInfo {
+    pub code: &'static str,
+    /// Name of the struct implementing the diagnostic
+    pub name: &'static str,
+    pub typ: DiagnosticType,
+    /// One line summary of the diagnostic
+    pub title: &'static str,
+    /// When the diagnostic is reported
+    pub description: &'static str,
+    /// Lines of Python code that get the diagnostic, empty if there is no example
+    pub example: &'static [&'static str],
+    /// How to get rid of the diagnostic
+    pub fix: Option<&'static str>,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Diagnostic {
     body: String,
     typ: DiagnosticType,
     range: TextRange,
+    note: Option<String>,
+    help: Option<String>,
 }
 
 impl Diagnostic {
     pub fn new(body: String, typ: DiagnosticType, range: TextRange) -> Diagnostic {
-        Diagnostic { body, typ, range }
+        Diagnostic {
+            body,
+            typ,
+            range,
+            note: None,
+            help: None,
+        }
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Diagnostic {
+        self.note = Some(note.into());
+        self
+    }
+    pub fn with_help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help = Some(help.into());
+        self
     }
 
     pub fn error(body: String, range: TextRange) -> Diagnostic {
@@ -107,13 +176,39 @@ impl Diag for Diagnostic {
     fn print<'a>(&'a self, file_name: &'a str) -> DiagReport<'a> {
         let main_color = type_to_color(&self.typ);
         let kind = type_to_kind(&self.typ);
-        Report::build(kind, file_name, self.range.start().to_usize())
+        let mut report = Report::build(kind, file_name, self.range.start().to_usize())
             .with_label(
                 Label::new((file_name, convert_range(self.range)))
                     .with_message(&self.body)
                     .with_color(main_color),
             )
-            .with_config(Config::default().with_compact(false))
-            .finish()
+            .with_config(Config::default().with_compact(false));
+        if let Some(note) = self.note() {
+            report.set_note(note);
+        }
+        if let Some(help) = self.help() {
+            report.set_help(help);
+        }
+        report.finish()
+    }
+
+    fn range(&self) -> TextRange {
+        self.range
+    }
+
+    fn message(&self) -> String {
+        self.body.clone()
+    }
+
+    fn typ(&self) -> DiagnosticType {
+        self.typ
+    }
+
+    fn note(&self) -> Option<String> {
+        self.note.clone()
+    }
+
+    fn help(&self) -> Option<String> {
+        self.help.clone()
     }
 }
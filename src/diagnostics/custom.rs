@@ -16,34 +16,723 @@
 use std::sync::Arc;
 
 use ariadne::{Fmt, Label, Report};
+use ruff_python_ast::{Operator, UnaryOp};
 use ruff_text_size::TextRange;
 
 use super::macros;
 use crate::{
-    diagnostics::{convert_range, Diag, DiagReport, DiagnosticType},
-    types::Type,
+    config::Language,
+    diagnostics::{convert_range, Diag, DiagReport, DiagnosticInfo, DiagnosticType},
+    symbol::Symbol,
+    types::{is_subtype, with_qualified_collisions, Type},
 };
 
 macros::custom_diagnostic!(
-    (RevealTypeDiag, self, DiagnosticType::Info),
+    (RevealTypeDiag, "PCV005", self, DiagnosticType::Info),
     (typ: Type),
-    |s: &RevealTypeDiag, c| format!("Type is {}", (&s.typ).fg(c))
+    |s: &RevealTypeDiag, c| format!("Type is {}", (&s.typ).fg(c)),
+    explanation: {
+        title: "Revealed type",
+        description: "Informational diagnostic emitted by `reveal_type(...)`, showing the type \
+            pycavalry inferred for the expression. `reveal_type` works without an \
+            import, and can also be imported from `typing` or `pycavalry`.",
+        fix: "Remove the `reveal_type` call once the type has been checked.",
+    }
 );
 
 macros::custom_diagnostic!(
-    (NotInScopeDiag, self, DiagnosticType::Error),
-    (name: Arc<String>),
-    |s: &NotInScopeDiag, _| format!("Name \"{}\" not found in scope.", &s.name)
+    (NotInScopeDiag, "PCV001", self, DiagnosticType::Error),
+    (name: Symbol),
+    |s: &NotInScopeDiag, _| format!("Name \"{}\" not found in scope.", &s.name),
+    explanation: {
+        title: "Name not found in scope",
+        description: "A name is used that hasn't been defined, imported or declared anywhere \
+            pycavalry can see.",
+        example: [
+            "print(undefined_name)",
+        ],
+        fix: "Define or import the name before using it.",
+    }
 );
 
 macros::custom_diagnostic!(
-    (ExpectedButGotDiag, self, DiagnosticType::Error),
+    (NotInModuleDiag, "PCV002", self, DiagnosticType::Error),
+    (module: Symbol, name: Symbol, available: Vec<Symbol>),
+    |s: &NotInModuleDiag, _| format!("Name \"{}\" not found in module \"{}\".", &s.name, &s.module),
+    note: |s: &NotInModuleDiag| s.available.is_empty().then(|| {
+        format!("Module \"{}\" could not be resolved, so none of its names are known.", &s.module)
+    }),
+    help: |s: &NotInModuleDiag| (!s.available.is_empty()).then(|| {
+        let names: Vec<&str> = s.available.iter().map(|n| n.as_str()).collect();
+        format!("Available names are: {}", names.join(", "))
+    }),
+    explanation: {
+        title: "Name not found in module",
+        description: "A `from module import name` statement imports a name the module doesn't \
+            export. If the module itself couldn't be resolved, none of its names are \
+            known.",
+        example: [
+            "from sys import not_a_real_member",
+        ],
+        fix: "Import a name the module defines, or check that the right module is found.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (ExpectedButGotDiag, "PCV003", self, DiagnosticType::Error),
     (expected: Type, got: Type),
-    |s: &ExpectedButGotDiag, _| format!("Expected {} but found {}.", s.expected, s.got)
+    |s: &ExpectedButGotDiag, _| with_qualified_collisions(&[&s.expected, &s.got], || {
+        format!("Expected {} but found {}.", s.expected, s.got)
+    }),
+    note: |s: &ExpectedButGotDiag| match (&s.expected, &s.got) {
+        (Type::Function(_), Type::Function(_)) => Some(
+            "Function arguments are contravariant, the found function must accept every \
+            argument type the expected function accepts, while its return type must be a \
+            subtype of the expected return type.",
+        ),
+        _ => None,
+    },
+    help: |s: &ExpectedButGotDiag| match &s.got {
+        Type::Union(types) if types.iter().any(Type::is_none) && !is_subtype(&Type::None, &s.expected) => {
+            Some("The found type includes None, check that the value is not None before using it.")
+        }
+        _ => None,
+    },
+    explanation: {
+        title: "Expected a type but found another",
+        description: "A value is used where its type isn't compatible with the expected type, for \
+            example when assigning to an annotated variable, passing a function argument \
+            or returning from a function.",
+        example: [
+            "a: int = \"not an int\"",
+        ],
+        fix: "Change the value or widen the expected type.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (CantReassignLockedDiag, "PCV004", self, DiagnosticType::Error),
+    (expected: Type, got: Type, name: Symbol, declared_at: Option<TextRange>),
+    |s: &CantReassignLockedDiag, _| with_qualified_collisions(&[&s.expected, &s.got], || {
+        format!("Can't redeclare \"{}\" as {}", &s.name, s.got)
+    }),
+    labels: |s: &CantReassignLockedDiag| s.declared_at.map(|range| {
+        with_qualified_collisions(&[&s.expected, &s.got], || {
+            (range, format!("\"{}\" was declared as {} here", &s.name, s.expected))
+        })
+    }),
+    note: |_| Some("A type annotation fixes the type of a variable for the rest of its scope."),
+    explanation: {
+        title: "Can't redeclare a variable with a type annotation",
+        description: "Once a variable has been declared with a type annotation, that type is fixed \
+            for the rest of its scope, even if the new annotation is the same type.",
+        example: [
+            "a: int = 1",
+            "a: str = \"a\"",
+        ],
+        fix: "Use a different variable name, or assign without an annotation.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (UnsupportedSyntaxDiag, "PCV006", self, DiagnosticType::Warning),
+    (syntax: &'static str),
+    |s: &UnsupportedSyntaxDiag, _| format!("{} is not supported yet.", s.syntax),
+    note: |_| Some("pycavalry treats it as Unknown and continues checking the rest of the file."),
+    explanation: {
+        title: "Syntax not supported yet",
+        description: "pycavalry is still missing support for parts of Python. When it runs into \
+            one of them it reports this warning, treats the result as `Unknown` and \
+            keeps checking the rest of the file, so other diagnostics might be missed in \
+            that part of the code.",
+        fix: "Rewrite that part with supported syntax if it should be checked.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (FileTooLargeDiag, "PCV007", self, DiagnosticType::Warning),
+    (size: usize, limit: usize),
+    |s: &FileTooLargeDiag, _| format!(
+        "File skipped, it is {} bytes which is over the limit of {} bytes.",
+        s.size, s.limit
+    ),
+    help: |_| Some("Raise the limit with --max-file-size if this file should be checked."),
+    explanation: {
+        title: "File too large",
+        description: "The file is bigger than the configured `--max-file-size`, so it was \
+            skipped without being parsed. This keeps huge generated files from using up all \
+            the memory on the machine.",
+        fix: "Raise the limit with `--max-file-size` if the file should be checked.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (ModuleAttributeAssignDiag, "PCV008", self, DiagnosticType::Warning),
+    (module: Symbol, name: Symbol, exists: bool),
+    |s: &ModuleAttributeAssignDiag, _| format!(
+        "Assigning to \"{}\" on module \"{}\" is not tracked.",
+        &s.name, &s.module
+    ),
+    note: |_| Some("Modules are treated as fixed, reading the attribute later still gives its original type."),
+    help: |s: &ModuleAttributeAssignDiag| (!s.exists).then(|| format!(
+        "Define \"{}\" in \"{}\", or give the module a __getattr__ to allow dynamic attributes.",
+        &s.name, &s.module
+    )),
+    explanation: {
+        title: "Module attribute assignment is not tracked",
+        description: "pycavalry treats modules as fixed. Assigning to an attribute of a module, \
+            like `settings.DEBUG = True`, is checked against the attribute's type when \
+            the module defines it, but later reads still see the original type. \
+            Assigning to an attribute the module doesn't define is only allowed without \
+            this warning when the module has a `__getattr__`, which marks its attributes \
+            as dynamic.",
+        fix: "Assign a value of the declared type, or define the attribute in the module.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (DynamicGlobalDiag, "PCV009", self, DiagnosticType::Warning),
+    (),
+    |_: &DynamicGlobalDiag, _| "Names added through globals() are not tracked.".to_owned(),
+    help: |_| Some("Assign the name directly so its type is known."),
+    explanation: {
+        title: "Dynamic global",
+        description: "Names created with `globals()[\"name\"] = value` can't be seen by \
+            pycavalry, so uses of them will be reported as not found.",
+        fix: "Assign the name directly instead.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (UntypedDefDiag, "PCV010", self, DiagnosticType::Warning),
+    (function: Symbol, parameter: Option<Symbol>),
+    |s: &UntypedDefDiag, _| match &s.parameter {
+        Some(parameter) => format!(
+            "Parameter \"{}\" of \"{}\" is missing a type annotation.",
+            parameter, &s.function
+        ),
+        None => format!("\"{}\" is missing a return type annotation.", &s.function),
+    },
+    note: |_| Some("Unannotated parameters and return types are Unknown, so they aren't checked."),
+    explanation: {
+        title: "Missing annotation in strict mode",
+        description: "With `--strict`, every function parameter and return type needs an \
+            annotation, like mypy's `--disallow-untyped-defs`. Without one the type is \
+            `Unknown` and nothing about it is checked. A file can opt out with a `# \
+            pycavalry: no-strict` line, and functions with a decorator given to \
+            `--strict-exempt-decorator` are skipped.",
+        fix: "Add the missing annotation, or opt the file or function out.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (ReassignDiag, "PCV011", self, DiagnosticType::Warning),
+    (name: Symbol, imported_from: Option<Symbol>, defined_at: Option<TextRange>),
+    |s: &ReassignDiag, _| match &s.imported_from {
+        Some(module) => format!("Reassigning \"{}\" which was imported from \"{}\".", &s.name, module),
+        None => format!("Reassigning the constant \"{}\".", &s.name),
+    },
+    labels: |s: &ReassignDiag| s.defined_at.map(|range| {
+        (range, format!("\"{}\" was first bound here", &s.name))
+    }),
+    note: |s: &ReassignDiag| s.imported_from.as_ref().map(|module| format!(
+        "This only changes the name in this module, \"{}\" still has the original value.",
+        module
+    )),
+    explanation: {
+        title: "Reassigned import or constant",
+        description: "Only reported with `--warn-reassign`. A name imported from another \
+            module, or a module level name written in UPPER_CASE, is assigned a new value. \
+            Rebinding an imported name doesn't change it in the module it came from, and \
+            constants are expected to keep their value.",
+        fix: "Pick a new name for the new value.",
+    }
 );
 
 macros::custom_diagnostic!(
-    (CantReassignLockedDiag, self, DiagnosticType::Error),
-    (expected: Type, got: Type, name: Arc<String>),
-    |s: &CantReassignLockedDiag, _| format!("\"{0}\" is already defined as {1}, can't redefine as {2} as it was previously defined with a type hint, so it can't be redefined as a different type.", &s.name, s.expected, s.got)
+    (EncodingDiag, "PCV012", self, DiagnosticType::Warning),
+    (encoding: Arc<String>, supported: bool),
+    |s: &EncodingDiag, _| if s.supported {
+        format!("File isn't valid {}, the invalid bytes were replaced.", &s.encoding)
+    } else {
+        format!("Encoding \"{}\" is not supported, the file was read as UTF-8.", &s.encoding)
+    },
+    help: |s: &EncodingDiag| (!s.supported).then_some("Save the file as UTF-8 instead."),
+    explanation: {
+        title: "File encoding problem",
+        description: "Files are read as UTF-8, or as Latin-1 when they start with a `# -*- coding: \
+            latin-1 -*-` declaration. A UTF-8 byte order mark is skipped. When the file \
+            has bytes that aren't valid in its encoding, or declares an encoding \
+            pycavalry doesn't support, the unreadable bytes are replaced and the rest of \
+            the file is still checked. Positions in other diagnostics might be off \
+            around the replaced characters.",
+        fix: "Save the file as UTF-8, or fix its encoding declaration.",
+    }
 );
+
+macros::custom_diagnostic!(
+    (SqlPlaceholderCountDiag, "PCV013", self, DiagnosticType::Error),
+    (placeholders: usize, parameters: usize),
+    |s: &SqlPlaceholderCountDiag, _| format!(
+        "The query has {} placeholders but {} parameters were given.",
+        s.placeholders, s.parameters
+    ),
+    explanation: {
+        title: "Wrong number of SQL parameters",
+        description: "Only reported with `--check-sql`. The literal query passed to `execute` has \
+            a different number of `?` or `%s` placeholders than the tuple of parameters \
+            passed with it.",
+        example: [
+            "db.execute(\"SELECT * FROM users WHERE id = ? AND name = ?\", (1,))",
+        ],
+        fix: "Pass one parameter for every placeholder in the query.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (SqlParameterTypeDiag, "PCV014", self, DiagnosticType::Error),
+    (typ: Type),
+    |s: &SqlParameterTypeDiag, _| format!("{} can't be used as a query parameter.", &s.typ),
+    note: |_| Some("Query parameters have to be str, int, float, bool or None."),
+    explanation: {
+        title: "Unsupported SQL parameter type",
+        description: "Only reported with `--check-sql`. A parameter passed with a query isn't one \
+            of the types database drivers can bind, `str`, `int`, `float`, `bool` or \
+            `None`.",
+        fix: "Convert the value to one of those types before passing it.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (UnusedParameterDiag, "PCV015", self, DiagnosticType::Warning),
+    (name: Symbol, function: Symbol),
+    |s: &UnusedParameterDiag, _| format!(
+        "Parameter \"{}\" of \"{}\" is never used.",
+        &s.name, &s.function
+    ),
+    help: |s: &UnusedParameterDiag| Some(format!(
+        "Remove it, or rename it to \"_{}\" if it has to stay.",
+        &s.name
+    )),
+    explanation: {
+        title: "Unused parameter",
+        description: "Only reported with `--warn-unused-parameters`. The value of a function \
+            parameter is never read in the function body. Parameters named `self` or \
+            `cls`, and ones starting with an underscore, are never reported.",
+        fix: "Remove the parameter, or prefix its name with an underscore.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (DuplicateDefinitionDiag, "PCV016", self, DiagnosticType::Error),
+    (kind: &'static str, name: Symbol, first: TextRange),
+    |s: &DuplicateDefinitionDiag, _| format!("Duplicate {} \"{}\".", s.kind, &s.name),
+    labels: |s: &DuplicateDefinitionDiag| Some((s.first, format!("\"{}\" is first defined here", &s.name))),
+    note: |_| Some("Only the last definition is kept, the earlier ones are overwritten."),
+    explanation: {
+        title: "Duplicate definition",
+        description: "A class body defines the same method or annotated field twice, or a \
+            dict display has the same literal key twice. Python silently keeps only the \
+            last one, which is usually a copy-paste mistake. Overloads and property setters \
+            and deleters are not counted as duplicates.",
+        example: [
+            "class User:",
+            "    name: str",
+            "    name: int",
+        ],
+        fix: "Remove or rename one of the definitions.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (MutableDefaultDiag, "PCV017", self, DiagnosticType::Warning),
+    (kind: &'static str),
+    |s: &MutableDefaultDiag, _| format!("Mutable {} used as a default value.", s.kind),
+    note: |_| Some("Default values are created once, so changes to it are seen by every later call."),
+    help: |s: &MutableDefaultDiag| Some(format!(
+        "Use None as the default and create the {} in the function body.",
+        s.kind
+    )),
+    explanation: {
+        title: "Mutable default value",
+        description: "A list, dict or set is used as the default value of a parameter. The default \
+            is created once when the function is defined, so changing it in one call \
+            changes it for every later call.",
+        example: [
+            "def add(item, items=[]):",
+            "    items.append(item)",
+            "    return items",
+        ],
+        fix: "Use `None` as the default and create the value inside the function.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (StarredAfterKeywordDiag, "PCV018", self, DiagnosticType::Warning),
+    (),
+    |_: &StarredAfterKeywordDiag, _| "Unpacked arguments after a keyword argument are passed before it.".to_owned(),
+    help: |_| Some("Move the unpacked arguments before the keyword arguments."),
+    explanation: {
+        title: "Unpacked arguments after a keyword argument",
+        description: "In `f(x=1, *args)` the unpacked `args` are passed as positional \
+            arguments, so they fill the parameters before `x` even though they are written \
+            after it.",
+        fix: "Write the unpacked arguments first to match how they are passed.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (SpecialMethodDiag, "PCV019", self, DiagnosticType::Error),
+    (method: Symbol, problem: &'static str),
+    |s: &SpecialMethodDiag, _| format!("{} {}.", &s.method, s.problem),
+    explanation: {
+        title: "Malformed special method",
+        description: "A method Python calls itself while creating objects doesn't have the shape \
+            it expects. `__init__` has to return `None`, both in its annotation and its \
+            `return` statements, and `__new__` and `__init_subclass__` have to take the \
+            class as their first parameter.",
+        fix: "Match the signature Python expects for the method.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (StarImportCollisionDiag, "PCV020", self, DiagnosticType::Warning),
+    (name: Symbol, previous: Symbol, module: Symbol),
+    |s: &StarImportCollisionDiag, _| format!(
+        "\"{}\" from \"{}\" replaces the one imported from \"{}\".",
+        &s.name, &s.module, &s.previous
+    ),
+    help: |s: &StarImportCollisionDiag| Some(format!(
+        "Import \"{}\" explicitly from the module it should come from.",
+        &s.name
+    )),
+    explanation: {
+        title: "Star import collision",
+        description: "A `from module import *` brings in a name that was already imported \
+            from a different module, so the later import silently wins.",
+        example: [
+            "from os.path import *",
+            "from posixpath import *",
+        ],
+        fix: "Import the name explicitly from the module it should come from.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (ConditionalDefinitionDiag, "PCV021", self, DiagnosticType::Warning),
+    (name: Symbol, expected: Type, got: Type, first: Option<TextRange>),
+    |s: &ConditionalDefinitionDiag, _| with_qualified_collisions(&[&s.expected, &s.got], || {
+        format!("Fallback definition of \"{}\" is {}, which doesn't match {}.", &s.name, s.got, s.expected)
+    }),
+    labels: |s: &ConditionalDefinitionDiag| s.first.map(|range| {
+        with_qualified_collisions(&[&s.expected, &s.got], || {
+            (range, format!("\"{}\" is defined as {} here", &s.name, s.expected))
+        })
+    }),
+    note: |_| Some("The definition in the main branch is used everywhere, like in the type checking branch of TYPE_CHECKING."),
+    explanation: {
+        title: "Incompatible fallback definition",
+        description: "Names defined under `if TYPE_CHECKING:` or in a `try:` that imports \
+            something can have a fallback definition in the `else:` or `except \
+            ImportError:` branch. pycavalry uses the type from the main branch \
+            everywhere, and reports a fallback that doesn't fit it. A fallback of `None` \
+            is allowed and makes the name optional.",
+        example: [
+            "try:",
+            "    from fastjson import loads",
+            "except ImportError:",
+            "    loads = 1",
+        ],
+        fix: "Give the fallback the same type as the main definition.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (UnreachableBranchDiag, "PCV022", self, DiagnosticType::Warning),
+    (earlier_taken: bool),
+    |s: &UnreachableBranchDiag, _| if s.earlier_taken {
+        "This branch is never run, an earlier condition is always true.".to_owned()
+    } else {
+        "This branch is never run, its condition is always false.".to_owned()
+    },
+    note: |_| Some("Code in branches that never run isn't checked."),
+    explanation: {
+        title: "Unreachable branch",
+        description: "Only reported with `--warn-unreachable`. The condition of an `if` or `elif` \
+            is a constant, like `if False:` or `if DEBUG:` after `DEBUG = False`, so the \
+            branch can never run. Branches that never run are skipped without being \
+            checked, whether or not this warning is turned on.",
+        fix: "Remove the branch, or fix the condition.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (AssertTypeDiag, "PCV023", self, DiagnosticType::Error),
+    (expected: Type, got: Type),
+    |s: &AssertTypeDiag, _| with_qualified_collisions(&[&s.expected, &s.got], || {
+        format!("Type is {}, not {} as asserted.", s.got, s.expected)
+    }),
+    explanation: {
+        title: "assert_type failed",
+        description: "`assert_type(value, T)` from `typing` or `pycavalry` checks that pycavalry \
+            inferred exactly `T` for the value, not just something compatible with it.",
+        example: [
+            "from pycavalry import assert_type",
+            "assert_type(1, int)  # The type is Literal[1]",
+        ],
+        fix: "Assert the reported type, or change the value to match.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (RevealLocalsDiag, "PCV024", self, DiagnosticType::Info),
+    (locals: Vec<(Symbol, Type)>),
+    |s: &RevealLocalsDiag, _| if s.locals.is_empty() {
+        "There are no local variables.".to_owned()
+    } else {
+        let locals: Vec<String> = s
+            .locals
+            .iter()
+            .map(|(name, typ)| format!("{}: {}", name, typ))
+            .collect();
+        format!("Local types are {}", locals.join(", "))
+    },
+    explanation: {
+        title: "Revealed local types",
+        description: "Informational diagnostic emitted by `reveal_locals()`, listing the type of \
+            every variable in the current scope.",
+        fix: "Remove the `reveal_locals` call once the types have been checked.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (UnknownFileTypeDiag, "PCV025", self, DiagnosticType::Warning),
+    (),
+    |_: &UnknownFileTypeDiag, _| "File skipped, its type couldn't be detected.".to_owned(),
+    help: |_| Some("Choose how it is checked with --language."),
+    explanation: {
+        title: "Unknown file type",
+        description: "The file was skipped because pycavalry couldn't tell what language it \
+            is written in. Files ending in `.py` and `.pyi` are checked as Python, and \
+            files without an extension are checked when they start with a `python` shebang.",
+        fix: "Pass `--language python` to check files with other extensions.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (UnsupportedLanguageDiag, "PCV026", self, DiagnosticType::Warning),
+    (language: Language),
+    |s: &UnsupportedLanguageDiag, _| format!(
+        "File skipped, checking {:?} files isn't supported yet.",
+        s.language
+    ),
+    explanation: {
+        title: "Language not supported yet",
+        description: "The file was skipped because it is in a language pycavalry can't check yet, \
+            like a Jinja template detected from a `.jinja` or `.j2` extension or chosen \
+            with `--language jinja`.",
+        fix: "Exclude the file, or pass `--language python` if it is Python.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (UnknownAttributeDiag, "PCV027", self, DiagnosticType::Error),
+    (typ: Type, name: Symbol),
+    |s: &UnknownAttributeDiag, _| format!("{} has no attribute \"{}\".", &s.typ, &s.name),
+    explanation: {
+        title: "Unknown attribute",
+        description: "The class doesn't have the attribute. The attributes of a class are the ones \
+            declared in its body, its methods and properties, the attributes assigned to \
+            `self` in its methods and the attributes of its base classes. Reading an \
+            attribute the class doesn't define from an instance gives the return type of \
+            its `__getattr__` method, and assigning one checks the value against the \
+            `__setattr__` method. Builtin classes and classes inheriting from something \
+            pycavalry doesn't know the attributes of aren't checked.",
+        example: [
+            "class Point:",
+            "    def __init__(self, x: int) -> None:",
+            "        self.x = x",
+            "",
+            "Point(1).y  # Point has no attribute \"y\"",
+        ],
+        fix: "Fix the spelling, or define the attribute in the class or in `__init__`.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (KeywordArgumentDiag, "PCV028", self, DiagnosticType::Error),
+    (name: Symbol, problem: &'static str),
+    |s: &KeywordArgumentDiag, _| format!("Keyword argument \"{}\" {}.", &s.name, s.problem),
+    explanation: {
+        title: "Keyword argument problem",
+        description: "A keyword argument doesn't match the parameters of the function being \
+            called. Either the function has no parameter with that name and no \
+            `**kwargs`, the parameter was already given by position, or a keyword only \
+            parameter without a default value wasn't given.",
+        example: [
+            "def greet(name: str, *, loud: bool) -> str: ...",
+            "",
+            "greet(\"a\", name=\"b\", loud=True)  # Keyword argument \"name\" is given more than once",
+            "greet(\"a\", quiet=True, loud=True)  # Keyword argument \"quiet\" is not a parameter of the function",
+        ],
+        fix: "Pass each parameter once, by its name, and give every required one.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (PositionalOnlyDiag, "PCV029", self, DiagnosticType::Error),
+    (name: Symbol),
+    |s: &PositionalOnlyDiag, _| format!(
+        "Parameter \"{}\" is positional only and can't be passed by keyword.",
+        &s.name
+    ),
+    help: |_: &PositionalOnlyDiag| Some("Pass it by position instead."),
+    explanation: {
+        title: "Positional only parameter passed by keyword",
+        description: "The parameters before a `/` in the parameter list can only be passed by \
+            position, so their names aren't part of the function's signature.",
+        example: [
+            "def scale(value: float, /, factor: float) -> float: ...",
+            "",
+            "scale(value=1.0, factor=2.0)  # Parameter \"value\" is positional only and can't be passed by keyword",
+        ],
+        fix: "Pass the argument by position instead.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (TooManyPositionalDiag, "PCV030", self, DiagnosticType::Error),
+    (expected: usize, given: usize),
+    |s: &TooManyPositionalDiag, _| format!(
+        "Expected at most {} positional arguments but got {}.",
+        s.expected, s.given
+    ),
+    help: |_: &TooManyPositionalDiag| Some("The parameters after `*` can only be passed by keyword."),
+    explanation: {
+        title: "Too many positional arguments",
+        description: "More arguments were passed by position than the function has positional \
+            parameters, while the rest of its parameters come after a `*` and can only \
+            be passed by keyword.",
+        example: [
+            "def connect(host: str, *, port: int) -> None: ...",
+            "",
+            "connect(\"localhost\", 80)  # Expected at most 1 positional arguments but got 2",
+        ],
+        fix: "Pass the arguments after the positional parameters by keyword.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (UnsupportedOperandDiag, "PCV031", self, DiagnosticType::Error),
+    (op: Operator, left: Type, right: Type),
+    |s: &UnsupportedOperandDiag, _| format!(
+        "Unsupported operand types for {}: {} and {}.",
+        s.op.as_str(),
+        &s.left,
+        &s.right
+    ),
+    explanation: {
+        title: "Unsupported operand types",
+        description: "The binary operator isn't defined for the types of its operands. Numbers, \
+            strings, bytes, tuples and instances of classes whose methods are all known \
+            are checked, an operator on a class instance works if the left operand has \
+            the method for it, like `__add__`, or the right operand has the reflected \
+            method, like `__radd__`.",
+        example: [
+            "count = \"a\" - 1  # Unsupported operand types for -: Literal[\"a\"] and Literal[1]",
+        ],
+        fix: "Convert an operand, like `str(count)`, or define the operator method.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (UnsupportedUnaryOperandDiag, "PCV032", self, DiagnosticType::Error),
+    (op: UnaryOp, operand: Type),
+    |s: &UnsupportedUnaryOperandDiag, _| format!(
+        "Unsupported operand type for {}: {}.",
+        s.op.as_str(),
+        &s.operand
+    ),
+    explanation: {
+        title: "Unsupported operand type for a unary operator",
+        description: "The unary operator isn't defined for the type of its operand, like `~` on a \
+            float or `-` on a string. On an instance of a class the operators use the \
+            `__neg__`, `__pos__` and `__invert__` methods.",
+        example: [
+            "flipped = ~1.5  # Unsupported operand type for ~: Literal[1.5]",
+        ],
+        fix: "Convert the operand, or define the operator method on its class.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (InvalidIndexDiag, "PCV033", self, DiagnosticType::Error),
+    (value: Type, index: Type),
+    |s: &InvalidIndexDiag, _| format!("{} can't be indexed with {}.", &s.value, &s.index),
+    explanation: {
+        title: "Invalid index",
+        description: "The value can't be indexed with a value of that type. Tuples, lists and \
+            strings take ints, dicts and other mappings take their key type, and \
+            instances of classes take what their `__getitem__` method accepts.",
+        example: [
+            "def lookup(ages: dict[str, int], names: list[str]) -> None:",
+            "    ages[0]  # dict[str, int] can't be indexed with Literal[0]",
+            "    names[\"first\"]  # list[str] can't be indexed with Literal[\"first\"]",
+        ],
+        fix: "Index with the type the container takes, like an `int` for a list.",
+    }
+);
+
+macros::custom_diagnostic!(
+    (IndexOutOfRangeDiag, "PCV034", self, DiagnosticType::Error),
+    (index: i64, value: Type),
+    |s: &IndexOutOfRangeDiag, _| format!("Index {} is out of range for {}.", s.index, &s.value),
+    explanation: {
+        title: "Index out of range",
+        description: "The literal index is past the end of a tuple or string whose length is \
+            known. Negative indices count from the end, so `-1` is the last element.",
+        example: [
+            "def second(pair: tuple[int, str]) -> str:",
+            "    return pair[2]  # Index 2 is out of range for tuple[int, str]",
+        ],
+        fix: "Use an index inside the length of the tuple or string.",
+    }
+);
+
+/// Every diagnostic with a code, in the order of their codes.
+pub const DIAGNOSTICS: [DiagnosticInfo; 34] = [
+    NotInScopeDiag::INFO,
+    NotInModuleDiag::INFO,
+    ExpectedButGotDiag::INFO,
+    CantReassignLockedDiag::INFO,
+    RevealTypeDiag::INFO,
+    UnsupportedSyntaxDiag::INFO,
+    FileTooLargeDiag::INFO,
+    ModuleAttributeAssignDiag::INFO,
+    DynamicGlobalDiag::INFO,
+    UntypedDefDiag::INFO,
+    ReassignDiag::INFO,
+    EncodingDiag::INFO,
+    SqlPlaceholderCountDiag::INFO,
+    SqlParameterTypeDiag::INFO,
+    UnusedParameterDiag::INFO,
+    DuplicateDefinitionDiag::INFO,
+    MutableDefaultDiag::INFO,
+    StarredAfterKeywordDiag::INFO,
+    SpecialMethodDiag::INFO,
+    StarImportCollisionDiag::INFO,
+    ConditionalDefinitionDiag::INFO,
+    UnreachableBranchDiag::INFO,
+    AssertTypeDiag::INFO,
+    RevealLocalsDiag::INFO,
+    UnknownFileTypeDiag::INFO,
+    UnsupportedLanguageDiag::INFO,
+    UnknownAttributeDiag::INFO,
+    KeywordArgumentDiag::INFO,
+    PositionalOnlyDiag::INFO,
+    TooManyPositionalDiag::INFO,
+    UnsupportedOperandDiag::INFO,
+    UnsupportedUnaryOperandDiag::INFO,
+    InvalidIndexDiag::INFO,
+    IndexOutOfRangeDiag::INFO,
+];
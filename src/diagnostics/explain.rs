@@ -0,0 +1,114 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Extended explanations of the diagnostic codes. They are written next to each diagnostic in
+//! `custom_diagnostic!`, printed by `pycavalry explain` and rendered into docs/diagnostics.md.
+
+use std::fmt::{self, Write};
+
+use super::{custom::DIAGNOSTICS, DiagnosticInfo};
+
+/// The start of docs/diagnostics.md, before the explanation of every code.
+const DOCS_INTRO: &str = "\
+# Diagnostics
+
+Every diagnostic pycavalry reports has a code, this page explains what each of
+them means and how to fix it. The same explanations are printed by
+`pycavalry explain PCV001`. This page is generated from the explanations next
+to the diagnostics in src/diagnostics/custom.rs, regenerate it with
+`PYCAVALRY_UPDATE_DOCS=1 cargo test --test explain` after changing them.
+
+Codes can be turned off inside a single function with a comment on the line
+it's defined on, `def f():  # pycavalry: disable=PCV001,PCV003`. Functions
+decorated with `@no_type_check` aren't checked at all.
+
+For whole parts of a project, `--per-path-ignore` ignores codes in the files
+matching a glob, like `--per-path-ignore 'tests/**:PCV010,PCV015'` to keep
+strict mode out of the tests. Leaving the codes out, `--per-path-ignore
+generated`, ignores every diagnostic in those files.
+";
+
+/// Width the paragraphs of docs/diagnostics.md are wrapped at.
+const DOCS_WIDTH: usize = 79;
+
+/// The explanation of a diagnostic, displayed the way `pycavalry explain` prints it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Explanation(pub DiagnosticInfo);
+
+/// The explanation of a diagnostic code like PCV012, ignoring case.
+pub fn explain(code: &str) -> Option<Explanation> {
+    DIAGNOSTICS
+        .iter()
+        .find(|info| info.code.eq_ignore_ascii_case(code.trim()))
+        .map(|info| Explanation(*info))
+}
+
+/// The contents of docs/diagnostics.md, with a section explaining every diagnostic code.
+pub fn diagnostics_markdown() -> String {
+    let mut docs = DOCS_INTRO.to_owned();
+    for info in DIAGNOSTICS {
+        // Writing to a String can't fail
+        let _ = writeln!(docs, "\n## {}\n", info.code);
+        let _ = writeln!(docs, "{}", wrap(&format!("**{}.**", info.title)));
+        let _ = writeln!(docs, "\n{}", wrap(info.description));
+        if !info.example.is_empty() {
+            let _ = writeln!(docs, "\n```python\n{}\n```", info.example.join("\n"));
+        }
+        if let Some(fix) = info.fix {
+            let _ = writeln!(docs, "\n{}", wrap(fix));
+        }
+    }
+    docs
+}
+
+/// Break the text into lines no longer than the width of the docs, where possible.
+fn wrap(text: &str) -> String {
+    let mut wrapped = String::with_capacity(text.len());
+    let mut line_len = 0;
+    for word in text.split_whitespace() {
+        if line_len > 0 && line_len + 1 + word.len() > DOCS_WIDTH {
+            wrapped.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            wrapped.push(' ');
+            line_len += 1;
+        }
+        wrapped.push_str(word);
+        line_len += word.len();
+    }
+    wrapped
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = &self.0;
+        writeln!(f, "{} ({}): {}", info.code, info.typ, info.title)?;
+        writeln!(f)?;
+        writeln!(f, "{}", info.description)?;
+        if !info.example.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Example:")?;
+            writeln!(f)?;
+            for line in info.example {
+                writeln!(f, "    {}", line)?;
+            }
+        }
+        if let Some(fix) = info.fix {
+            writeln!(f)?;
+            writeln!(f, "Fix: {}", fix)?;
+        }
+        Ok(())
+    }
+}
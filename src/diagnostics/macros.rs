@@ -9,8 +9,41 @@ macro_rules! impl_diagnostic_to_box {
 }
 pub(crate) use impl_diagnostic_to_box;
 
+macro_rules! optional_section {
+    ( $self:ident ) => {
+        None
+    };
+    ( $self:ident, $section:expr ) => {
+        $section($self).map(|s| s.to_string())
+    };
+}
+pub(crate) use optional_section;
+
+macro_rules! optional_literal {
+    () => {
+        None
+    };
+    ( $literal:literal ) => {
+        Some($literal)
+    };
+}
+pub(crate) use optional_literal;
+
 macro_rules! custom_diagnostic {
-    ( ($typ:ident, $self:ident, $kind:expr), ($( $prop:ident: $prop_typ:ty ),*), $func:expr ) => {
+    (
+        ($typ:ident, $code:literal, $self:ident, $kind:expr),
+        ($( $prop:ident: $prop_typ:ty ),*),
+        $func:expr
+        $(, labels: $labels:expr)?
+        $(, note: $note:expr)?
+        $(, help: $help:expr)?,
+        explanation: {
+            title: $title:literal,
+            description: $description:literal,
+            $(example: [$($example:literal),* $(,)?],)?
+            $(fix: $fix:literal,)?
+        }
+    ) => {
         #[derive(Debug, PartialEq)]
         pub struct $typ {
             $(
@@ -20,6 +53,16 @@ macro_rules! custom_diagnostic {
         }
 
         impl $typ {
+            pub const INFO: crate::diagnostics::DiagnosticInfo = crate::diagnostics::DiagnosticInfo {
+                code: $code,
+                name: stringify!($typ),
+                typ: $kind,
+                title: $title,
+                description: $description,
+                example: &[$($($example),*)?],
+                fix: crate::diagnostics::macros::optional_literal!($($fix)?),
+            };
+
             pub fn new($($prop: $prop_typ,)* range: TextRange) -> Self {
                 Self { $($prop,)* range }
             }
@@ -32,13 +75,51 @@ macro_rules! custom_diagnostic {
                 use crate::diagnostics::{type_to_color, type_to_kind};
                 let color = type_to_color(&$kind);
                 let kind = type_to_kind(&$kind);
-                Report::build(kind, file_name, $self.range.start().to_usize())
+                let mut report = Report::build(kind, file_name, $self.range.start().to_usize())
+                    .with_code($code)
                     .with_label(
                         Label::new((file_name, convert_range($self.range)))
-                            .with_message($func($self, color))
+                            .with_message($func($self, Some(color)))
                             .with_color(color),
-                    )
-                    .finish()
+                    );
+                $(
+                    report.add_labels($labels($self).into_iter().map(|(range, message)| {
+                        Label::new((file_name, convert_range(range)))
+                            .with_message(message)
+                            .with_color(ariadne::Color::Cyan)
+                    }));
+                )?
+                if let Some(note) = $self.note() {
+                    report.set_note(note);
+                }
+                if let Some(help) = $self.help() {
+                    report.set_help(help);
+                }
+                report.finish()
+            }
+
+            fn range(&$self) -> TextRange {
+                $self.range
+            }
+
+            fn typ(&$self) -> DiagnosticType {
+                $kind
+            }
+
+            fn message(&$self) -> String {
+                $func($self, None::<ariadne::Color>)
+            }
+
+            fn code(&$self) -> Option<&'static str> {
+                Some($code)
+            }
+
+            fn note(&$self) -> Option<String> {
+                crate::diagnostics::macros::optional_section!($self $(, $note)?)
+            }
+
+            fn help(&$self) -> Option<String> {
+                crate::diagnostics::macros::optional_section!($self $(, $help)?)
             }
         }
     };
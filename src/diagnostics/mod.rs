@@ -17,5 +17,6 @@ pub mod base;
 pub mod macros;
 pub mod custom;
 pub mod dyn_compare;
+pub mod explain;
 
 pub use base::*;
@@ -0,0 +1,101 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Decoding source files into text, following the BOM or PEP 263 encoding declaration.
+
+use std::sync::Arc;
+
+use ruff_text_size::TextRange;
+
+use crate::diagnostics::custom::EncodingDiag;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Utf8,
+    Latin1,
+}
+
+/// Decode the bytes of a Python file, with a diagnostic if the text had to be guessed at.
+///
+/// Files are UTF-8 unless they start with a `# -*- coding: ... -*-` comment on one of the first
+/// two lines. Bytes that aren't valid in the encoding are replaced, as are files in encodings
+/// that aren't supported.
+pub fn decode_source(bytes: Vec<u8>) -> (String, Option<EncodingDiag>) {
+    if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+        return decode_utf8(rest.to_vec(), "UTF-8");
+    }
+    let Some(name) = coding_cookie(&bytes) else {
+        return decode_utf8(bytes, "UTF-8");
+    };
+    match encoding_of(&name) {
+        Some(Encoding::Utf8) => decode_utf8(bytes, "UTF-8"),
+        // Every byte is the code point with the same value in Latin-1
+        Some(Encoding::Latin1) => (bytes.iter().map(|&b| b as char).collect(), None),
+        None => {
+            let (content, _) = decode_utf8(bytes, "UTF-8");
+            let diag = EncodingDiag::new(Arc::new(name), false, TextRange::default());
+            (content, Some(diag))
+        }
+    }
+}
+
+fn decode_utf8(bytes: Vec<u8>, encoding: &str) -> (String, Option<EncodingDiag>) {
+    match String::from_utf8(bytes) {
+        Ok(content) => (content, None),
+        Err(err) => {
+            let content = String::from_utf8_lossy(err.as_bytes()).into_owned();
+            let diag = EncodingDiag::new(Arc::new(encoding.to_owned()), true, TextRange::default());
+            (content, Some(diag))
+        }
+    }
+}
+
+/// The encoding named in a coding comment on the first two lines, as described in PEP 263.
+fn coding_cookie(bytes: &[u8]) -> Option<String> {
+    for line in bytes.split(|&b| b == b'\n').take(2) {
+        let line = String::from_utf8_lossy(line);
+        let Some(comment) = line.trim_start().strip_prefix('#') else {
+            continue;
+        };
+        let Some(index) = comment.find("coding") else {
+            continue;
+        };
+        let rest = &comment[index + "coding".len()..];
+        let Some(rest) = rest.strip_prefix([':', '=']) else {
+            continue;
+        };
+        let name: String = rest
+            .trim_start_matches([' ', '\t'])
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            .collect();
+        if !name.is_empty() {
+            return Some(name);
+        }
+    }
+    None
+}
+
+fn encoding_of(name: &str) -> Option<Encoding> {
+    match name.to_lowercase().replace('_', "-").as_str() {
+        "utf-8" | "utf8" | "utf-8-sig" | "ascii" | "us-ascii" => Some(Encoding::Utf8),
+        "latin-1" | "latin1" | "iso-8859-1" | "iso8859-1" | "l1" | "cp819" => {
+            Some(Encoding::Latin1)
+        }
+        _ => None,
+    }
+}
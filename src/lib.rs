@@ -17,20 +17,60 @@ use std::io;
 use std::sync::Arc;
 use std::{path::PathBuf, string::FromUtf8Error};
 
-use ruff_python_parser::{parse, Mode};
+use analysis::ScopeSnapshot;
+use encoding::decode_source;
+use ruff_python_parser::{parse_unchecked, Mode};
+use ruff_text_size::{TextRange, TextSize};
 use state::StatementSynthData;
+use stubs::builtins_with_stubs;
 
-pub use diagnostics::{custom::*, Diag, Diagnostic, DiagnosticType};
+pub use analysis::{
+    annotated_metadata, annotation_edits, apply_edits, completions_at, references_of, rename_edits,
+    semantic_tokens, signature_at, type_map, AnnotatedSite, Completion, CompletionKind,
+    ExpressionType, ParameterHelp, SemanticToken, SignatureHelp, TextEdit, TokenKind,
+};
+pub use config::{Config, Language, PathRule};
+pub use conformance::{
+    check_conformance, check_conformance_file, ConformanceOutcome, ConformanceReport,
+};
+pub use corpus::{check_corpus, check_corpus_file, CorpusReport, FileOutcome};
+pub use diagnostics::{
+    custom::*,
+    explain::{diagnostics_markdown, explain, Explanation},
+    Diag, Diagnostic, DiagnosticInfo, DiagnosticType,
+};
+pub use modules::{module_cache, with_dependents, ModuleCache};
+pub use project::{file_list, python_files};
 pub use scope::{Scope, ScopedType};
-pub use state::Info;
+pub use sql::{PlaceholderValidator, SqlQuery, SqlValidator};
+pub use state::{Info, Phase, Profiler};
+pub use symbol::Symbol;
 pub use synth::{check_statement, synth, synth_annotation};
-pub use types::{Type, TypeLiteral};
+pub use types::{
+    intersection, is_subtype, union, with_qualified_collisions, with_union_style, Class, Type,
+    TypeLiteral, UnionStyle,
+};
 
+mod analysis;
+mod builtins;
+mod config;
+mod conformance;
+mod corpus;
 mod diagnostics;
+mod encoding;
+mod modules;
+mod project;
 mod scope;
+mod sql;
 mod state;
+mod stubs;
+mod symbol;
 mod synth;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod types;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
 
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -65,22 +105,134 @@ impl From<&[ruff_python_parser::ParseError]> for Error {
 }
 
 pub fn error_check_file(name: PathBuf, content: String) -> Result<Info, Error> {
-    // Parse the module with ruff
-    let module = parse(&content, Mode::Module)?;
-    let errors = module.errors();
-    if !errors.is_empty() {
-        return Err(errors.into());
+    error_check_file_with_config(name, content, Arc::default())
+}
+
+pub fn error_check_file_with_config(
+    name: PathBuf,
+    content: String,
+    config: Arc<Config>,
+) -> Result<Info, Error> {
+    let info = check_with_config(name, content, config);
+    info.reporter
+        .apply_path_rules(&info.file_name, &info.config.path_rules);
+    Ok(info)
+}
+
+/// Check a file from its raw bytes, decoding it according to its BOM or encoding declaration.
+pub fn error_check_bytes_with_config(
+    name: PathBuf,
+    bytes: Vec<u8>,
+    config: Arc<Config>,
+) -> Result<Info, Error> {
+    let (content, problem) = decode_source(bytes);
+    let info = check_with_config(name, content, config);
+    if let Some(problem) = problem {
+        info.reporter.add(problem);
     }
+    // The path rules apply to the decode problem too
+    info.reporter
+        .apply_path_rules(&info.file_name, &info.config.path_rules);
+    Ok(info)
+}
 
-    let mut scope = Scope::new();
+/// Check a file unless it is too large or not Python, without applying the path rules yet.
+fn check_with_config(name: PathBuf, content: String, config: Arc<Config>) -> Info {
+    let info = Info::with_config(Arc::new(name), Arc::new(content), config);
+    if !is_too_large(&info) && is_checkable(&info) {
+        parse_and_check(&info);
+    }
+    info
+}
+
+/// Check a file without ever failing, parse errors are reported as diagnostics instead.
+///
+/// Any syntax the checker doesn't support yet is reported with an UnsupportedSyntaxDiag, so this
+/// never panics on valid Python.
+pub fn error_check_file_lossy(name: PathBuf, content: String) -> Info {
     let info = Info::new(Arc::new(name), Arc::new(content));
+    parse_and_check(&info);
+    info
+}
+
+/// Parse the module with ruff, reporting syntax errors as diagnostics and checking the parts of
+/// the file that could still be parsed around them.
+fn parse_and_check(info: &Info) -> Scope {
+    let parsed = info.profiler.time(Phase::Parsing, || {
+        parse_unchecked(&info.file_content, Mode::Module)
+    });
+    for err in parsed.errors() {
+        info.reporter
+            .add(Diagnostic::error(err.error.to_string(), err.location));
+    }
+    check_module(info, parsed.into_syntax())
+}
+
+/// Report the file as skipped if it is over the configured size limit.
+fn is_too_large(info: &Info) -> bool {
+    let size = info.file_content.len();
+    match info.config.max_file_size {
+        Some(limit) if size > limit => {
+            info.reporter
+                .add(FileTooLargeDiag::new(size, limit, TextRange::default()));
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Report the file as skipped if it isn't Python or a stub, going by --language or the extension.
+fn is_checkable(info: &Info) -> bool {
+    let language = info
+        .config
+        .language
+        .or_else(|| Language::detect(&info.file_name, &info.file_content));
+    match language {
+        Some(Language::Python | Language::Pyi) => true,
+        Some(language) => {
+            info.reporter
+                .add(UnsupportedLanguageDiag::new(language, TextRange::default()));
+            false
+        }
+        None => {
+            info.reporter
+                .add(UnknownFileTypeDiag::new(TextRange::default()));
+            false
+        }
+    }
+}
+
+/// Check the module, returning the scope with its global variables.
+fn check_module(info: &Info, module: ruff_python_ast::Mod) -> Scope {
+    info.profiler
+        .time(Phase::Checking, || check_module_body(info, module))
+}
+
+fn check_module_body(info: &Info, module: ruff_python_ast::Mod) -> Scope {
+    let mut scope = Scope::with_builtins(builtins_with_stubs(info));
     let mut data = StatementSynthData::new(None);
-    let module = match module.into_syntax() {
+    let module = match module {
         ruff_python_ast::Mod::Module(m) => m,
         ruff_python_ast::Mod::Expression(_) => unreachable!(),
     };
     for stmt in module.body.into_iter() {
-        check_statement(&info, &mut data, &mut scope, stmt);
+        check_statement(info, &mut data, &mut scope, stmt);
     }
-    Ok(info)
+    info.analysis.add_scope(ScopeSnapshot {
+        range: TextRange::up_to(TextSize::of(info.file_content.as_str())),
+        names: scope.visible(),
+    });
+    scope
+}
+
+/// The global variables of another file, checked with its diagnostics thrown away.
+pub(crate) fn module_globals(
+    name: PathBuf,
+    bytes: Vec<u8>,
+    config: Arc<Config>,
+) -> Vec<(Symbol, ScopedType)> {
+    let (content, _) = decode_source(bytes);
+    let info = Info::with_config(Arc::new(name), Arc::new(content), config);
+    let module = parse_unchecked(&info.file_content, Mode::Module);
+    check_module(&info, module.into_syntax()).locals()
 }
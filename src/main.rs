@@ -15,21 +15,53 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::{
-    fs::read,
-    io::Write,
+    fs::{read, read_to_string, write},
+    io::{self, Write},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, available_parallelism},
 };
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use clio::{ClioPath, Output};
 
-use pycavalry::{error_check_file, Error, Info};
+use pycavalry::{
+    annotation_edits, apply_edits, check_conformance, check_corpus, error_check_bytes_with_config,
+    explain, file_list, python_files, rename_edits, type_map, with_dependents, with_union_style,
+    Config, CorpusReport, DiagnosticType, Error, Info, Language, PathRule, Phase,
+    PlaceholderValidator, UnionStyle,
+};
+use ruff_text_size::TextSize;
 
 #[derive(Parser)]
-#[clap(name = "pycavalry")]
+#[clap(
+    name = "pycavalry",
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
 struct Opt {
-    #[clap()]
-    file: PathBuf,
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// Files to check, directories are searched for .py and .pyi files
+    #[clap(required_unless_present = "files_from")]
+    files: Vec<PathBuf>,
+
+    /// Also check the files listed in this file, one per line, '-' for stdin
+    #[clap(long, value_name = "FILE")]
+    files_from: Option<PathBuf>,
+
+    /// Only check the files changed since this git revision and the files importing them, '-' to
+    /// read the changed files from stdin instead, one per line
+    #[clap(long, value_name = "REV")]
+    changed_since: Option<String>,
+
+    /// Only print the diagnostics, without the summary at the end
+    #[clap(long, short)]
+    quiet: bool,
 
     /// Output file '-' for stdout
     #[clap(long, short, value_parser, default_value = "-")]
@@ -38,46 +70,598 @@ struct Opt {
     /// Directory to store log files in
     #[clap(long, short, value_parser = clap::value_parser!(ClioPath).exists().is_dir(), default_value = ".")]
     log_dir: ClioPath,
+
+    /// Report the time spent in each phase of checking the file
+    #[clap(long)]
+    profile: bool,
+
+    /// Skip files larger than this many bytes
+    #[clap(long)]
+    max_file_size: Option<usize>,
+
+    /// How unions are displayed in diagnostics
+    #[clap(long, value_enum, default_value_t)]
+    union_style: UnionStyle,
+
+    /// Warn about functions with unannotated parameters or return types
+    #[clap(long)]
+    strict: bool,
+
+    /// Don't apply strict mode to functions with this decorator, can be given multiple times
+    #[clap(long = "strict-exempt-decorator", value_name = "NAME")]
+    strict_exempt_decorators: Vec<String>,
+
+    /// Warn when imported names or module level UPPER_CASE constants are reassigned
+    #[clap(long)]
+    warn_reassign: bool,
+
+    /// Warn about function parameters that are never used
+    #[clap(long)]
+    warn_unused_parameters: bool,
+
+    /// Warn about branches that are never run because their condition is constant
+    #[clap(long)]
+    warn_unreachable: bool,
+
+    /// Check the placeholders of literal SQL queries passed to execute calls
+    #[clap(long)]
+    check_sql: bool,
+
+    /// Directory with .pyi stubs to use before the bundled ones, can be given multiple times
+    #[clap(long = "stub-path", value_name = "DIR")]
+    stub_paths: Vec<PathBuf>,
+
+    /// Check the files as this language instead of detecting it from their extension
+    #[clap(long, value_enum)]
+    language: Option<Language>,
+
+    /// Skip files and directories matching this glob when searching directories, can be given
+    /// multiple times
+    #[clap(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Ignore diagnostics in the files matching a glob, as GLOB to ignore all of them or
+    /// GLOB:CODE,CODE to ignore only some codes, can be given multiple times
+    #[clap(long, value_name = "GLOB[:CODES]")]
+    per_path_ignore: Vec<PathRule>,
+
+    /// Also check files ignored by .gitignore files
+    #[clap(long)]
+    no_gitignore: bool,
+
+    /// Print the inferred type of every expression instead of the diagnostics
+    #[clap(long, value_enum, value_name = "FORMAT")]
+    emit_type_map: Option<TypeMapFormat>,
+}
+
+/// Formats the type map can be printed in.
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum TypeMapFormat {
+    /// An array with an object for each file, listing the start and end line and column of each
+    /// expression and its type
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Rename the variable at a position and all references to it
+    Rename {
+        file: PathBuf,
+        /// Line of the variable, starting at 1
+        line: usize,
+        /// Column of the variable, starting at 1
+        column: usize,
+        new_name: String,
+
+        /// Print the renamed file instead of writing it
+        #[clap(long)]
+        dry_run: bool,
+    },
+    /// Add the inferred annotations to functions that are missing them
+    Annotate {
+        file: PathBuf,
+
+        /// Print the changed lines instead of writing the file
+        #[clap(long)]
+        diff: bool,
+    },
+    /// Check every Python file in a directory of projects and compare the diagnostics against a
+    /// stored snapshot
+    SelfTest {
+        /// Directory containing the projects to check
+        #[clap(long, value_parser = clap::value_parser!(ClioPath).exists().is_dir())]
+        corpus: ClioPath,
+
+        /// Snapshot to compare against, defaults to snapshot.txt in the corpus directory
+        #[clap(long)]
+        snapshot: Option<PathBuf>,
+
+        /// Overwrite the snapshot with the new results instead of comparing against it
+        #[clap(long)]
+        update: bool,
+    },
+    /// Run the typing conformance test suite and print which feature areas pass
+    Conformance {
+        /// Directory with the test files of the suite
+        #[clap(long, value_parser = clap::value_parser!(ClioPath).exists().is_dir())]
+        suite: ClioPath,
+    },
+    /// Print what a diagnostic code means, an example of it and how to fix it
+    Explain {
+        /// Diagnostic code, like PCV012
+        code: String,
+    },
 }
 
-fn read_file(file_name: &Path) -> Result<String, Error> {
-    let bytes = read(file_name)?;
-    let content = String::from_utf8(bytes)?;
-    Ok(content)
+/// Everything printed for one checked file, rendered on the thread that checked it.
+struct FileResult {
+    output: Vec<u8>,
+    errors: usize,
+    warnings: usize,
+    /// Whether the file could be parsed and checked
+    checked: bool,
+    /// Whether the file couldn't be checked or has an error, which makes the run fail
+    failed: bool,
+}
+
+fn check_to_buffer(
+    file: &Path,
+    config: Arc<Config>,
+    profile: bool,
+    name_errors: bool,
+) -> FileResult {
+    let mut output = vec![];
+    let type_map = config.record_types;
+    let (errors, warnings, checked, failed) = match read_and_check(file.to_owned(), config) {
+        Ok(info) if type_map => {
+            let _ = write_type_map(&mut output, &info);
+            let (errors, warnings) = count_diagnostics(&info);
+            (errors, warnings, true, false)
+        }
+        Err(e) if type_map => {
+            let mut message = vec![];
+            let _ = write_error(&mut message, e);
+            let _ = write!(
+                output,
+                "  {{\"file\": {}, \"error\": {}}}",
+                json_string(&file.display().to_string()),
+                json_string(&String::from_utf8_lossy(&message))
+            );
+            (1, 0, false, true)
+        }
+        Ok(info) => {
+            let (errors, warnings) = count_diagnostics(&info);
+            let failed = errors > 0;
+            // Writing to a Vec can't fail
+            let _ = info
+                .profiler
+                .time(Phase::Rendering, || info.reporter.flush(&info, &mut output));
+            if profile {
+                let _ = info.profiler.write(&info, &mut output);
+            }
+            (errors, warnings, true, failed)
+        }
+        Err(e) => {
+            if name_errors {
+                let _ = write!(output, "{}: ", file.display());
+            }
+            let _ = write_error(&mut output, e);
+            (1, 0, false, true)
+        }
+    };
+    FileResult {
+        output,
+        errors,
+        warnings,
+        checked,
+        failed,
+    }
+}
+
+/// The number of errors and warnings reported for a file, leaving out informational
+/// diagnostics like `reveal_type`.
+fn count_diagnostics(info: &Info) -> (usize, usize) {
+    let diagnostics = info.reporter.errors();
+    let diagnostics = diagnostics.lock().unwrap();
+    let count = |typ| diagnostics.iter().filter(|diag| diag.typ() == typ).count();
+    (count(DiagnosticType::Error), count(DiagnosticType::Warning))
+}
+
+/// A JSON string literal with the text.
+fn json_string(text: &str) -> String {
+    let mut json = String::with_capacity(text.len() + 2);
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// The JSON object for the types of a file, with 1 based lines and columns counted in characters.
+fn write_type_map(output: &mut impl Write, info: &Info) -> std::io::Result<()> {
+    let content = info.file_content.as_str();
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let position = |offset: TextSize| {
+        let offset = offset.to_usize();
+        let line = line_starts.partition_point(|&s| s <= offset);
+        let column = content[line_starts[line - 1]..offset].chars().count() + 1;
+        (line, column)
+    };
+
+    let file = info.file_name.display().to_string();
+    write!(output, "  {{\"file\": {}, \"types\": [", json_string(&file))?;
+    for (i, expression) in type_map(info).iter().enumerate() {
+        let (start_line, start_column) = position(expression.range.start());
+        let (end_line, end_column) = position(expression.range.end());
+        let typ = with_union_style(info.config.union_style, || expression.typ.to_string());
+        write!(
+            output,
+            "{}\n    {{\"start\": [{}, {}], \"end\": [{}, {}], \"type\": {}}}",
+            if i == 0 { "" } else { "," },
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+            json_string(&typ)
+        )?;
+    }
+    write!(output, "\n  ]}}")
+}
+
+/// Check the files on all cores, returning the results in the same order as the files.
+fn check_files(files: &[PathBuf], config: Arc<Config>, profile: bool) -> Vec<FileResult> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(files.len()));
+    let workers = available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(files.len());
+    thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(file) = files.get(i) else {
+                    break;
+                };
+                let result = check_to_buffer(file, config.clone(), profile, files.len() > 1);
+                results.lock().unwrap().push((i, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn read_and_check(file_name: PathBuf, config: Arc<Config>) -> Result<Info, Error> {
+    let bytes = read(&file_name)?;
+    error_check_bytes_with_config(file_name, bytes, config)
+}
+
+/// The files changed since the git revision and the new files git doesn't ignore, one per line
+/// relative to the working directory.
+fn git_changed_files(revision: &str) -> io::Result<String> {
+    let mut changed = String::new();
+    for args in [
+        &["diff", "--name-only", "--relative", revision][..],
+        &["ls-files", "--others", "--exclude-standard"],
+    ] {
+        let output = std::process::Command::new("git").args(args).output()?;
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+            return Err(io::Error::other(message));
+        }
+        changed.push_str(&String::from_utf8_lossy(&output.stdout));
+    }
+    Ok(changed)
 }
 
-fn read_and_check(file_name: PathBuf) -> Result<Info, Error> {
-    let content = read_file(&file_name)?;
-    error_check_file(file_name, content)
+/// Peak resident memory of the process in kilobytes, only available on Linux.
+fn peak_memory_kb() -> Option<u64> {
+    let status = read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Byte offset of a 1 based line and column, with the column counted in characters.
+fn offset_of(content: &str, line: usize, column: usize) -> Option<TextSize> {
+    let line_start: usize = content
+        .split_inclusive('\n')
+        .take(line.checked_sub(1)?)
+        .map(|l| l.len())
+        .sum();
+    let line_text = content.get(line_start..)?.lines().next().unwrap_or("");
+    let in_line = match line_text.char_indices().nth(column.checked_sub(1)?) {
+        Some((i, _)) => i,
+        None => line_text.len(),
+    };
+    TextSize::try_from(line_start + in_line).ok()
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn write_error(output: &mut impl Write, error: Error) -> std::io::Result<()> {
+    match error {
+        Error::Io(e) => write!(output, "Failed to open file: {}", e),
+        Error::FromUtf8(e) => write!(output, "File contains invalid UTF8 sequences: {}", e),
+        Error::RuffParse(errors) => {
+            writeln!(output, "Failed to parse Python into AST:")?;
+            for error in errors {
+                write!(output, "{}", error)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn rename(
+    output: &mut Output,
+    config: Arc<Config>,
+    file: PathBuf,
+    (line, column): (usize, usize),
+    new_name: String,
+    dry_run: bool,
+) -> Result<(), Error> {
+    if !is_identifier(&new_name) {
+        writeln!(output, "\"{}\" is not a valid identifier", new_name)?;
+        return Ok(());
+    }
+    let info = match read_and_check(file.clone(), config) {
+        Ok(info) => info,
+        Err(e) => {
+            write_error(output, e)?;
+            return Ok(());
+        }
+    };
+    let edits = offset_of(&info.file_content, line, column)
+        .map(|offset| rename_edits(&info, offset, &new_name))
+        .unwrap_or_default();
+    if edits.is_empty() {
+        writeln!(output, "No variable found at {}:{}", line, column)?;
+        return Ok(());
+    }
+
+    let renamed = apply_edits(&info.file_content, &edits);
+    if dry_run {
+        write!(output, "{}", renamed)?;
+    } else {
+        write(&file, renamed)?;
+        writeln!(output, "Renamed {} occurrences", edits.len())?;
+    }
+    Ok(())
+}
+
+/// The lines that differ between the two versions of a file, which have the same number of lines
+/// since annotations are only inserted within lines.
+fn write_line_diff(output: &mut Output, before: &str, after: &str) -> std::io::Result<()> {
+    for (i, (old, new)) in before.lines().zip(after.lines()).enumerate() {
+        if old != new {
+            writeln!(output, "@@ line {} @@", i + 1)?;
+            writeln!(output, "-{}", old)?;
+            writeln!(output, "+{}", new)?;
+        }
+    }
+    Ok(())
+}
+
+fn annotate(
+    output: &mut Output,
+    config: Arc<Config>,
+    file: PathBuf,
+    diff: bool,
+) -> Result<(), Error> {
+    let info = match read_and_check(file.clone(), config) {
+        Ok(info) => info,
+        Err(e) => {
+            write_error(output, e)?;
+            return Ok(());
+        }
+    };
+    let edits = annotation_edits(&info);
+    if edits.is_empty() {
+        writeln!(output, "No annotations to add")?;
+        return Ok(());
+    }
+
+    let annotated = apply_edits(&info.file_content, &edits);
+    if diff {
+        write_line_diff(output, &info.file_content, &annotated)?;
+    } else {
+        write(&file, annotated)?;
+        writeln!(output, "Added {} annotations", edits.len())?;
+    }
+    Ok(())
+}
+
+/// Returns whether the results matched the snapshot.
+fn self_test(
+    output: &mut Output,
+    config: Arc<Config>,
+    corpus: &Path,
+    snapshot: PathBuf,
+    update: bool,
+) -> Result<bool, Error> {
+    let report = check_corpus(corpus, config)?;
+    writeln!(
+        output,
+        "Checked {} files, found {} diagnostics",
+        report.files.len(),
+        report.diagnostic_count()
+    )?;
+    for (file, message) in report.crashes() {
+        writeln!(output, "Crashed on {}: {}", file, message)?;
+    }
+
+    if update || !snapshot.exists() {
+        write(&snapshot, report.to_string())?;
+        writeln!(output, "Wrote snapshot to {}", snapshot.display())?;
+        return Ok(true);
+    }
+    let Some(stored) = CorpusReport::parse(&read_to_string(&snapshot)?) else {
+        writeln!(output, "Snapshot {} is malformed", snapshot.display())?;
+        return Ok(false);
+    };
+    let changes = report.diff(&stored);
+    if changes.is_empty() {
+        writeln!(output, "No changes from the snapshot")?;
+    } else {
+        writeln!(output, "{} files changed from the snapshot:", changes.len())?;
+        for change in &changes {
+            writeln!(output, "{}", change)?;
+        }
+    }
+    Ok(changes.is_empty())
 }
 
 fn main() -> Result<(), Error> {
     let mut opt = Opt::parse();
 
-    match read_and_check(opt.file) {
-        Ok(info) => {
-            let error_count = info.reporter.len();
-            info.reporter.flush(&info, &mut opt.output)?;
-            if error_count > 0 {
-                writeln!(opt.output, "Found {} errors", error_count)?;
-            } else {
-                writeln!(opt.output, "No errors found")?;
+    let config = Arc::new(Config {
+        max_file_size: opt.max_file_size,
+        union_style: opt.union_style,
+        strict: opt.strict,
+        strict_exempt_decorators: opt.strict_exempt_decorators,
+        warn_reassign: opt.warn_reassign,
+        warn_unused_parameters: opt.warn_unused_parameters,
+        warn_unreachable: opt.warn_unreachable,
+        sql_validators: if opt.check_sql {
+            vec![Arc::new(PlaceholderValidator)]
+        } else {
+            vec![]
+        },
+        stub_paths: opt.stub_paths,
+        language: opt.language,
+        respect_gitignore: !opt.no_gitignore,
+        exclude: opt.exclude,
+        record_types: opt.emit_type_map.is_some(),
+        path_rules: opt.per_path_ignore,
+    });
+
+    let files = match opt.command {
+        Some(Command::Rename {
+            file,
+            line,
+            column,
+            new_name,
+            dry_run,
+        }) => {
+            return rename(
+                &mut opt.output,
+                config,
+                file,
+                (line, column),
+                new_name,
+                dry_run,
+            )
+        }
+        Some(Command::Annotate { file, diff }) => {
+            return annotate(&mut opt.output, config, file, diff)
+        }
+        Some(Command::SelfTest {
+            corpus,
+            snapshot,
+            update,
+        }) => {
+            let snapshot = snapshot.unwrap_or_else(|| corpus.join("snapshot.txt").to_path_buf());
+            if !self_test(&mut opt.output, config, &corpus, snapshot, update)? {
+                std::process::exit(1);
             }
+            return Ok(());
         }
-        Err(e) => match e {
-            Error::Io(e) => {
-                write!(opt.output, "Failed to open file: {}", e)?;
+        Some(Command::Conformance { suite }) => {
+            let report = check_conformance(&suite, config)?;
+            write!(opt.output, "{}", report)?;
+            return Ok(());
+        }
+        Some(Command::Explain { code }) => {
+            match explain(&code) {
+                Some(explanation) => write!(opt.output, "{}", explanation)?,
+                None => writeln!(opt.output, "Unknown diagnostic code \"{}\"", code)?,
             }
-            Error::FromUtf8(e) => {
-                write!(opt.output, "File contains invalid UTF8 sequences: {}", e)?;
+            return Ok(());
+        }
+        None => {
+            let mut paths = opt.files.clone();
+            if let Some(list) = &opt.files_from {
+                let list = if list == Path::new("-") {
+                    io::read_to_string(io::stdin())?
+                } else {
+                    read_to_string(list)?
+                };
+                paths.extend(file_list(&list));
             }
-            Error::RuffParse(errors) => {
-                writeln!(opt.output, "Failed to parse Python into AST:")?;
-                for error in errors {
-                    write!(opt.output, "{}", error)?;
+            let files = python_files(&paths, &config)?;
+            match &opt.changed_since {
+                Some(revision) => {
+                    let changed = if revision == "-" {
+                        io::read_to_string(io::stdin())?
+                    } else {
+                        git_changed_files(revision)?
+                    };
+                    with_dependents(&files, &file_list(&changed), config.clone())
                 }
+                None => files,
             }
-        },
+        }
+    };
+
+    if files.is_empty() {
+        if !opt.quiet {
+            writeln!(opt.output, "No Python files found")?;
+        }
+        return Ok(());
+    }
+    let results = check_files(&files, config, opt.profile);
+    if opt.emit_type_map.is_some() {
+        writeln!(opt.output, "[")?;
+        for (i, result) in results.iter().enumerate() {
+            opt.output.write_all(&result.output)?;
+            writeln!(
+                opt.output,
+                "{}",
+                if i + 1 < results.len() { "," } else { "" }
+            )?;
+        }
+        writeln!(opt.output, "]")?;
+        return Ok(());
+    }
+    let (mut error_count, mut warning_count) = (0, 0);
+    for result in &results {
+        opt.output.write_all(&result.output)?;
+        error_count += result.errors;
+        warning_count += result.warnings;
+    }
+    if results.iter().any(|r| r.checked) && !opt.quiet {
+        let found = match (error_count, warning_count) {
+            (0, 0) => "No errors found".to_owned(),
+            (errors, 0) => format!("Found {} errors", errors),
+            (errors, warnings) => format!("Found {} errors, {} warnings", errors, warnings),
+        };
+        match files.len() {
+            1 => writeln!(opt.output, "{}", found)?,
+            n => writeln!(opt.output, "{} in {} files", found, n)?,
+        }
+    }
+    if opt.profile {
+        if let Some(peak) = peak_memory_kb() {
+            writeln!(opt.output, "  {:<24}{:>9} kB", "peak memory", peak)?;
+        }
+    }
+    if results.iter().any(|r| r.failed) {
+        opt.output.flush()?;
+        std::process::exit(1);
     }
 
     Ok(())
@@ -0,0 +1,303 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Finding the modules of the project next to the checked file and loading the types of their
+//! global variables.
+
+use std::{
+    cell::RefCell,
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::{
+    module_globals, scope::ScopedType, state::Info, stubs::bundled_stub, symbol::Symbol, Config,
+};
+
+pub type Globals = HashMap<Symbol, ScopedType>;
+
+/// The modules a module imported and their interface hashes when it was checked.
+type Dependencies = Vec<(PathBuf, u64)>;
+
+/// A checked module, with the interface hashes of the modules it imported when it was checked.
+struct Loaded {
+    content: u64,
+    dependencies: Dependencies,
+    globals: Globals,
+    interface: u64,
+}
+
+/// Number of separately locked parts of the cache, so threads checking different files rarely
+/// wait for each other.
+const SHARDS: usize = 16;
+
+/// The last checked version of every module loaded so far, shared by every check in the process.
+///
+/// Modules are stored by absolute path. Invalidating a module makes the next import check it again,
+/// and the modules importing it are checked again too if that changes its interface.
+pub struct ModuleCache {
+    shards: [Mutex<HashMap<PathBuf, Loaded>>; SHARDS],
+}
+
+impl ModuleCache {
+    fn shard(&self, path: &Path) -> (PathBuf, &Mutex<HashMap<PathBuf, Loaded>>) {
+        let path = absolute(path);
+        let shard = &self.shards[hash_of(&path) as usize % SHARDS];
+        (path, shard)
+    }
+
+    /// The dependencies, globals and interface hash of the module if it was checked with the
+    /// same content.
+    fn get(&self, path: &Path, content: u64) -> Option<(Dependencies, (Globals, u64))> {
+        let (path, shard) = self.shard(path);
+        let shard = shard.lock().unwrap();
+        let loaded = shard
+            .get(&path)
+            .filter(|loaded| loaded.content == content)?;
+        let globals = (loaded.globals.clone(), loaded.interface);
+        Some((loaded.dependencies.clone(), globals))
+    }
+
+    fn insert(&self, path: &Path, loaded: Loaded) {
+        let (path, shard) = self.shard(path);
+        shard.lock().unwrap().insert(path, loaded);
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        let (path, shard) = self.shard(path);
+        shard.lock().unwrap().contains_key(&path)
+    }
+
+    /// Forget the module, for when it changed in a way its content hash can't see, like a stub
+    /// path or the config changing.
+    pub fn invalidate(&self, path: &Path) {
+        let (path, shard) = self.shard(path);
+        shard.lock().unwrap().remove(&path);
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.lock().unwrap().clear();
+        }
+    }
+
+    /// The modules in the cache that import one of the files, directly or through other modules,
+    /// as absolute paths. The files themselves are included.
+    pub fn dependents(&self, files: &[PathBuf]) -> HashSet<PathBuf> {
+        let mut importers: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for shard in &self.shards {
+            for (path, loaded) in shard.lock().unwrap().iter() {
+                for (dependency, _) in &loaded.dependencies {
+                    importers
+                        .entry(absolute(dependency))
+                        .or_default()
+                        .push(path.clone());
+                }
+            }
+        }
+
+        let mut found = HashSet::new();
+        let mut pending: Vec<PathBuf> = files.iter().map(|f| absolute(f)).collect();
+        while let Some(path) = pending.pop() {
+            if let Some(importers) = importers.get(&path) {
+                pending.extend(importers.iter().filter(|i| !found.contains(*i)).cloned());
+            }
+            found.insert(path);
+        }
+        found
+    }
+}
+
+static CACHE: OnceLock<ModuleCache> = OnceLock::new();
+
+/// The module cache of the process.
+pub fn module_cache() -> &'static ModuleCache {
+    CACHE.get_or_init(|| ModuleCache {
+        shards: std::array::from_fn(|_| Mutex::default()),
+    })
+}
+
+/// The files that changed and the files importing them, directly or through other modules, in the
+/// order of `files`.
+///
+/// Every file is loaded into the module cache first so the imports between them are known, the
+/// modules they import are found the same way as when checking them.
+pub fn with_dependents(
+    files: &[PathBuf],
+    changed: &[PathBuf],
+    config: Arc<Config>,
+) -> Vec<PathBuf> {
+    for file in files {
+        let info = Info::with_config(Arc::new(file.clone()), Arc::default(), config.clone());
+        load_globals(&info, file);
+    }
+    let dependents = module_cache().dependents(changed);
+    files
+        .iter()
+        .filter(|f| dependents.contains(&absolute(f)))
+        .cloned()
+        .collect()
+}
+
+/// A module being checked, collecting the modules it imports.
+struct Loading {
+    path: PathBuf,
+    dependencies: Dependencies,
+}
+
+thread_local! {
+    /// Modules being checked on this thread, an import cycle gets an empty module instead of
+    /// checking the same files forever.
+    static LOADING: RefCell<Vec<Loading>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Pops the module on top of LOADING when dropped, so a panic while checking it, caught by the
+/// corpus or conformance runner, doesn't leave it there to look like an import cycle later.
+struct LoadingGuard;
+
+impl Drop for LoadingGuard {
+    fn drop(&mut self) {
+        LOADING.with(|l| l.borrow_mut().pop());
+    }
+}
+
+/// The path the cache stores the module under.
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_owned())
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A hash of the names and types a module exports, which only changes when something importing
+/// the module could see a difference.
+fn interface_hash(globals: &Globals) -> u64 {
+    let mut names: Vec<_> = globals
+        .iter()
+        .map(|(name, scoped)| {
+            (
+                name.as_str(),
+                format!("{:?}", scoped.typ),
+                &scoped.docstring,
+            )
+        })
+        .collect();
+    names.sort();
+    hash_of(names)
+}
+
+/// The file a module would be loaded from, looking in the directory of the importing file.
+/// Stubs are preferred over the source and modules over packages, like Python does.
+fn find_module(importer: &Path, module: &str) -> Option<PathBuf> {
+    let mut base = importer.parent().unwrap_or(Path::new("")).to_owned();
+    base.extend(module.split('.'));
+    let candidates = [
+        base.with_extension("pyi"),
+        base.with_extension("py"),
+        base.join("__init__.pyi"),
+        base.join("__init__.py"),
+    ];
+    candidates.into_iter().find(|c| c.is_file())
+}
+
+/// The global variables of a module in the project, None if there is no file for it.
+pub fn local_module(info: &Info, module: &str) -> Option<Globals> {
+    load_globals(info, &find_module(&info.file_name, module)?)
+}
+
+/// Check the file once and return its global variables, reusing them while the file is unchanged.
+pub fn load_globals(info: &Info, path: &Path) -> Option<Globals> {
+    load(info, path).map(|(globals, _)| globals)
+}
+
+/// The globals of the module and its interface hash.
+///
+/// A module is only checked again if its content changed or the interface of a module it imports
+/// changed, so changing the body of a function doesn't check every module depending on it again.
+fn load(info: &Info, path: &Path) -> Option<(Globals, u64)> {
+    let bytes = match bundled_stub(path) {
+        Some(source) => source.as_bytes().to_vec(),
+        None => fs::read(path).ok()?,
+    };
+    let content = hash_of(&bytes);
+    let is_cycle = LOADING.with(|l| l.borrow().iter().any(|m| m.path == path));
+    if is_cycle {
+        return Some((Globals::new(), 0));
+    }
+
+    LOADING.with(|l| {
+        l.borrow_mut().push(Loading {
+            path: path.to_owned(),
+            dependencies: vec![],
+        })
+    });
+    let guard = LoadingGuard;
+    let cached = module_cache().get(path, content);
+    let loaded = match cached {
+        Some((dependencies, globals))
+            if dependencies
+                .iter()
+                .all(|(dep, interface)| load(info, dep).is_some_and(|(_, i)| i == *interface)) =>
+        {
+            Some(globals)
+        }
+        _ => check(info, path, bytes, content),
+    };
+    // Off the stack before it's recorded as a dependency of the module importing it
+    drop(guard);
+
+    if let Some((_, interface)) = &loaded {
+        LOADING.with(|l| {
+            if let Some(importer) = l.borrow_mut().last_mut() {
+                importer.dependencies.push((path.to_owned(), *interface));
+            }
+        });
+    }
+    loaded
+}
+
+/// Check the module on top of LOADING and store the result.
+fn check(info: &Info, path: &Path, bytes: Vec<u8>, content: u64) -> Option<(Globals, u64)> {
+    LOADING.with(|l| l.borrow_mut().last_mut().unwrap().dependencies.clear());
+    let globals = module_globals(path.to_owned(), bytes, info.config.clone());
+    let dependencies =
+        LOADING.with(|l| std::mem::take(&mut l.borrow_mut().last_mut().unwrap().dependencies));
+
+    // Where names were declared in the other file means nothing in this one
+    let globals: Globals = globals
+        .into_iter()
+        .map(|(name, scoped)| {
+            let docstring = scoped.docstring.clone();
+            (name, ScopedType::new(scoped.typ).with_docstring(docstring))
+        })
+        .collect();
+    let interface = interface_hash(&globals);
+    module_cache().insert(
+        path,
+        Loaded {
+            content,
+            dependencies,
+            globals: globals.clone(),
+            interface,
+        },
+    );
+    Some((globals, interface))
+}
@@ -0,0 +1,328 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Finding the files of a project to check.
+
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::Config;
+
+/// Directories that contain installed or generated code rather than the project's own.
+const DEFAULT_EXCLUDES: [&str; 6] = [
+    "__pycache__",
+    "build",
+    "dist",
+    "node_modules",
+    "site-packages",
+    "venv",
+];
+
+/// One line of a `.gitignore` file, or an exclude glob from the config.
+#[derive(Debug)]
+struct IgnorePattern {
+    glob: String,
+    negated: bool,
+    dir_only: bool,
+    /// Patterns with a `/` are matched against the path from the base directory, others against
+    /// the file name in any directory.
+    anchored: bool,
+}
+
+impl IgnorePattern {
+    fn parse(line: &str) -> Option<IgnorePattern> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        Some(IgnorePattern {
+            glob: line.trim_start_matches('/').to_owned(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let target = if self.anchored {
+            relative
+        } else {
+            relative.rsplit('/').next().unwrap_or(relative)
+        };
+        glob_match(self.glob.as_bytes(), target.as_bytes())
+    }
+}
+
+/// Match a path against a glob, where `*` and `?` don't match `/` and `**` matches any number of
+/// directories.
+fn glob_match(glob: &[u8], path: &[u8]) -> bool {
+    match glob {
+        [] => path.is_empty(),
+        [b'*', b'*'] => true,
+        [b'*', b'*', b'/', rest @ ..] => {
+            glob_match(rest, path)
+                || (0..path.len()).any(|i| path[i] == b'/' && glob_match(rest, &path[i + 1..]))
+        }
+        [b'*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != b'/')
+            .any(|i| glob_match(rest, &path[i..])),
+        [b'?', rest @ ..] => {
+            matches!(path.first(), Some(c) if *c != b'/') && glob_match(rest, &path[1..])
+        }
+        [b'[', class @ ..] if class.contains(&b']') => {
+            let end = class.iter().position(|c| *c == b']').unwrap();
+            let (negated, set) = match &class[..end] {
+                [b'!' | b'^', set @ ..] => (true, set),
+                set => (false, set),
+            };
+            let Some(&c) = path.first() else {
+                return false;
+            };
+            let (mut i, mut in_set) = (0, false);
+            while i < set.len() {
+                if set.get(i + 1) == Some(&b'-') && i + 2 < set.len() {
+                    in_set |= (set[i]..=set[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    in_set |= set[i] == c;
+                    i += 1;
+                }
+            }
+            in_set != negated && c != b'/' && glob_match(&class[end + 1..], &path[1..])
+        }
+        [b'\\', c, rest @ ..] | [c, rest @ ..] => {
+            path.first() == Some(c) && glob_match(rest, &path[1..])
+        }
+    }
+}
+
+/// The patterns of a `.gitignore` or the exclude globs, with the directory they are relative to.
+struct IgnoreSet {
+    base: PathBuf,
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreSet {
+    fn read(dir: &Path) -> Option<IgnoreSet> {
+        let content = fs::read_to_string(dir.join(".gitignore")).ok()?;
+        Some(IgnoreSet {
+            base: dir.to_owned(),
+            patterns: content.lines().filter_map(IgnorePattern::parse).collect(),
+        })
+    }
+
+    /// Whether the last pattern of the set that matches the path ignores it, `ignored` if none
+    /// match.
+    fn is_ignored(&self, path: &Path, is_dir: bool, mut ignored: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return ignored;
+        };
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        for pattern in &self.patterns {
+            if pattern.matches(&relative, is_dir) {
+                ignored = !pattern.negated;
+            }
+        }
+        ignored
+    }
+}
+
+/// Everything that decides which files of a directory are skipped, the `.gitignore` files of the
+/// directories being walked are added and removed as the walk enters and leaves them.
+struct Ignores {
+    gitignore: bool,
+    sets: Vec<IgnoreSet>,
+    /// The exclude globs of the config, checked apart from `sets` so a `!pattern` in a
+    /// `.gitignore` can't include an excluded file again.
+    exclude: IgnoreSet,
+}
+
+impl Ignores {
+    /// The last pattern that matches decides, so a later `!pattern` can include a file again.
+    /// Files matching the exclude globs are always skipped.
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.exclude.is_ignored(path, is_dir, false)
+            || self
+                .sets
+                .iter()
+                .fold(false, |ignored, set| set.is_ignored(path, is_dir, ignored))
+    }
+}
+
+/// Whether the file, or a directory it is in, matches the glob. Relative globs are matched against
+/// the path from the working directory.
+pub(crate) fn path_matches(glob: &str, file: &Path) -> bool {
+    let Some(pattern) = IgnorePattern::parse(glob) else {
+        return false;
+    };
+    let Ok(absolute) = std::path::absolute(file) else {
+        return false;
+    };
+    let relative = match std::env::current_dir() {
+        Ok(dir) => absolute.strip_prefix(dir).unwrap_or(&absolute).to_owned(),
+        Err(_) => absolute,
+    };
+    let components: Vec<_> = relative
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect();
+    (1..=components.len())
+        .any(|end| pattern.matches(&components[..end].join("/"), end < components.len()))
+}
+
+/// The `.gitignore` files above a directory up to the root of its git repository, outermost
+/// first. Empty if the directory isn't in a git repository.
+fn parent_gitignores(dir: &Path) -> Vec<IgnoreSet> {
+    if dir.join(".git").exists() {
+        return vec![];
+    }
+    let mut sets = vec![];
+    for parent in dir.ancestors().skip(1) {
+        sets.extend(IgnoreSet::read(parent));
+        if parent.join(".git").exists() {
+            sets.reverse();
+            return sets;
+        }
+    }
+    vec![]
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+fn is_python(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "py" || ext == "pyi")
+}
+
+/// Whether the directory is a virtualenv or one of the DEFAULT_EXCLUDES.
+fn is_excluded_dir(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| DEFAULT_EXCLUDES.iter().any(|e| name == *e))
+        || path.join("pyvenv.cfg").exists()
+}
+
+/// Walk a directory, `absolute` is the same directory as an absolute path that the ignore
+/// patterns are matched against.
+fn walk(
+    dir: &Path,
+    absolute: &Path,
+    ignores: &mut Ignores,
+    files: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let gitignore = ignores
+        .gitignore
+        .then(|| IgnoreSet::read(absolute))
+        .flatten();
+    let pushed = gitignore.is_some();
+    ignores.sets.extend(gitignore);
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        if is_hidden(&path) || ignores.is_ignored(&absolute.join(entry.file_name()), is_dir) {
+            continue;
+        }
+        if is_dir {
+            if !is_excluded_dir(&path) {
+                walk(&path, &absolute.join(entry.file_name()), ignores, files)?;
+            }
+        } else if is_python(&path) {
+            files.push(path);
+        }
+    }
+
+    if pushed {
+        ignores.sets.pop();
+    }
+    Ok(())
+}
+
+/// The Python files to check for the paths given on the command line.
+///
+/// Files are always included, directories are searched recursively for `.py` and `.pyi` files,
+/// skipping hidden files and directories like `.venv` and `.git`, virtualenvs, build output, files
+/// matching the exclude globs of the config and, unless turned off, files ignored by git. Every
+/// file is only included once and the files of a directory are sorted.
+pub fn python_files(paths: &[PathBuf], config: &Config) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for path in paths {
+        if path.is_dir() {
+            let absolute = std::path::absolute(path)?;
+            let mut ignores = Ignores {
+                gitignore: config.respect_gitignore,
+                sets: vec![],
+                exclude: IgnoreSet {
+                    base: absolute.clone(),
+                    patterns: config
+                        .exclude
+                        .iter()
+                        .filter_map(|glob| IgnorePattern::parse(glob))
+                        .collect(),
+                },
+            };
+            if config.respect_gitignore {
+                ignores.sets = parent_gitignores(&absolute);
+            }
+
+            let start = files.len();
+            walk(path, &absolute, &mut ignores, &mut files)?;
+            files[start..].sort();
+        } else {
+            files.push(path.clone());
+        }
+    }
+    let mut seen = HashSet::new();
+    files.retain(|f| seen.insert(f.clone()));
+    Ok(files)
+}
+
+/// The files in a list with one path per line, like `git diff --name-only` prints.
+///
+/// Blank lines are skipped and so are files that don't exist, since a list of changed files also
+/// has the deleted ones.
+pub fn file_list(list: &str) -> Vec<PathBuf> {
+    list.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .filter(|path| path.exists())
+        .collect()
+}
@@ -15,12 +15,23 @@
 
 use std::{collections::HashMap, iter, sync::Arc};
 
+use ruff_text_size::TextRange;
+
+use crate::builtins::{builtins, Builtins};
+use crate::symbol::Symbol;
 use crate::types::Type;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ScopedType {
     pub typ: Type,
     pub is_locked: bool,
+    /// Where the annotation that locked this variable was written
+    pub declared_at: Option<TextRange>,
+    /// Where the name was last bound, such as an assignment target or a function name
+    pub defined_at: Option<TextRange>,
+    /// The module the name was imported from, if it came from an import
+    pub imported_from: Option<Symbol>,
+    pub docstring: Option<Arc<String>>,
 }
 
 impl ScopedType {
@@ -28,15 +39,35 @@ impl ScopedType {
         ScopedType {
             typ,
             is_locked: false,
+            declared_at: None,
+            defined_at: None,
+            imported_from: None,
+            docstring: None,
         }
     }
 
-    pub fn locked(typ: Type) -> ScopedType {
+    pub fn locked(typ: Type, declared_at: TextRange) -> ScopedType {
         ScopedType {
-            typ,
             is_locked: true,
+            declared_at: Some(declared_at),
+            ..Self::new(typ)
         }
     }
+
+    pub fn with_definition(mut self, defined_at: TextRange) -> ScopedType {
+        self.defined_at = Some(defined_at);
+        self
+    }
+
+    pub fn with_import(mut self, module: Symbol) -> ScopedType {
+        self.imported_from = Some(module);
+        self
+    }
+
+    pub fn with_docstring(mut self, docstring: Option<Arc<String>>) -> ScopedType {
+        self.docstring = docstring;
+        self
+    }
 }
 
 impl From<Type> for ScopedType {
@@ -45,13 +76,24 @@ impl From<Type> for ScopedType {
     }
 }
 
-type ScopeMap = HashMap<Arc<String>, ScopedType>;
+type ScopeMap = HashMap<Symbol, ScopedType>;
+
+/// A variable, all assignments to the same name in the same scope bind the same variable.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Binding {
+    /// Unique id of the scope within the file, 0 is the global scope
+    pub scope: usize,
+    pub name: Symbol,
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Scope {
-    // builtin: Arc<HashMap<String, ScopedType>>,
+    builtin: Builtins,
     global: ScopeMap,
     scopes: Vec<ScopeMap>,
+    /// The id of each scope in `scopes`
+    ids: Vec<usize>,
+    next_id: usize,
 }
 
 impl Default for Scope {
@@ -62,9 +104,15 @@ impl Default for Scope {
 
 impl Scope {
     pub fn new() -> Scope {
+        Scope::with_builtins(builtins())
+    }
+    pub fn with_builtins(builtin: Builtins) -> Scope {
         Scope {
+            builtin,
             global: HashMap::new(),
             scopes: Vec::new(),
+            ids: Vec::new(),
+            next_id: 1,
         }
     }
     fn top_scope(&self) -> &ScopeMap {
@@ -73,23 +121,25 @@ impl Scope {
     fn top_scope_mut(&mut self) -> &mut ScopeMap {
         self.scopes.last_mut().unwrap_or(&mut self.global)
     }
-    fn all_scopes(
-        &self,
-    ) -> iter::Chain<iter::Rev<std::slice::Iter<ScopeMap>>, iter::Once<&ScopeMap>> {
-        self.scopes.iter().rev().chain(iter::once(&self.global))
+    fn all_scopes(&self) -> impl Iterator<Item = &ScopeMap> {
+        self.scopes
+            .iter()
+            .rev()
+            .chain(iter::once(&self.global))
+            .chain(iter::once(self.builtin.as_ref()))
     }
-    pub fn get_top_ref<'a>(&'a self, name: &Arc<String>) -> Option<&'a ScopedType> {
+    pub fn get_top_ref<'a>(&'a self, name: &Symbol) -> Option<&'a ScopedType> {
         self.top_scope().get(name)
     }
     /// Get a variable from the top scope or None if that scope doesn't contain the provided
     /// variable
-    pub fn get_top(&self, name: &Arc<String>) -> Option<ScopedType> {
+    pub fn get_top(&self, name: &Symbol) -> Option<ScopedType> {
         self.get_top_ref(name).cloned()
     }
-    pub fn get_top_is_locked(&self, name: &Arc<String>) -> Option<bool> {
+    pub fn get_top_is_locked(&self, name: &Symbol) -> Option<bool> {
         self.get_top_ref(name).map(|i| i.is_locked)
     }
-    pub fn get_ref<'a>(&'a self, name: &Arc<String>) -> Option<&'a ScopedType> {
+    pub fn get_ref<'a>(&'a self, name: &Symbol) -> Option<&'a ScopedType> {
         for scope in self.all_scopes() {
             let maybe_type = scope.get(name);
             if let Some(typ) = maybe_type {
@@ -100,19 +150,80 @@ impl Scope {
         None
     }
     /// Get a variable from any scope
-    pub fn get(&self, name: &Arc<String>) -> Option<ScopedType> {
+    pub fn get(&self, name: &Symbol) -> Option<ScopedType> {
         self.get_ref(name).cloned()
     }
-    pub fn get_is_locked(&self, name: &Arc<String>) -> Option<bool> {
+    pub fn get_is_locked(&self, name: &Symbol) -> Option<bool> {
         self.get_ref(name).map(|i| i.is_locked)
     }
-    pub fn set(&mut self, name: Arc<String>, value: impl Into<ScopedType>) {
+    /// All variables visible from the top scope, inner scopes shadowing outer ones
+    pub fn visible(&self) -> Vec<(Symbol, ScopedType)> {
+        let mut visible: HashMap<&Symbol, &ScopedType> = HashMap::new();
+        for scope in self.all_scopes() {
+            for (name, typ) in scope.iter() {
+                visible.entry(name).or_insert(typ);
+            }
+        }
+        visible
+            .into_iter()
+            .map(|(name, typ)| (name.clone(), typ.clone()))
+            .collect()
+    }
+    /// Variables set in the top scope, sorted by name
+    pub fn locals(&self) -> Vec<(Symbol, ScopedType)> {
+        let mut locals: Vec<(Symbol, ScopedType)> = self
+            .top_scope()
+            .iter()
+            .map(|(name, typ)| (name.clone(), typ.clone()))
+            .collect();
+        locals.sort_by(|(a, _), (b, _)| a.cmp(b));
+        locals
+    }
+    /// The variable a name refers to, builtins aren't bindings in the file
+    pub fn binding(&self, name: &Symbol) -> Option<Binding> {
+        let scope = self
+            .scopes
+            .iter()
+            .zip(self.ids.iter())
+            .rev()
+            .chain(iter::once((&self.global, &0)))
+            .find(|(scope, _)| scope.contains_key(name))
+            .map(|(_, id)| *id)?;
+        Some(Binding {
+            scope,
+            name: name.clone(),
+        })
+    }
+    /// The variable a name set in the top scope binds
+    pub fn top_binding(&self, name: &Symbol) -> Binding {
+        Binding {
+            scope: self.ids.last().copied().unwrap_or(0),
+            name: name.clone(),
+        }
+    }
+    /// The variables of the top scope, to check several branches from the same starting point
+    pub fn top_variables(&self) -> ScopeMap {
+        self.top_scope().clone()
+    }
+    pub fn set_top_variables(&mut self, variables: ScopeMap) {
+        *self.top_scope_mut() = variables;
+    }
+    pub fn set(&mut self, name: Symbol, value: impl Into<ScopedType>) {
         self.top_scope_mut().insert(name, value.into());
     }
-    pub fn add_scope(&mut self) {
-        self.scopes.push(HashMap::new())
+    /// Unbind a name in the top scope, like `del name` does
+    pub fn remove(&mut self, name: &Symbol) -> Option<ScopedType> {
+        self.top_scope_mut().remove(name)
     }
-    pub fn pop_scope(&mut self) {
-        assert_ne!(self.scopes.pop(), None)
+    pub fn add_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+        self.ids.push(self.next_id);
+        self.next_id += 1;
+    }
+    /// Remove the top scope, returning the variables that were set in it
+    pub fn pop_scope(&mut self) -> HashMap<Symbol, ScopedType> {
+        let popped = self.scopes.pop().expect("Can't pop the global scope");
+        self.ids.pop();
+        popped
     }
 }
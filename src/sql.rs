@@ -0,0 +1,95 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Hooks for checking literal SQL passed to database calls like `cursor.execute(sql, params)`.
+
+use std::fmt;
+
+use ruff_text_size::TextRange;
+
+use crate::{
+    diagnostics::{
+        custom::{SqlParameterTypeDiag, SqlPlaceholderCountDiag},
+        Diag,
+    },
+    types::{is_subtype, union, Type},
+};
+
+/// A call passing a literal SQL query, and the parameters for it if there are any.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SqlQuery<'a> {
+    pub sql: &'a str,
+    pub range: TextRange,
+    pub parameters: Option<(&'a Type, TextRange)>,
+}
+
+/// Checks the SQL queries in a file, configured with [`Config::sql_validators`].
+///
+/// [`Config::sql_validators`]: crate::Config::sql_validators
+pub trait SqlValidator: fmt::Debug + Send + Sync {
+    /// Check a query, returning the diagnostics to report for it.
+    fn validate(&self, query: &SqlQuery) -> Vec<Box<dyn Diag>>;
+}
+
+/// Checks that a tuple of parameters matches the number of `?` or `%s` placeholders in the
+/// query, and that every parameter is a type database drivers can bind.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlaceholderValidator;
+
+impl SqlValidator for PlaceholderValidator {
+    fn validate(&self, query: &SqlQuery) -> Vec<Box<dyn Diag>> {
+        let placeholders = count_placeholders(query.sql);
+        let (parameters, parameters_range) = match query.parameters {
+            Some((Type::Tuple(types), range)) => (types.as_slice(), range),
+            Some(_) => return vec![],
+            None => (&[][..], query.range),
+        };
+        let mut diags: Vec<Box<dyn Diag>> = vec![];
+        if placeholders != parameters.len() {
+            diags.push(
+                SqlPlaceholderCountDiag::new(placeholders, parameters.len(), parameters_range)
+                    .into(),
+            );
+        }
+        let bindable = union(vec![Type::String, Type::Float, Type::None]);
+        for typ in parameters {
+            if !is_subtype(typ, &bindable) {
+                diags.push(SqlParameterTypeDiag::new(typ.clone(), parameters_range).into());
+            }
+        }
+        diags
+    }
+}
+
+/// Number of `?` and `%s` placeholders outside of quoted strings in the query.
+fn count_placeholders(sql: &str) -> usize {
+    let mut count = 0;
+    let mut quote = None;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '?') => count += 1,
+            (None, '%') if chars.peek() == Some(&'s') => {
+                chars.next();
+                count += 1;
+            }
+            (None, _) => {}
+        }
+    }
+    count
+}
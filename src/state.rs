@@ -14,25 +14,31 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     fmt, hash, io,
-    os::unix::ffi::OsStrExt,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use clio::Output;
 use ruff_text_size::TextRange;
 
 use crate::{
+    analysis::Analysis,
+    config::{Config, PathRule},
     diagnostics::{Diag, Diagnostic, DiagnosticType},
-    types::Type,
+    project::path_matches,
+    scope::ScopedType,
+    symbol::Symbol,
+    types::{with_union_style, Type},
 };
 
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct StatementSynthData {
     pub returns: Option<StatementSynthDataReturn>,
     pub partial_list: VecDeque<PartialItem>,
+    /// The variables at each `break` of the loops being checked, innermost loop last.
+    pub breaks: Vec<Vec<HashMap<Symbol, ScopedType>>>,
 }
 
 impl StatementSynthData {
@@ -40,6 +46,7 @@ impl StatementSynthData {
         StatementSynthData {
             partial_list: VecDeque::new(),
             returns,
+            breaks: vec![],
         }
     }
 }
@@ -47,11 +54,11 @@ impl StatementSynthData {
 #[derive(Clone, Debug, PartialEq)]
 pub struct PartialItem {
     pub path: Arc<PathBuf>,
-    pub name: Arc<String>,
+    pub name: Symbol,
 }
 
 impl PartialItem {
-    pub fn new(path: Arc<PathBuf>, name: Arc<String>) -> PartialItem {
+    pub fn new(path: Arc<PathBuf>, name: Symbol) -> PartialItem {
         PartialItem { path, name }
     }
 }
@@ -99,12 +106,42 @@ impl Reporter {
         errors.extend(new_errors.into());
     }
 
-    pub fn flush(&self, info: &Info, output: &mut Output) -> io::Result<()> {
-        let errors = self.0.lock().unwrap();
-        for e in errors.iter() {
-            e.write(output, &info.file_name, &info.file_content)?
+    /// Drop the diagnostics with one of the codes that are inside the range.
+    pub fn suppress(&self, range: TextRange, codes: &[String]) {
+        let mut errors = self.0.lock().unwrap();
+        errors.retain(|e| {
+            !(range.contains_range(e.range())
+                && e.code().is_some_and(|code| codes.iter().any(|c| c == code)))
+        });
+    }
+
+    /// Drop the diagnostics the path rules of the config ignore for the file.
+    pub fn apply_path_rules(&self, file: &Path, rules: &[PathRule]) {
+        let rules: Vec<&PathRule> = rules
+            .iter()
+            .filter(|rule| path_matches(&rule.glob, file))
+            .collect();
+        if rules.is_empty() {
+            return;
         }
-        Ok(())
+        let mut errors = self.0.lock().unwrap();
+        errors.retain(|e| {
+            !rules.iter().any(|rule| {
+                rule.codes.is_empty()
+                    || e.code()
+                        .is_some_and(|code| rule.codes.iter().any(|c| c == code))
+            })
+        });
+    }
+
+    pub fn flush(&self, info: &Info, output: &mut dyn io::Write) -> io::Result<()> {
+        let errors = self.0.lock().unwrap();
+        with_union_style(info.config.union_style, || {
+            for e in errors.iter() {
+                e.write(output, &info.file_name, &info.file_content)?
+            }
+            Ok(())
+        })
     }
     pub fn len(&self) -> usize {
         let errors = self.0.lock().unwrap();
@@ -118,16 +155,113 @@ impl Reporter {
     }
 }
 
+/// A phase of checking a file that is timed by the [`Profiler`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Phase {
+    Parsing,
+    Annotations,
+    Checking,
+    Rendering,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [
+        Phase::Parsing,
+        Phase::Annotations,
+        Phase::Checking,
+        Phase::Rendering,
+    ];
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match *self {
+            Self::Parsing => "parsing",
+            Self::Annotations => "annotation evaluation",
+            Self::Checking => "body checking",
+            Self::Rendering => "diagnostic rendering",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Wall time spent in each phase of checking a file.
+///
+/// Annotations are evaluated while checking the body, so that time is subtracted from the body
+/// checking time when reading it back.
+#[derive(Clone, Default)]
+pub struct Profiler(Arc<Mutex<Vec<(Phase, Duration)>>>);
+
+impl fmt::Debug for Profiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Profiler")
+    }
+}
+
+impl Profiler {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn time<T>(&self, phase: Phase, f: impl FnOnce() -> T) -> T {
+        let start = std::time::Instant::now();
+        let result = f();
+        self.add(phase, start.elapsed());
+        result
+    }
+    /// There is no clock in the browser without going through JavaScript, so nothing is timed.
+    #[cfg(target_arch = "wasm32")]
+    pub fn time<T>(&self, _phase: Phase, f: impl FnOnce() -> T) -> T {
+        f()
+    }
+    pub fn add(&self, phase: Phase, duration: Duration) {
+        let mut timings = self.0.lock().unwrap();
+        timings.push((phase, duration));
+    }
+    fn total(&self, phase: Phase) -> Duration {
+        let timings = self.0.lock().unwrap();
+        timings
+            .iter()
+            .filter(|(p, _)| *p == phase)
+            .map(|(_, d)| *d)
+            .sum()
+    }
+    pub fn get(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Checking => self
+                .total(Phase::Checking)
+                .saturating_sub(self.total(Phase::Annotations)),
+            phase => self.total(phase),
+        }
+    }
+
+    pub fn write(&self, info: &Info, output: &mut impl io::Write) -> io::Result<()> {
+        writeln!(output, "Profile for {}:", info.file_name.display())?;
+        let mut total = Duration::ZERO;
+        for phase in Phase::ALL {
+            let duration = self.get(phase);
+            total += duration;
+            writeln!(output, "  {:<24}{:>12.3?}", phase.to_string(), duration)?;
+        }
+        writeln!(output, "  {:<24}{:>12.3?}", "total", total)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Info {
     pub file_name: Arc<PathBuf>,
     pub file_content: Arc<String>,
     pub reporter: Reporter,
+    pub profiler: Profiler,
+    pub analysis: Analysis,
+    pub config: Arc<Config>,
 }
 
 impl hash::Hash for Info {
     fn hash<H: hash::Hasher>(&self, state: &mut H) {
-        state.write(self.file_name.as_os_str().as_bytes());
+        #[cfg(unix)]
+        state.write(std::os::unix::ffi::OsStrExt::as_bytes(
+            self.file_name.as_os_str(),
+        ));
+        #[cfg(not(unix))]
+        state.write(self.file_name.to_string_lossy().as_bytes());
         state.write(self.file_content.as_bytes());
     }
 }
@@ -145,11 +279,34 @@ impl Default for Info {
 }
 
 impl Info {
+    /// Name of the module being checked, taken from the file name
+    pub fn module_name(&self) -> Symbol {
+        let stem = self.file_name.file_stem().unwrap_or_default();
+        Symbol::new(&stem.to_string_lossy())
+    }
+    /// Whether strict mode is on for this file, a `# pycavalry: no-strict` line turns it off.
+    pub fn is_strict(&self) -> bool {
+        self.config.strict
+            && !self
+                .file_content
+                .lines()
+                .any(|l| l.trim() == "# pycavalry: no-strict")
+    }
     pub fn new(file_name: Arc<PathBuf>, file_content: Arc<String>) -> Self {
+        Self::with_config(file_name, file_content, Arc::default())
+    }
+    pub fn with_config(
+        file_name: Arc<PathBuf>,
+        file_content: Arc<String>,
+        config: Arc<Config>,
+    ) -> Self {
         Info {
             file_name,
             file_content,
             reporter: Reporter::default(),
+            profiler: Profiler::default(),
+            analysis: Analysis::default(),
+            config,
         }
     }
 }
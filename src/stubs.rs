@@ -0,0 +1,90 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Loading the types of modules from `.pyi` stubs, the ones given in the config and the standard
+//! library stubs from typeshed.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    builtins::{builtins, Builtins},
+    modules::{load_globals, Globals},
+    state::Info,
+};
+
+// The standard library stubs of the typeshed submodule, embedded by build.rs as the `.pyi` files
+// by their path in typeshed's stdlib directory, sorted by path
+include!(concat!(env!("OUT_DIR"), "/bundled_stubs.rs"));
+
+/// Where the bundled stubs appear to be for the module cache, there is no such directory on disk.
+const BUNDLED_DIR: &str = "<typeshed>/stdlib";
+
+/// The source of a bundled stub from the path `bundled_path` gave it.
+pub fn bundled_stub(path: &Path) -> Option<&'static str> {
+    let relative = path
+        .strip_prefix(BUNDLED_DIR)
+        .ok()?
+        .to_str()?
+        .replace('\\', "/");
+    let index = BUNDLED_STUBS
+        .binary_search_by_key(&relative.as_str(), |(name, _)| name)
+        .ok()?;
+    Some(BUNDLED_STUBS[index].1)
+}
+
+/// The path of the bundled stub for a module, if there is one.
+fn bundled_path(module: &str) -> Option<PathBuf> {
+    let base = module.replace('.', "/");
+    [format!("{}.pyi", base), format!("{}/__init__.pyi", base)]
+        .into_iter()
+        .find(|c| {
+            BUNDLED_STUBS
+                .binary_search_by_key(&c.as_str(), |(name, _)| name)
+                .is_ok()
+        })
+        .map(|c| Path::new(BUNDLED_DIR).join(c))
+}
+
+fn find_stub(dir: &Path, module: &str) -> Option<PathBuf> {
+    let mut base = dir.to_owned();
+    base.extend(module.split('.'));
+    [base.with_extension("pyi"), base.join("__init__.pyi")]
+        .into_iter()
+        .find(|c| c.is_file())
+}
+
+/// The global variables of a module from the first stub for it, searching the configured stub
+/// paths before the bundled ones.
+pub fn stub_module(info: &Info, module: &str) -> Option<Globals> {
+    let path = info
+        .config
+        .stub_paths
+        .iter()
+        .find_map(|dir| find_stub(dir, module))
+        .or_else(|| bundled_path(module))?;
+    load_globals(info, &path)
+}
+
+/// The builtins with everything from the builtins stub the hardcoded ones don't have.
+pub fn builtins_with_stubs(info: &Info) -> Builtins {
+    let Some(mut stubs) = stub_module(info, "builtins") else {
+        return builtins();
+    };
+    stubs.extend(builtins().as_ref().clone());
+    Arc::new(stubs)
+}
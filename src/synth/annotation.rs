@@ -13,18 +13,26 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{fmt, sync::Arc};
+use std::fmt;
 
-use ruff_python_ast::{Expr, Number};
+use ruff_python_ast::{Expr, Number, Operator, UnaryOp};
 use ruff_text_size::{Ranged, TextRange};
 
 use crate::{
-    diagnostics::{custom::NotInScopeDiag, Diag, Diagnostic},
-    scope::Scope,
-    state::Info,
-    types::{union, Type, TypeLiteral},
+    analysis::{AnnotatedSite, TokenKind},
+    builtins::builtin_instance,
+    diagnostics::{
+        custom::{NotInModuleDiag, NotInScopeDiag, UnsupportedSyntaxDiag},
+        Diag, Diagnostic,
+    },
+    scope::{Scope, ScopedType},
+    state::{Info, Phase},
+    symbol::Symbol,
+    types::{generic_arity, generic_name, union, Function, Type, TypeLiteral},
 };
 
+use super::{expr_kind, module_attribute};
+
 #[derive(Clone, Debug, PartialEq)]
 enum Annotation {
     Type(RangedType),
@@ -43,16 +51,29 @@ impl Ranged for Annotation {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum PartialAnnotationType {
     Union,
+    Optional,
     Literal,
     Tuple,
+    Callable,
+    /// type[X], the class object of X
+    Type,
+    /// The list of parameter types in Callable
+    Parameters,
+    /// A generic class like list or Mapping, by its canonical name
+    Generic(&'static str),
 }
 
 impl fmt::Display for PartialAnnotationType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let name = match *self {
             Self::Union => "Union",
+            Self::Optional => "Optional",
             Self::Literal => "Literal",
             Self::Tuple => "tuple",
+            Self::Callable => "Callable",
+            Self::Type => "type",
+            Self::Parameters => "parameter list",
+            Self::Generic(name) => name,
         };
         write!(f, "{}", name)
     }
@@ -81,6 +102,17 @@ fn verify_annotation(ann: Annotation) -> Result<Type, Box<dyn Diag>> {
                     .map(verify_annotation)
                     .collect::<Result<Vec<Type>, Box<dyn Diag>>>()?,
             )),
+            PartialAnnotationType::Optional => {
+                let mut arguments = t.arguments.into_iter();
+                let (Some(arg), None) = (arguments.next(), arguments.next()) else {
+                    return Err(Diagnostic::error(
+                        "Optional expects a single type".to_owned(),
+                        t.range,
+                    )
+                    .into());
+                };
+                Ok(union(vec![verify_annotation(arg)?, Type::None]))
+            }
             PartialAnnotationType::Literal => {
                 let mut literals = Vec::with_capacity(t.arguments.len());
                 for arg in t.arguments {
@@ -106,17 +138,145 @@ fn verify_annotation(ann: Annotation) -> Result<Type, Box<dyn Diag>> {
                 }
                 Ok(union(literals))
             }
-            PartialAnnotationType::Tuple => Ok(Type::Tuple(
-                t.arguments
-                    .into_iter()
-                    .map(verify_annotation)
-                    .collect::<Result<Vec<Type>, Box<dyn Diag>>>()?,
-            )),
+            PartialAnnotationType::Tuple => {
+                let is_ellipsis = |arg: &Annotation| {
+                    matches!(
+                        arg,
+                        Annotation::Type(RangedType {
+                            value: Type::Literal(TypeLiteral::EllipsisLiteral),
+                            ..
+                        })
+                    )
+                };
+                match &t.arguments[..] {
+                    // tuple[int, ...] has any number of elements of the same type
+                    [_, ellipsis] if is_ellipsis(ellipsis) => {
+                        let element = t.arguments.into_iter().next().unwrap();
+                        Ok(Type::Generic(
+                            Symbol::new("tuple"),
+                            vec![verify_annotation(element)?],
+                        ))
+                    }
+                    arguments => {
+                        if let Some(ellipsis) = arguments.iter().find(|a| is_ellipsis(a)) {
+                            return Err(Diagnostic::error(
+                                "... is only allowed as the second argument of tuple".to_owned(),
+                                ellipsis.range(),
+                            )
+                            .into());
+                        }
+                        Ok(Type::Tuple(
+                            t.arguments
+                                .into_iter()
+                                .map(verify_annotation)
+                                .collect::<Result<Vec<Type>, Box<dyn Diag>>>()?,
+                        ))
+                    }
+                }
+            }
+            PartialAnnotationType::Callable => {
+                let mut arguments = t.arguments.into_iter();
+                let (Some(params), Some(ret), None) =
+                    (arguments.next(), arguments.next(), arguments.next())
+                else {
+                    return Err(Diagnostic::error(
+                        "Callable expects a list of parameter types and a return type".to_owned(),
+                        t.range,
+                    )
+                    .into());
+                };
+                let params = match params {
+                    Annotation::PartialAnnotation(PartialAnnotation {
+                        annotation: PartialAnnotationType::Parameters,
+                        arguments,
+                        ..
+                    }) => arguments
+                        .into_iter()
+                        .map(verify_annotation)
+                        .collect::<Result<Vec<Type>, Box<dyn Diag>>>()?,
+                    Annotation::Type(RangedType {
+                        value: Type::Literal(TypeLiteral::EllipsisLiteral),
+                        range,
+                    }) => {
+                        return Err(UnsupportedSyntaxDiag::new(
+                            "Callable with any arguments",
+                            range,
+                        )
+                        .into())
+                    }
+                    other => {
+                        return Err(Diagnostic::error(
+                            "Expected a list of parameter types".to_owned(),
+                            other.range(),
+                        )
+                        .into())
+                    }
+                };
+                // Callable parameters can only be passed by position, so they have no names
+                let names = params.iter().map(|_| Symbol::new("")).collect();
+                let ret = verify_annotation(ret)?;
+                Ok(Type::Function(Function::new(params, names, Box::new(ret))))
+            }
+            PartialAnnotationType::Type => {
+                let mut arguments = t.arguments.into_iter();
+                let (Some(arg), None) = (arguments.next(), arguments.next()) else {
+                    return Err(Diagnostic::error(
+                        "type expects a single class".to_owned(),
+                        t.range,
+                    )
+                    .into());
+                };
+                let range = arg.range();
+                match verify_annotation(arg)? {
+                    Type::Instance(cls) => Ok(Type::Class(cls)),
+                    Type::Any => Ok(Type::Any),
+                    other => Err(Diagnostic::error(
+                        format!("Expected a class in type, found {}", other),
+                        range,
+                    )
+                    .into()),
+                }
+            }
+            PartialAnnotationType::Generic(name) => {
+                let arity = generic_arity(name);
+                let args = if t.arguments.is_empty() {
+                    // A bare generic class takes anything
+                    vec![Type::Any; arity]
+                } else if t.arguments.len() == arity {
+                    t.arguments
+                        .into_iter()
+                        .map(verify_annotation)
+                        .collect::<Result<Vec<Type>, Box<dyn Diag>>>()?
+                } else {
+                    return Err(Diagnostic::error(
+                        format!(
+                            "{} expects {} type arguments, got {}",
+                            name,
+                            arity,
+                            t.arguments.len()
+                        ),
+                        t.range,
+                    )
+                    .into());
+                };
+                Ok(Type::Generic(Symbol::new(name), args))
+            }
+            PartialAnnotationType::Parameters => Err(Diagnostic::error(
+                "A list of parameter types is only allowed in Callable".to_owned(),
+                t.range,
+            )
+            .into()),
         },
     }
 }
 
 pub fn synth_annotation(info: &Info, scope: &mut Scope, maybe_ast: Option<Expr>) -> Type {
+    info.profiler.time(Phase::Annotations, || {
+        synth_annotation_untimed(info, scope, maybe_ast)
+    })
+}
+
+fn synth_annotation_untimed(info: &Info, scope: &mut Scope, maybe_ast: Option<Expr>) -> Type {
     let Some(ann) = _synth_annotation(info, scope, maybe_ast) else {
         return Type::Unknown;
     };
@@ -144,6 +304,41 @@ fn _synth_annotation(
 
     match ast {
         // TODO: Make sure Literal get arguments!
+        Expr::Subscript(s) if is_annotated(scope, &s.value) => {
+            let Expr::Tuple(tuple) = *s.slice else {
+                info.reporter.error(
+                    "Annotated expects a type and at least one metadata argument",
+                    s.range,
+                );
+                return None;
+            };
+            let mut elts = tuple.elts.into_iter();
+            let inner = _synth_annotation(info, scope, elts.next())?;
+            let metadata: Vec<Expr> = elts.collect();
+            if metadata.is_empty() {
+                info.reporter.error(
+                    "Annotated expects a type and at least one metadata argument",
+                    s.range,
+                );
+                return None;
+            }
+            let typ = match verify_annotation(inner) {
+                Ok(typ) => typ,
+                Err(err) => {
+                    info.reporter.add(err);
+                    return None;
+                }
+            };
+            info.analysis.add_annotated(AnnotatedSite {
+                range: s.range,
+                typ: typ.clone(),
+                metadata,
+            });
+            Some(Annotation::Type(RangedType {
+                range: s.range,
+                value: typ,
+            }))
+        }
         Expr::Subscript(s) => {
             let value_range = s.value.range();
             let mut value = match _synth_annotation(info, scope, Some(*s.value))? {
@@ -172,28 +367,22 @@ fn _synth_annotation(
         }
         Expr::Name(n) => {
             let range = n.range();
-            let str = Arc::new(n.id.to_string());
-            let typ = match scope.get(&str) {
-                Some(t) => t.typ,
+            let str = Symbol::new(n.id.as_str());
+            match scope.get(&str) {
+                Some(t) => {
+                    if let Some(binding) = scope.binding(&str) {
+                        info.analysis
+                            .add_reference(binding, range, TokenKind::of(&t.typ));
+                    }
+                    Some(scoped_annotation(t.typ, range))
+                }
                 None => {
-                    // Parse partial annotations
-                    if let Some(partial_annotation_type) = match str.as_str() {
-                        "Union" => Some(PartialAnnotationType::Union),
-                        "Literal" => Some(PartialAnnotationType::Literal),
-                        "Tuple" | "tuple" => Some(PartialAnnotationType::Tuple),
-                        _ => None,
-                    } {
-                        return Some(Annotation::PartialAnnotation(PartialAnnotation {
-                            annotation: partial_annotation_type,
-                            arguments: vec![],
-                            range,
-                        }));
-                    };
+                    if let Some(special_form) = special_form(&str, range) {
+                        return Some(special_form);
+                    }
 
                     // Parse regular types
-                    match str.as_str() {
-                        // TODO: Remove this hardcoded non-import
-                        "Any" => Type::Any,
+                    let typ = match str.as_str() {
                         "Unknown" => Type::Unknown,
                         "str" => Type::String,
                         "int" => Type::Int,
@@ -206,23 +395,80 @@ fn _synth_annotation(
                                 .add(NotInScopeDiag::new(unknown.to_owned().into(), range));
                             return None;
                         }
+                    };
+                    Some(Annotation::Type(RangedType { range, value: typ }))
+                }
+            }
+        }
+        // Special forms and classes from a module, like typing.List
+        Expr::Attribute(attr) if matches!(*attr.value, Expr::Name(_)) => {
+            let Expr::Name(value) = &*attr.value else {
+                unreachable!()
+            };
+            let value_name = Symbol::new(value.id.as_str());
+            let typ = match scope.get(&value_name).map(|scoped| scoped.typ) {
+                Some(Type::Module(module_name, module)) => {
+                    match module_attribute(&module, &attr.attr.id) {
+                        Some(typ) => typ,
+                        None => {
+                            let mut available: Vec<Symbol> = module.keys().cloned().collect();
+                            available.sort();
+                            info.reporter.add(NotInModuleDiag::new(
+                                module_name,
+                                Symbol::new(attr.attr.id.as_str()),
+                                available,
+                                attr.range,
+                            ));
+                            return None;
+                        }
                     }
                 }
+                Some(_) => {
+                    info.reporter
+                        .add(UnsupportedSyntaxDiag::new("Attribute access", attr.range));
+                    return None;
+                }
+                None => {
+                    info.reporter
+                        .add(NotInScopeDiag::new(value_name, value.range));
+                    return None;
+                }
             };
-            Some(Annotation::Type(RangedType { range, value: typ }))
+            Some(scoped_annotation(typ, attr.range))
+        }
+        Expr::List(list) => {
+            let mut arguments = Vec::with_capacity(list.elts.len());
+            for elem in list.elts.into_iter() {
+                arguments.push(_synth_annotation(info, scope, Some(elem))?);
+            }
+            Some(Annotation::PartialAnnotation(PartialAnnotation {
+                range: list.range,
+                annotation: PartialAnnotationType::Parameters,
+                arguments,
+            }))
         }
         Expr::StringLiteral(l) => Some(Annotation::Type(RangedType {
             value: Type::Literal(TypeLiteral::StringLiteral(l.value.to_str().to_owned())),
             range: l.range(),
         })),
-        Expr::BytesLiteral(_) => unimplemented!("Bytes literal not supported."),
         Expr::NumberLiteral(l) => {
             let range = l.range();
             let literal = match l.value {
-                Number::Int(i) => TypeLiteral::IntLiteral(i.as_i64().unwrap()),
+                Number::Int(i) => match i.as_i64() {
+                    Some(i) => TypeLiteral::IntLiteral(i),
+                    None => {
+                        info.reporter.add(UnsupportedSyntaxDiag::new(
+                            "Integer literal this large",
+                            range,
+                        ));
+                        return None;
+                    }
+                },
                 Number::Float(i) => TypeLiteral::FloatLiteral(i.to_string()),
                 Number::Complex { real: _, imag: _ } => {
-                    unimplemented!("Complex numbers not supported.")
+                    info.reporter
+                        .add(UnsupportedSyntaxDiag::new("Complex number", range));
+                    return None;
                 }
             };
             Some(Annotation::Type(RangedType {
@@ -230,6 +476,33 @@ fn _synth_annotation(
                 range,
             }))
         }
+        // PEP 604 unions, `int | str`
+        Expr::BinOp(bin_op) if bin_op.op == Operator::BitOr => {
+            let left = _synth_annotation(info, scope, Some(*bin_op.left))?;
+            let right = _synth_annotation(info, scope, Some(*bin_op.right))?;
+            Some(Annotation::PartialAnnotation(PartialAnnotation {
+                range: bin_op.range,
+                annotation: PartialAnnotationType::Union,
+                arguments: vec![left, right],
+            }))
+        }
+        // Negative ints are a unary operation, but are allowed in Literal
+        Expr::UnaryOp(u)
+            if u.op == UnaryOp::USub && matches!(*u.operand, Expr::NumberLiteral(_)) =>
+        {
+            let range = u.range;
+            let value = match _synth_annotation(info, scope, Some(*u.operand))? {
+                Annotation::Type(RangedType {
+                    value: Type::Literal(TypeLiteral::IntLiteral(i)),
+                    ..
+                }) => TypeLiteral::IntLiteral(-i),
+                _ => return None,
+            };
+            Some(Annotation::Type(RangedType {
+                value: Type::Literal(value),
+                range,
+            }))
+        }
         Expr::BooleanLiteral(l) => Some(Annotation::Type(RangedType {
             value: Type::Literal(TypeLiteral::BooleanLiteral(l.value)),
             range: l.range(),
@@ -242,6 +515,90 @@ fn _synth_annotation(
             value: Type::Literal(TypeLiteral::EllipsisLiteral),
             range: l.range(),
         })),
-        e => unimplemented!("{:?}", e),
+        e => {
+            info.reporter
+                .add(UnsupportedSyntaxDiag::new(expr_kind(&e), e.range()));
+            None
+        }
+    }
+}
+
+/// The annotation for a name bound in scope or imported from a module.
+fn scoped_annotation(typ: Type, range: TextRange) -> Annotation {
+    match typ {
+        Type::SpecialForm(name) => {
+            special_form(&name, range).unwrap_or(Annotation::Type(RangedType {
+                range,
+                value: Type::Unknown,
+            }))
+        }
+        // Annotating with a class means an instance of it, builtin generic classes can also take
+        // type arguments
+        Type::Class(cls) => {
+            if let Some(form) = builtin_instance(&cls).and_then(|_| special_form(&cls.name, range))
+            {
+                return form;
+            }
+            Annotation::Type(RangedType {
+                range,
+                value: builtin_instance(&cls).unwrap_or(Type::Instance(cls)),
+            })
+        }
+        value => Annotation::Type(RangedType { range, value }),
+    }
+}
+
+/// The annotation for a special form from `typing`, or a builtin generic class, by name.
+fn special_form(name: &str, range: TextRange) -> Option<Annotation> {
+    let partial_annotation_type = match name {
+        "Union" => PartialAnnotationType::Union,
+        "Optional" => PartialAnnotationType::Optional,
+        "Literal" => PartialAnnotationType::Literal,
+        "Tuple" | "tuple" => PartialAnnotationType::Tuple,
+        "Callable" => PartialAnnotationType::Callable,
+        "Type" | "type" => PartialAnnotationType::Type,
+        "Any" => {
+            return Some(Annotation::Type(RangedType {
+                range,
+                value: Type::Any,
+            }))
+        }
+        "Never" | "NoReturn" => {
+            return Some(Annotation::Type(RangedType {
+                range,
+                value: Type::Never,
+            }))
+        }
+        name => PartialAnnotationType::Generic(generic_name(name)?),
+    };
+    Some(Annotation::PartialAnnotation(PartialAnnotation {
+        annotation: partial_annotation_type,
+        arguments: vec![],
+        range,
+    }))
+}
+
+/// Is the subscripted value the `Annotated` special form, rather than something named like it?
+fn is_annotated(scope: &Scope, value: &Expr) -> bool {
+    let special_form = |typ: Option<Type>| match typ {
+        Some(Type::SpecialForm(name)) => name.as_str() == "Annotated",
+        _ => false,
+    };
+    match value {
+        Expr::Name(n) => {
+            let typ = scope.get(&Symbol::new(n.id.as_str())).map(|s| s.typ);
+            (n.id == "Annotated" && typ.is_none()) || special_form(typ)
+        }
+        Expr::Attribute(attr) => match &*attr.value {
+            Expr::Name(module) => match scope.get(&Symbol::new(module.id.as_str())) {
+                Some(ScopedType {
+                    typ: Type::Module(_, module),
+                    ..
+                }) => special_form(module_attribute(&module, &attr.attr.id)),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
     }
 }
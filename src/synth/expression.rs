@@ -13,30 +13,74 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use ruff_python_ast::{Expr, ExprContext, Number};
-use ruff_text_size::Ranged;
-use std::sync::Arc;
+use ruff_python_ast::{
+    Expr, ExprAttribute, ExprContext, ExprDict, ExprLambda, Number, Operator, ParameterWithDefault,
+    UnaryOp,
+};
+use ruff_text_size::{Ranged, TextRange};
+use std::collections::HashMap;
 
-use crate::diagnostics::custom::{ExpectedButGotDiag, NotInScopeDiag, RevealTypeDiag};
-use crate::scope::Scope;
+use crate::analysis::{AttributeSite, CallSite, TokenKind};
+use crate::builtins::{builtin_instance, container_method, refine_call, refine_method};
+use crate::diagnostics::custom::{
+    AssertTypeDiag, DuplicateDefinitionDiag, ExpectedButGotDiag, IndexOutOfRangeDiag,
+    InvalidIndexDiag, KeywordArgumentDiag, NotInModuleDiag, NotInScopeDiag, PositionalOnlyDiag,
+    RevealLocalsDiag, RevealTypeDiag, StarredAfterKeywordDiag, TooManyPositionalDiag,
+    UnknownAttributeDiag, UnsupportedOperandDiag, UnsupportedSyntaxDiag,
+    UnsupportedUnaryOperandDiag,
+};
+use crate::scope::{Scope, ScopedType};
+use crate::sql::SqlQuery;
 use crate::state::Info;
-use crate::types::{is_subtype, Function, Type, TypeLiteral};
+use crate::symbol::Symbol;
+use crate::types::{
+    is_subtype, iterable_element, mapping_items, sequence_element, union, widen, Function, Type,
+    TypeLiteral,
+};
+
+use super::{fold, synth_annotation};
 
 pub fn synth(info: &Info, scope: &mut Scope, ast: Expr) -> Type {
+    if !info.config.record_types {
+        return synth_expression(info, scope, ast);
+    }
+    let range = ast.range();
+    let typ = synth_expression(info, scope, ast);
+    info.analysis.add_type(range, typ.clone());
+    typ
+}
+
+fn synth_expression(info: &Info, scope: &mut Scope, ast: Expr) -> Type {
     match ast {
         Expr::NoneLiteral(_) => Type::None,
+        Expr::EllipsisLiteral(_) => Type::Ellipsis,
         Expr::BooleanLiteral(l) => Type::Literal(TypeLiteral::BooleanLiteral(l.value)),
         Expr::NumberLiteral(n) => match n.value {
-            Number::Int(l) => Type::Literal(TypeLiteral::IntLiteral(l.as_i64().unwrap())),
+            Number::Int(l) => match l.as_i64() {
+                Some(l) => Type::Literal(TypeLiteral::IntLiteral(l)),
+                // Too large to be tracked as a literal
+                None => Type::Int,
+            },
             Number::Float(l) => Type::Literal(TypeLiteral::FloatLiteral(l.to_string())),
-            Number::Complex { real: _, imag: _ } => unimplemented!(),
+            Number::Complex { real: _, imag: _ } => {
+                info.reporter
+                    .add(UnsupportedSyntaxDiag::new("Complex number", n.range));
+                Type::Unknown
+            }
         },
         Expr::StringLiteral(s) => {
             Type::Literal(TypeLiteral::StringLiteral(s.value.to_str().to_owned()))
         }
+        Expr::BytesLiteral(b) => {
+            Type::Literal(TypeLiteral::BytesLiteral(b.value.bytes().collect()))
+        }
         Expr::Name(name) if name.ctx == ExprContext::Load => {
-            let name_str = Arc::new(name.id.to_string());
+            let name_str = Symbol::new(name.id.as_str());
             if let Some(scoped) = scope.get(&name_str) {
+                if let Some(binding) = scope.binding(&name_str) {
+                    info.analysis
+                        .add_read(binding, name.range, TokenKind::of(&scoped.typ));
+                }
                 scoped.typ
             } else {
                 info.reporter
@@ -44,30 +88,19 @@ pub fn synth(info: &Info, scope: &mut Scope, ast: Expr) -> Type {
                 Type::Unknown
             }
         }
-        Expr::Lambda(lambda) => {
-            let mut args: Vec<Type> = vec![];
-            let mut arg_names = vec![];
-            if let Some(params) = lambda.parameters {
-                for arg in params.args.into_iter() {
-                    let ann = arg
-                        .parameter
-                        .annotation
-                        .map(|a| synth(info, scope, *a))
-                        .unwrap_or(Type::Unknown);
-                    let param_name = arg.parameter.name.id;
-                    args.push(ann);
-                    arg_names.push(Arc::new(param_name.to_string()));
-                }
-            }
-            let ret = Box::new(synth(info, scope, *lambda.body));
-            Type::Function(Function::new(args, arg_names, ret))
-        }
-        Expr::Call(mut call) => {
-            // Early handling for reveal_type
-            let func = match *call.func {
-                Expr::Name(func_name) if func_name.id == "reveal_type" => {
+        // What the parser put in place of an expression it couldn't parse
+        Expr::Name(name) if name.ctx == ExprContext::Invalid => Type::Unknown,
+        Expr::Lambda(lambda) => synth_lambda(info, scope, lambda, None).unwrap_or(Type::Unknown),
+        Expr::Call(call) => {
+            // Early handling for the type checking helpers
+            match helper_name(&call.func) {
+                Some("reveal_type") => {
                     // TODO: Get an owned value here to avoid the clone
-                    let arg = call.arguments.args.first().unwrap().clone();
+                    let Some(arg) = call.arguments.args.first().cloned() else {
+                        info.reporter
+                            .error("reveal_type expects one argument", call.range);
+                        return Type::Unknown;
+                    };
                     let arg_range = arg.range();
                     let typ = synth(info, scope, arg);
                     info.reporter.add(RevealTypeDiag {
@@ -76,66 +109,707 @@ pub fn synth(info: &Info, scope: &mut Scope, ast: Expr) -> Type {
                     });
                     return Type::Unknown;
                 }
-                func => func,
-            };
-            // Re-assemble the call, we didn't need it in the end
-            call.func = Box::new(func);
+                Some("assert_type") => {
+                    let [value, annotation] = &*call.arguments.args else {
+                        info.reporter
+                            .error("assert_type expects a value and a type", call.range);
+                        return Type::Unknown;
+                    };
+                    let range = value.range();
+                    let got = synth(info, scope, value.clone());
+                    let expected = synth_annotation(info, scope, Some(annotation.clone()));
+                    if got != expected && expected != Type::Unknown {
+                        info.reporter
+                            .add(AssertTypeDiag::new(expected, got.clone(), range));
+                    }
+                    return got;
+                }
+                Some("reveal_locals") => {
+                    let locals = scope
+                        .locals()
+                        .into_iter()
+                        .map(|(name, scoped)| (name, scoped.typ))
+                        .collect();
+                    info.reporter.add(RevealLocalsDiag::new(locals, call.range));
+                    return Type::None;
+                }
+                _ => {}
+            }
 
             // Regular call handling
+            if let Some(keyword) = call.arguments.keywords.first() {
+                for arg in call.arguments.args.iter() {
+                    if arg.is_starred_expr() && arg.start() > keyword.start() {
+                        info.reporter.add(StarredAfterKeywordDiag::new(arg.range()));
+                    }
+                }
+            }
+            let is_execute = match &*call.func {
+                Expr::Name(name) => name.id == "execute",
+                Expr::Attribute(attr) => attr.attr.id == "execute",
+                _ => false,
+            };
+            let qualified = qualified_name(scope, &call.func);
             let callee_range = call.func.range();
             let call_range = call.range();
-            let callee = match synth(info, scope, *call.func) {
+            // The value a method is called on can make what the method gives back more precise
+            let (callee, receiver) = match *call.func {
+                Expr::Attribute(attr) => {
+                    let receiver = synth(info, scope, *attr.value.clone());
+                    let method = synth_attribute(info, receiver.clone(), &attr);
+                    // Recorded like synth records every other expression
+                    if info.config.record_types {
+                        info.analysis.add_type(attr.range, method.clone());
+                    }
+                    (method, Some((receiver, attr.attr.id)))
+                }
+                func => (synth(info, scope, func), None),
+            };
+            let callee = match callee {
                 Type::Function(func) => func,
+                // Calling a class checks the arguments against its `__init__`, which is stored
+                // without `self`, and gives an instance of it
+                Type::Class(cls) => {
+                    let instance = builtin_instance(&cls).unwrap_or(Type::Instance(cls.clone()));
+                    match cls.member("__init__") {
+                        Some(Type::Function(init)) => Function {
+                            ret: Box::new(instance),
+                            ..init
+                        },
+                        _ => {
+                            for arg in call.arguments.args.into_vec() {
+                                synth(info, scope, arg);
+                            }
+                            return instance;
+                        }
+                    }
+                }
                 type_ => {
                     info.reporter
                         .error(format!("{} not callable", type_), callee_range);
                     return Type::Unknown;
                 }
             };
-            if callee.args.len() != call.arguments.len() {
+            let call_index = info.analysis.add_call(CallSite {
+                name: Symbol::new(&info.file_content[callee_range]),
+                callee: callee.clone(),
+                arguments: call.arguments.range,
+                argument_ranges: call.arguments.args.iter().map(|a| a.range()).collect(),
+                argument_types: vec![],
+            });
+            // Match the positional arguments to the parameters, unpacking tuples of a known length
+            let extra = callee.variadic.as_ref().map(|(_, typ)| *typ.clone());
+            let expected_at = |i: usize| callee.args.get(i).cloned().or_else(|| extra.clone());
+            let mut arg_types = Vec::with_capacity(callee.args.len());
+            let mut unknown_length = false;
+            for arg in call.arguments.args.iter() {
+                match arg {
+                    Expr::Starred(starred) => match synth(info, scope, *starred.value.clone()) {
+                        Type::Tuple(types) if !unknown_length => {
+                            for typ in types {
+                                match expected_at(arg_types.len()) {
+                                    Some(expected) if !is_subtype(&typ, &expected) => {
+                                        info.reporter.add(ExpectedButGotDiag::new(
+                                            expected,
+                                            typ.clone(),
+                                            starred.range,
+                                        ))
+                                    }
+                                    _ => {}
+                                }
+                                arg_types.push(typ);
+                            }
+                        }
+                        _ => unknown_length = true,
+                    },
+                    arg => match expected_at(arg_types.len()).filter(|_| !unknown_length) {
+                        Some(expected) => arg_types
+                            .push(check(info, scope, arg.clone(), expected).unwrap_or_default()),
+                        None => arg_types.push(synth(info, scope, arg.clone())),
+                    },
+                }
+            }
+
+            // Then the keyword arguments to the parameters left
+            let mut given = vec![unknown_length; callee.args.len()];
+            given
+                .iter_mut()
+                .take(arg_types.len())
+                .for_each(|g| *g = true);
+            let mut given_keyword_only = vec![false; callee.keyword_only.len()];
+            let mut any_keywords = false;
+            for keyword in call.arguments.keywords.iter() {
+                let Some(name) = &keyword.arg else {
+                    // Unpacking a dict with `**` can pass any of them
+                    synth(info, scope, keyword.value.clone());
+                    any_keywords = true;
+                    continue;
+                };
+                let name = Symbol::new(name.id.as_str());
+                let positional = callee.arg_names.iter().position(|n| *n == name);
+                let keyword_only = callee.keyword_only.iter().position(|(n, ..)| *n == name);
+                let positional_only = positional.is_some_and(|i| i < callee.positional_only);
+                let expected = if positional_only && callee.keywords.is_none() {
+                    info.reporter
+                        .add(PositionalOnlyDiag::new(name, keyword.range));
+                    // It is still given, just the wrong way
+                    given[positional.unwrap()] = true;
+                    None
+                } else if let Some(i) = positional.filter(|_| !positional_only) {
+                    if given[i] && !unknown_length {
+                        info.reporter.add(KeywordArgumentDiag::new(
+                            name,
+                            "is given more than once",
+                            keyword.range,
+                        ));
+                    }
+                    given[i] = true;
+                    Some(callee.args[i].clone())
+                } else if let Some(i) = keyword_only {
+                    given_keyword_only[i] = true;
+                    Some(callee.keyword_only[i].1.clone())
+                } else if let Some((_, typ)) = &callee.keywords {
+                    Some(*typ.clone())
+                } else {
+                    info.reporter.add(KeywordArgumentDiag::new(
+                        name,
+                        "is not a parameter of the function",
+                        keyword.range,
+                    ));
+                    None
+                };
+                match expected {
+                    Some(expected) => check(info, scope, keyword.value.clone(), expected),
+                    None => Some(synth(info, scope, keyword.value.clone())),
+                };
+            }
+            if !any_keywords {
+                for ((name, _, default), given) in
+                    callee.keyword_only.iter().zip(given_keyword_only)
+                {
+                    if default.is_none() && !given {
+                        info.reporter.add(KeywordArgumentDiag::new(
+                            name.clone(),
+                            "is required",
+                            call_range,
+                        ));
+                    }
+                }
+            }
+
+            let arg_count = call.arguments.len();
+            let required = callee.required_args();
+            let missing = !any_keywords && given[..required].contains(&false);
+            let too_many = arg_types.len() > callee.args.len() && callee.variadic.is_none();
+            if too_many && !missing && !callee.keyword_only.is_empty() {
+                info.reporter.add(TooManyPositionalDiag::new(
+                    callee.args.len(),
+                    arg_types.len(),
+                    call_range,
+                ));
+                return Type::Unknown;
+            }
+            if missing || too_many {
+                let expected = if callee.variadic.is_some() {
+                    format!("at least {}", required)
+                } else if required == callee.args.len() {
+                    required.to_string()
+                } else {
+                    format!("{} to {}", required, callee.args.len())
+                };
                 info.reporter.error(
-                    format!(
-                        "expected {} args, got {} args",
-                        callee.args.len(),
-                        call.arguments.args.len()
-                    ),
+                    format!("expected {} args, got {} args", expected, arg_count),
                     call_range,
                 );
                 return Type::Unknown;
             }
-            for (expected_arg, got_arg) in callee.args.into_iter().zip(call.arguments.args.iter()) {
-                check(info, scope, got_arg.clone(), expected_arg);
+            if !call.arguments.args.iter().any(|a| a.is_starred_expr()) {
+                info.analysis
+                    .set_argument_types(call_index, arg_types.clone());
+            }
+            if is_execute && !info.config.sql_validators.is_empty() {
+                check_sql(info, &call.arguments.args, &arg_types);
             }
-            *callee.ret
+            qualified
+                .and_then(|(module, name)| refine_call(&module, &name, &arg_types))
+                .or_else(|| {
+                    let (receiver, method) = receiver?;
+                    refine_method(&receiver, &method, &arg_types)
+                })
+                .unwrap_or(*callee.ret)
         }
         Expr::Attribute(attr) => {
-            let value = synth(info, scope, *attr.value);
-            match value {
-                Type::Module(_, module) => module
-                    .get(&attr.attr.id.to_string())
-                    .map(|t| t.typ.clone())
-                    .unwrap_or(Type::Unknown),
-                typ => {
-                    info.reporter.error(
-                        format!("Unknown attribute \"{}\" for {}", &attr.attr.id, typ),
-                        attr.range,
-                    );
+            let value = synth(info, scope, *attr.value.clone());
+            synth_attribute(info, value, &attr)
+        }
+        Expr::Tuple(tuple) => {
+            let mut types = Vec::with_capacity(tuple.elts.len());
+            let mut is_variadic = false;
+            let mut is_unknown = false;
+            for expr in tuple.elts.into_iter() {
+                match expr {
+                    // Splice in the elements of fixed size tuples, other iterables make the length
+                    // of the resulting tuple unknown.
+                    Expr::Starred(starred) => match synth(info, scope, *starred.value) {
+                        Type::Tuple(inner) => types.extend(inner),
+                        other => match iterable_element(&other) {
+                            Some(element) => {
+                                types.push(element);
+                                is_variadic = true;
+                            }
+                            None => is_unknown = true,
+                        },
+                    },
+                    expr => types.push(synth(info, scope, expr)),
+                }
+            }
+            if is_unknown {
+                Type::Unknown
+            } else if is_variadic {
+                Type::Generic(Symbol::new("tuple"), vec![union(types)])
+            } else {
+                Type::Tuple(types)
+            }
+        }
+        Expr::List(list) if list.ctx == ExprContext::Load => Type::Generic(
+            Symbol::new("list"),
+            vec![synth_elements(info, scope, list.elts)],
+        ),
+        Expr::Set(set) => Type::Generic(
+            Symbol::new("set"),
+            vec![synth_elements(info, scope, set.elts)],
+        ),
+        Expr::Dict(dict) => {
+            check_duplicate_keys(info, &dict);
+            let mut keys = Vec::with_capacity(dict.items.len());
+            let mut values = Vec::with_capacity(dict.items.len());
+            for item in dict.items {
+                let value = synth(info, scope, item.value);
+                match item.key {
+                    Some(key) => {
+                        keys.push(widen(&synth(info, scope, key)));
+                        values.push(widen(&value));
+                    }
+                    // Unpacking another mapping with **
+                    None => {
+                        let (key, value) =
+                            mapping_items(&value).unwrap_or((Type::Unknown, Type::Unknown));
+                        keys.push(key);
+                        values.push(value);
+                    }
+                }
+            }
+            Type::Generic(
+                Symbol::new("dict"),
+                vec![element_union(keys), element_union(values)],
+            )
+        }
+        Expr::BinOp(bin_op) => {
+            let left = synth(info, scope, *bin_op.left);
+            let right = synth(info, scope, *bin_op.right);
+            match fold::binary_operation(&left, bin_op.op, &right)
+                .or_else(|| dunder_operation(&left, bin_op.op, &right))
+            {
+                Some(typ) => typ,
+                None if is_open(&left) || is_open(&right) => {
+                    info.reporter
+                        .add(UnsupportedSyntaxDiag::new("Binary operation", bin_op.range));
+                    Type::Unknown
+                }
+                None => {
+                    info.reporter.add(UnsupportedOperandDiag::new(
+                        bin_op.op,
+                        left,
+                        right,
+                        bin_op.range,
+                    ));
+                    Type::Unknown
+                }
+            }
+        }
+        Expr::UnaryOp(unary_op) => {
+            let operand = synth(info, scope, *unary_op.operand);
+            let dunder = match unary_op.op {
+                UnaryOp::USub => "__neg__",
+                UnaryOp::UAdd => "__pos__",
+                UnaryOp::Invert => "__invert__",
+                UnaryOp::Not => "__bool__",
+            };
+            match fold::unary_operation(unary_op.op, &operand).or_else(|| match &operand {
+                Type::Instance(cls) => match cls.member(dunder)? {
+                    Type::Function(func) => Some(*func.ret),
+                    _ => None,
+                },
+                _ => None,
+            }) {
+                Some(typ) => typ,
+                None if is_open(&operand) => {
+                    info.reporter.add(UnsupportedSyntaxDiag::new(
+                        "Unary operation",
+                        unary_op.range,
+                    ));
                     Type::Unknown
                 }
+                None => {
+                    info.reporter.add(UnsupportedUnaryOperandDiag::new(
+                        unary_op.op,
+                        operand,
+                        unary_op.range,
+                    ));
+                    Type::Unknown
+                }
+            }
+        }
+        Expr::BoolOp(bool_op) => {
+            let values: Vec<Type> = bool_op
+                .values
+                .into_iter()
+                .map(|value| synth(info, scope, value))
+                .collect();
+            fold::boolean_operation(bool_op.op, &values)
+        }
+        Expr::If(if_expr) => {
+            // Like in an if statement, a branch the condition never leads to isn't checked
+            match fold::truthiness(&synth(info, scope, *if_expr.test)) {
+                Some(true) => synth(info, scope, *if_expr.body),
+                Some(false) => synth(info, scope, *if_expr.orelse),
+                None => union(vec![
+                    synth(info, scope, *if_expr.body),
+                    synth(info, scope, *if_expr.orelse),
+                ]),
             }
         }
-        Expr::Tuple(tuple) => Type::Tuple(
-            tuple
-                .elts
+        Expr::Compare(compare) => {
+            let left = synth(info, scope, *compare.left);
+            let comparators: Vec<Type> = compare
+                .comparators
+                .into_vec()
                 .into_iter()
                 .map(|expr| synth(info, scope, expr))
+                .collect();
+            fold::comparison(&left, &compare.ops, &comparators)
+        }
+        Expr::Subscript(subscript) if subscript.ctx == ExprContext::Load => {
+            let value = synth(info, scope, *subscript.value);
+            match *subscript.slice {
+                Expr::Slice(slice) => {
+                    for bound in [slice.lower, slice.upper, slice.step].into_iter().flatten() {
+                        check(info, scope, *bound, union(vec![Type::Int, Type::None]));
+                    }
+                    match value {
+                        Type::Tuple(types) if types.is_empty() => Type::Tuple(types),
+                        // TODO: Keep the exact elements for slices with literal bounds
+                        Type::Tuple(types) => {
+                            Type::Generic(Symbol::new("tuple"), vec![union(types)])
+                        }
+                        Type::Generic(name, args) if name.as_str() == "tuple" => {
+                            Type::Generic(name, args)
+                        }
+                        Type::Any | Type::Unknown => value,
+                        // Slicing a list gives a list and a string a string
+                        value if sequence_element(&widen(&value)).is_some() => widen(&value),
+                        _ => {
+                            info.reporter
+                                .add(UnsupportedSyntaxDiag::new("Subscript", subscript.range));
+                            Type::Unknown
+                        }
+                    }
+                }
+                index => {
+                    let index_range = index.range();
+                    let index = synth(info, scope, index);
+                    index_value(info, value, index, index_range, subscript.range)
+                }
+            }
+        }
+        e => {
+            info.reporter
+                .add(UnsupportedSyntaxDiag::new(expr_kind(&e), e.range()));
+            Type::Unknown
+        }
+    }
+}
+
+/// The type of the attribute of a value whose type is already known, `value.attr`.
+fn synth_attribute(info: &Info, value: Type, attr: &ExprAttribute) -> Type {
+    let value_end = attr.value.end();
+    if let Type::Module(_, module) = &value {
+        if let Some(member) = module.get(&Symbol::new(attr.attr.id.as_str())) {
+            info.analysis
+                .add_token(attr.attr.range, TokenKind::of(&member.typ));
+        }
+        info.analysis.add_attribute(AttributeSite {
+            range: TextRange::new(value_end, attr.range.end()),
+            members: module
+                .iter()
+                .map(|(name, scoped)| (name.clone(), scoped.clone()))
                 .collect(),
-        ),
-        e => unimplemented!("Unknown expression for synth: {e:?}"),
+        });
+    }
+    match value {
+        Type::Module(module_name, module) => {
+            let name = Symbol::new(attr.attr.id.as_str());
+            module_attribute(&module, &name).unwrap_or_else(|| {
+                let mut available: Vec<Symbol> = module.keys().cloned().collect();
+                available.sort();
+                info.reporter.add(NotInModuleDiag::new(
+                    module_name,
+                    name,
+                    available,
+                    attr.attr.range,
+                ));
+                Type::Unknown
+            })
+        }
+        Type::Instance(ref cls) | Type::Class(ref cls) => {
+            let name = attr.attr.id.as_str();
+            let is_dunder = name.starts_with("__") && name.ends_with("__");
+            match cls.member(name) {
+                Some(typ) => typ,
+                None => match cls.member("__getattr__") {
+                    // Instances look up the attributes their class doesn't define with
+                    // __getattr__
+                    Some(Type::Function(getattr)) if matches!(value, Type::Instance(_)) => {
+                        *getattr.ret
+                    }
+                    _ if is_dunder || !cls.has_known_members() => Type::Unknown,
+                    _ => {
+                        info.reporter.add(UnknownAttributeDiag::new(
+                            value.clone(),
+                            Symbol::new(name),
+                            attr.attr.range,
+                        ));
+                        Type::Unknown
+                    }
+                },
+            }
+        }
+        typ => match container_method(&typ, &attr.attr.id) {
+            Some(method) => Type::Function(method),
+            None => {
+                info.reporter.error(
+                    format!("Unknown attribute \"{}\" for {}", &attr.attr.id, typ),
+                    attr.range,
+                );
+                Type::Unknown
+            }
+        },
+    }
+}
+
+/// The type of the element of a container picked with an index, `value[index]`.
+fn index_value(
+    info: &Info,
+    value: Type,
+    index: Type,
+    index_range: TextRange,
+    range: TextRange,
+) -> Type {
+    let is_int = is_subtype(&index, &Type::Int);
+    let out_of_range = |i: i64, value: &Type| {
+        info.reporter
+            .add(IndexOutOfRangeDiag::new(i, value.clone(), index_range));
+        Type::Unknown
+    };
+    let position = |i: i64, len: usize| {
+        if i < 0 {
+            usize::try_from(i + len as i64).ok()
+        } else {
+            usize::try_from(i).ok()
+        }
+    };
+    match (value, index) {
+        (Type::Any | Type::Unknown, _) => Type::Unknown,
+        (Type::Tuple(types), Type::Literal(TypeLiteral::IntLiteral(i))) => {
+            match position(i, types.len()).and_then(|p| types.get(p)) {
+                Some(typ) => typ.clone(),
+                None => out_of_range(i, &Type::Tuple(types)),
+            }
+        }
+        (Type::Tuple(types), _) if is_int => union(types),
+        (
+            Type::Literal(TypeLiteral::StringLiteral(s)),
+            Type::Literal(TypeLiteral::IntLiteral(i)),
+        ) => {
+            let chars: Vec<char> = s.chars().collect();
+            match position(i, chars.len()).and_then(|p| chars.get(p)) {
+                Some(c) => Type::Literal(TypeLiteral::StringLiteral(c.to_string())),
+                None => out_of_range(i, &Type::Literal(TypeLiteral::StringLiteral(s))),
+            }
+        }
+        (value, index) => {
+            let found = if let Some((key, item)) = mapping_items(&value) {
+                is_subtype(&index, &key).then_some(item)
+            } else if let Some(element) = sequence_element(&widen(&value)) {
+                is_int.then_some(element)
+            } else if let Type::Instance(cls) = &value {
+                match cls.member("__getitem__") {
+                    Some(Type::Function(func))
+                        if func.args.first().is_none_or(|arg| is_subtype(&index, arg)) =>
+                    {
+                        Some(*func.ret)
+                    }
+                    _ => None,
+                }
+            } else {
+                None
+            };
+            match found {
+                Some(typ) => typ,
+                None if is_open(&value)
+                    && mapping_items(&value).is_none()
+                    && sequence_element(&widen(&value)).is_none() =>
+                {
+                    info.reporter
+                        .add(UnsupportedSyntaxDiag::new("Subscript", range));
+                    Type::Unknown
+                }
+                None => {
+                    info.reporter
+                        .add(InvalidIndexDiag::new(value, index, index_range));
+                    Type::Unknown
+                }
+            }
+        }
+    }
+}
+
+/// The element type of a list or set display, the union of its widened elements.
+fn synth_elements(info: &Info, scope: &mut Scope, elts: Vec<Expr>) -> Type {
+    let types = elts
+        .into_iter()
+        .map(|expr| match expr {
+            Expr::Starred(starred) => {
+                iterable_element(&synth(info, scope, *starred.value)).unwrap_or(Type::Unknown)
+            }
+            expr => widen(&synth(info, scope, expr)),
+        })
+        .collect();
+    element_union(types)
+}
+
+/// Check the elements of a list or set display against the expected element type.
+fn check_elements(info: &Info, scope: &mut Scope, elts: Vec<Expr>, expected: &Type) -> bool {
+    let mut ok = true;
+    for expr in elts {
+        ok &= match expr {
+            Expr::Starred(starred) => {
+                let element =
+                    iterable_element(&synth(info, scope, *starred.value)).unwrap_or(Type::Unknown);
+                is_subtype(&element, expected) || {
+                    info.reporter.add(ExpectedButGotDiag::new(
+                        expected.clone(),
+                        element,
+                        starred.range,
+                    ));
+                    false
+                }
+            }
+            expr => check(info, scope, expr, expected.clone()).is_some(),
+        };
+    }
+    ok
+}
+
+/// The union of the element types of a container display, unknown when it's empty.
+fn element_union(types: Vec<Type>) -> Type {
+    if types.is_empty() {
+        Type::Unknown
+    } else {
+        union(types)
+    }
+}
+
+/// Report literal keys that appear more than once in a dict display.
+fn check_duplicate_keys(info: &Info, dict: &ExprDict) {
+    let mut seen: HashMap<TypeLiteral, TextRange> = HashMap::new();
+    for key in dict.items.iter().filter_map(|item| item.key.as_ref()) {
+        let literal = match key {
+            Expr::StringLiteral(s) => TypeLiteral::StringLiteral(s.value.to_str().to_owned()),
+            Expr::NumberLiteral(n) => match &n.value {
+                Number::Int(i) => match i.as_i64() {
+                    Some(i) => TypeLiteral::IntLiteral(i),
+                    None => continue,
+                },
+                _ => continue,
+            },
+            Expr::BooleanLiteral(b) => TypeLiteral::BooleanLiteral(b.value),
+            Expr::NoneLiteral(_) => TypeLiteral::NoneLiteral,
+            _ => continue,
+        };
+        match seen.get(&literal) {
+            Some(first) => info.reporter.add(DuplicateDefinitionDiag::new(
+                "key",
+                match &literal {
+                    TypeLiteral::StringLiteral(s) => Symbol::new(s),
+                    _ => Symbol::new(&info.file_content[key.range()]),
+                },
+                *first,
+                key.range(),
+            )),
+            None => {
+                seen.insert(literal, key.range());
+            }
+        }
     }
 }
 
 pub fn check(info: &Info, scope: &mut Scope, ast: Expr, typ: Type) -> Option<Type> {
+    // Lambdas get their parameter types from the expected function
+    let ast = match (ast, &typ) {
+        (Expr::Lambda(lambda), Type::Function(expected))
+            if lambda_arity(&lambda) == Some(expected.args.len()) =>
+        {
+            return synth_lambda(info, scope, lambda, Some(expected));
+        }
+        // Both branches of a conditional expression are checked against the expected type
+        (Expr::If(if_expr), _) => {
+            let truthiness = fold::truthiness(&synth(info, scope, *if_expr.test));
+            let body =
+                (truthiness != Some(false)).then(|| check(info, scope, *if_expr.body, typ.clone()));
+            let orelse = (truthiness != Some(true))
+                .then(|| check(info, scope, *if_expr.orelse, typ.clone()));
+            return [body, orelse]
+                .into_iter()
+                .flatten()
+                .collect::<Option<Vec<_>>>()
+                .map(union);
+        }
+        // Container displays take their element types from the expected type, so that [1] can be
+        // a list[float] even though the type parameter of a list is invariant.
+        (Expr::List(list), Type::Generic(name, args))
+            if name.as_str() == "list" && list.ctx == ExprContext::Load =>
+        {
+            return check_elements(info, scope, list.elts, &args[0]).then_some(typ);
+        }
+        (Expr::Set(set), Type::Generic(name, args)) if name.as_str() == "set" => {
+            return check_elements(info, scope, set.elts, &args[0]).then_some(typ);
+        }
+        (Expr::Dict(dict), Type::Generic(name, args)) if name.as_str() == "dict" => {
+            check_duplicate_keys(info, &dict);
+            let mut ok = true;
+            for item in dict.items {
+                ok &= match item.key {
+                    Some(key) => {
+                        let key = check(info, scope, key, args[0].clone()).is_some();
+                        check(info, scope, item.value, args[1].clone()).is_some() && key
+                    }
+                    None => {
+                        let range = item.value.range();
+                        let value = synth(info, scope, item.value);
+                        let expected = Type::Generic(Symbol::new("Mapping"), args.clone());
+                        is_subtype(&value, &expected) || {
+                            info.reporter
+                                .add(ExpectedButGotDiag::new(expected, value, range));
+                            false
+                        }
+                    }
+                };
+            }
+            return ok.then_some(typ);
+        }
+        (ast, _) => ast,
+    };
     let range = ast.range();
     let synth_type = synth(info, scope, ast);
     if is_subtype(&synth_type, &typ) {
@@ -146,3 +820,195 @@ pub fn check(info: &Info, scope: &mut Scope, ast: Expr, typ: Type) -> Option<Typ
         None
     }
 }
+
+/// The name of a type checking helper like `reveal_type` being called, either directly or through
+/// the `typing` or `pycavalry` modules.
+fn helper_name(func: &Expr) -> Option<&str> {
+    let name = match func {
+        Expr::Name(name) => name.id.as_str(),
+        Expr::Attribute(attr) => match &*attr.value {
+            Expr::Name(module) if module.id == "typing" || module.id == "pycavalry" => {
+                attr.attr.id.as_str()
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    matches!(name, "reveal_type" | "assert_type" | "reveal_locals").then_some(name)
+}
+
+/// The return type of the `__add__` style method of the left operand that accepts the right one,
+/// or the reflected `__radd__` style method of the right operand.
+fn dunder_operation(left: &Type, op: Operator, right: &Type) -> Option<Type> {
+    let method = |operand: &Type, name: &str, other: &Type| match operand {
+        Type::Instance(cls) => match cls.member(name)? {
+            Type::Function(func) if func.args.first().is_none_or(|arg| is_subtype(other, arg)) => {
+                Some(*func.ret)
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    let reflected = format!("__r{}", &op.dunder()[2..]);
+    method(left, op.dunder(), right).or_else(|| method(right, &reflected, left))
+}
+
+/// Whether operators on the type could be defined somewhere this can't see, so an operation that
+/// isn't found might still work.
+fn is_open(typ: &Type) -> bool {
+    match typ {
+        Type::Instance(cls) => !cls.has_known_members(),
+        typ => !fold::is_scalar(typ) && !matches!(typ, Type::Tuple(_)),
+    }
+}
+
+/// Number of parameters of a lambda, if they can all be passed by position.
+fn lambda_arity(lambda: &ExprLambda) -> Option<usize> {
+    match &lambda.parameters {
+        None => Some(0),
+        Some(params)
+            if params.vararg.is_none()
+                && params.kwarg.is_none()
+                && params.kwonlyargs.is_empty() =>
+        {
+            Some(params.posonlyargs.len() + params.args.len())
+        }
+        Some(_) => None,
+    }
+}
+
+/// Synthesize a lambda, checking its body against the expected function if there is one.
+///
+/// Returns None if the body doesn't match the expected return type.
+fn synth_lambda(
+    info: &Info,
+    scope: &mut Scope,
+    lambda: ExprLambda,
+    expected: Option<&Function>,
+) -> Option<Type> {
+    scope.add_scope();
+    let mut args: Vec<Type> = vec![];
+    let mut arg_names = vec![];
+    let positional_only = lambda
+        .parameters
+        .as_ref()
+        .map_or(0, |p| p.posonlyargs.len());
+    let params: Vec<ParameterWithDefault> = lambda
+        .parameters
+        .map(|p| p.posonlyargs.into_iter().chain(p.args).collect())
+        .unwrap_or_default();
+    for (i, arg) in params.into_iter().enumerate() {
+        let typ = expected
+            .and_then(|e| e.args.get(i).cloned())
+            .unwrap_or(Type::Unknown);
+        let name = Symbol::new(arg.parameter.name.id.as_str());
+        info.analysis.add_reference(
+            scope.top_binding(&name),
+            arg.parameter.name.range,
+            TokenKind::Parameter,
+        );
+        scope.set(name.clone(), typ.clone());
+        args.push(typ);
+        arg_names.push(name);
+    }
+    let ret = match expected {
+        Some(expected) => check(info, scope, *lambda.body, *expected.ret.clone()),
+        None => Some(synth(info, scope, *lambda.body)),
+    };
+    scope.pop_scope();
+    Some(Type::Function(
+        Function::new(args, arg_names, Box::new(ret?)).with_positional_only(positional_only),
+    ))
+}
+
+/// The module a called function comes from and its name there, for `module.f` and functions
+/// imported with `from module import f`.
+fn qualified_name(scope: &Scope, func: &Expr) -> Option<(Symbol, Symbol)> {
+    match func {
+        Expr::Name(name) => {
+            let name = Symbol::new(name.id.as_str());
+            let module = scope.get_ref(&name)?.imported_from.clone()?;
+            Some((module, name))
+        }
+        Expr::Attribute(attr) => {
+            let Expr::Name(value) = &*attr.value else {
+                return None;
+            };
+            match &scope.get_ref(&Symbol::new(value.id.as_str()))?.typ {
+                Type::Module(module, _) => {
+                    Some((module.clone(), Symbol::new(attr.attr.id.as_str())))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Hand a literal SQL query passed to an `execute` call to the configured validators.
+fn check_sql(info: &Info, args: &[Expr], arg_types: &[Type]) {
+    let Some(Expr::StringLiteral(sql)) = args.first() else {
+        return;
+    };
+    let query = SqlQuery {
+        sql: sql.value.to_str(),
+        range: sql.range,
+        parameters: arg_types
+            .get(1)
+            .zip(args.get(1))
+            .map(|(t, a)| (t, a.range())),
+    };
+    for validator in info.config.sql_validators.iter() {
+        info.reporter.extend(validator.validate(&query));
+    }
+}
+
+/// Look up a module attribute, using the return type of the module's `__getattr__` for names it
+/// doesn't define.
+pub fn module_attribute(module: &HashMap<Symbol, ScopedType>, name: &str) -> Option<Type> {
+    if let Some(member) = module.get(&Symbol::new(name)) {
+        return Some(member.typ.clone());
+    }
+    match &module.get(&Symbol::new("__getattr__"))?.typ {
+        Type::Function(getattr) => Some(*getattr.ret.clone()),
+        _ => Some(Type::Unknown),
+    }
+}
+
+/// Human readable name of the kind of expression, for diagnostics.
+pub fn expr_kind(expr: &Expr) -> &'static str {
+    match expr {
+        Expr::BoolOp(_) => "Boolean operation",
+        Expr::Named(_) => "Named expression",
+        Expr::BinOp(_) => "Binary operation",
+        Expr::UnaryOp(_) => "Unary operation",
+        Expr::Lambda(_) => "Lambda",
+        Expr::If(_) => "Conditional expression",
+        Expr::Dict(_) => "Dict display",
+        Expr::Set(_) => "Set display",
+        Expr::ListComp(_) => "List comprehension",
+        Expr::SetComp(_) => "Set comprehension",
+        Expr::DictComp(_) => "Dict comprehension",
+        Expr::Generator(_) => "Generator expression",
+        Expr::Await(_) => "Await expression",
+        Expr::Yield(_) => "Yield expression",
+        Expr::YieldFrom(_) => "Yield from expression",
+        Expr::Compare(_) => "Comparison",
+        Expr::Call(_) => "Call",
+        Expr::FString(_) => "F-string",
+        Expr::StringLiteral(_) => "String literal",
+        Expr::BytesLiteral(_) => "Bytes literal",
+        Expr::NumberLiteral(_) => "Number literal",
+        Expr::BooleanLiteral(_) => "Boolean literal",
+        Expr::NoneLiteral(_) => "None literal",
+        Expr::EllipsisLiteral(_) => "Ellipsis literal",
+        Expr::Attribute(_) => "Attribute access",
+        Expr::Subscript(_) => "Subscript",
+        Expr::Starred(_) => "Starred expression",
+        Expr::Name(_) => "Name",
+        Expr::List(_) => "List display",
+        Expr::Tuple(_) => "Tuple display",
+        Expr::Slice(_) => "Slice",
+        Expr::IpyEscapeCommand(_) => "IPython escape command",
+    }
+}
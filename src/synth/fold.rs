@@ -0,0 +1,419 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Constant folding of operators on literal types, so conditions like `sys.version_info >= (3, 8)`
+//! and constants like `N = 2 * 2` keep their exact value.
+
+use std::cmp::Ordering;
+
+use ruff_python_ast::{BoolOp, CmpOp, Operator, UnaryOp};
+
+use crate::types::{union, Type, TypeLiteral};
+
+/// Whether a value of the type is always truthy or always falsy, if that is known.
+pub fn truthiness(typ: &Type) -> Option<bool> {
+    match typ {
+        Type::Literal(TypeLiteral::BooleanLiteral(b)) => Some(*b),
+        Type::Literal(TypeLiteral::IntLiteral(i)) => Some(*i != 0),
+        Type::Literal(TypeLiteral::StringLiteral(s)) => Some(!s.is_empty()),
+        Type::None | Type::Literal(TypeLiteral::NoneLiteral) => Some(false),
+        Type::Tuple(types) => Some(!types.is_empty()),
+        Type::Function(_) | Type::Class(_) | Type::Module(..) => Some(true),
+        _ => None,
+    }
+}
+
+/// The value of an int or bool literal, bools take part in arithmetic as 0 and 1.
+fn int_value(typ: &Type) -> Option<i64> {
+    match typ {
+        Type::Literal(TypeLiteral::IntLiteral(i)) => Some(*i),
+        Type::Literal(TypeLiteral::BooleanLiteral(b)) => Some(*b as i64),
+        _ => None,
+    }
+}
+
+/// Numeric tower rank, bool < int < float.
+fn numeric_rank(typ: &Type) -> Option<u8> {
+    match typ {
+        Type::Bool | Type::Literal(TypeLiteral::BooleanLiteral(_)) => Some(0),
+        Type::Int | Type::Literal(TypeLiteral::IntLiteral(_)) => Some(1),
+        Type::Float | Type::Literal(TypeLiteral::FloatLiteral(_)) => Some(2),
+        _ => None,
+    }
+}
+
+fn is_str(typ: &Type) -> bool {
+    matches!(
+        typ,
+        Type::String | Type::Literal(TypeLiteral::StringLiteral(_))
+    )
+}
+
+fn fold_int(left: i64, op: Operator, right: i64) -> Option<i64> {
+    match op {
+        Operator::Add => left.checked_add(right),
+        Operator::Sub => left.checked_sub(right),
+        Operator::Mult => left.checked_mul(right),
+        // Python rounds towards negative infinity and gives the remainder the sign of the divisor
+        Operator::FloorDiv if right != 0 => {
+            let div = left.checked_div(right)?;
+            Some(if (left % right != 0) && ((left < 0) != (right < 0)) {
+                div - 1
+            } else {
+                div
+            })
+        }
+        Operator::Mod if right != 0 => {
+            let rem = left.checked_rem(right)?;
+            Some(if rem != 0 && ((rem < 0) != (right < 0)) {
+                rem + right
+            } else {
+                rem
+            })
+        }
+        Operator::Pow => left.checked_pow(u32::try_from(right).ok()?),
+        Operator::LShift => u32::try_from(right)
+            .ok()
+            .filter(|r| *r < 63)
+            .and_then(|r| left.checked_mul(1 << r)),
+        Operator::RShift => Some(left >> u32::try_from(right).ok()?.min(63)),
+        Operator::BitAnd => Some(left & right),
+        Operator::BitOr => Some(left | right),
+        Operator::BitXor => Some(left ^ right),
+        _ => None,
+    }
+}
+
+/// The type of a binary operation, with the exact value when both sides are literals.
+///
+/// Returns None for operands this doesn't know how to combine.
+pub fn binary_operation(left: &Type, op: Operator, right: &Type) -> Option<Type> {
+    match (left, right) {
+        (Type::Any, _) | (_, Type::Any) => return Some(Type::Any),
+        (Type::Unknown, _) | (_, Type::Unknown) => return Some(Type::Unknown),
+        _ => {}
+    }
+    if let (Some(l), Some(r)) = (int_value(left), int_value(right)) {
+        // A negative power is a float, like 2 ** -1 == 0.5
+        if op == Operator::Div || (op == Operator::Pow && r < 0) {
+            return Some(Type::Float);
+        }
+        return Some(match fold_int(l, op, r) {
+            Some(value) => Type::Literal(TypeLiteral::IntLiteral(value)),
+            // Overflowed or raises at runtime, only the type is known
+            None => Type::Int,
+        });
+    }
+    if let (
+        Type::Literal(TypeLiteral::StringLiteral(l)),
+        Operator::Add,
+        Type::Literal(TypeLiteral::StringLiteral(r)),
+    ) = (left, op, right)
+    {
+        return Some(Type::Literal(TypeLiteral::StringLiteral(format!("{l}{r}"))));
+    }
+    if let Some(bytes) = bytes_operation(left, op, right) {
+        return Some(bytes);
+    }
+    match (left, op, right) {
+        (Type::Tuple(l), Operator::Add, Type::Tuple(r)) => {
+            return Some(Type::Tuple(l.iter().chain(r.iter()).cloned().collect()))
+        }
+        (Type::Generic(name, l), Operator::Add, Type::Generic(other, r))
+            if name == other && matches!(name.as_str(), "list" | "tuple") =>
+        {
+            let element = union(l.iter().chain(r.iter()).cloned().collect());
+            return Some(Type::Generic(name.clone(), vec![element]));
+        }
+        (l, Operator::Add, r) if is_str(l) && is_str(r) => return Some(Type::String),
+        // String repetition and %-formatting
+        (Type::Literal(TypeLiteral::StringLiteral(s)), Operator::Mult, count)
+        | (count, Operator::Mult, Type::Literal(TypeLiteral::StringLiteral(s)))
+            if int_value(count).is_some() =>
+        {
+            let count = usize::try_from(int_value(count).unwrap()).unwrap_or(0);
+            return Some(if s.len().saturating_mul(count) > MAX_FOLDED_LEN {
+                Type::String
+            } else {
+                Type::Literal(TypeLiteral::StringLiteral(s.repeat(count)))
+            });
+        }
+        (l, Operator::Mult, r) if is_str(l) && numeric_rank(r).is_some_and(|r| r < 2) => {
+            return Some(Type::String)
+        }
+        (l, Operator::Mult, r) if is_str(r) && numeric_rank(l).is_some_and(|l| l < 2) => {
+            return Some(Type::String)
+        }
+        (l, Operator::Mod, _) if is_str(l) => return Some(Type::String),
+        _ => {}
+    }
+    let rank = numeric_rank(left)?.max(numeric_rank(right)?);
+    match op {
+        Operator::Div => Some(Type::Float),
+        Operator::Add
+        | Operator::Sub
+        | Operator::Mult
+        | Operator::FloorDiv
+        | Operator::Mod
+        | Operator::Pow => Some(if rank == 2 { Type::Float } else { Type::Int }),
+        Operator::LShift
+        | Operator::RShift
+        | Operator::BitAnd
+        | Operator::BitOr
+        | Operator::BitXor
+            if rank < 2 =>
+        {
+            // Bitwise operations on two bools stay bools
+            Some(
+                if rank == 0 && op != Operator::LShift && op != Operator::RShift {
+                    Type::Bool
+                } else {
+                    Type::Int
+                },
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Concatenation and repetition of bytes literals, the only bytes there is a type for.
+fn bytes_operation(left: &Type, op: Operator, right: &Type) -> Option<Type> {
+    let repeat = |bytes: &Vec<u8>, count: i64| {
+        let count = usize::try_from(count).unwrap_or(0);
+        // Don't keep huge values around, the type is all that matters for those
+        if bytes.len().saturating_mul(count) > MAX_FOLDED_LEN {
+            return Type::Unknown;
+        }
+        Type::Literal(TypeLiteral::BytesLiteral(bytes.repeat(count)))
+    };
+    match (left, op, right) {
+        (
+            Type::Literal(TypeLiteral::BytesLiteral(l)),
+            Operator::Add,
+            Type::Literal(TypeLiteral::BytesLiteral(r)),
+        ) => Some(Type::Literal(TypeLiteral::BytesLiteral(
+            l.iter().chain(r.iter()).copied().collect(),
+        ))),
+        (Type::Literal(TypeLiteral::BytesLiteral(bytes)), Operator::Mult, count)
+        | (count, Operator::Mult, Type::Literal(TypeLiteral::BytesLiteral(bytes))) => {
+            Some(match int_value(count) {
+                Some(count) => repeat(bytes, count),
+                None if numeric_rank(count).is_some_and(|r| r < 2) => Type::Unknown,
+                None => return None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The longest string or bytes value repetition is folded into.
+const MAX_FOLDED_LEN: usize = 4096;
+
+/// The type of a unary operation, with the exact value for literal operands.
+pub fn unary_operation(op: UnaryOp, operand: &Type) -> Option<Type> {
+    match (op, operand) {
+        (UnaryOp::Not, operand) => Some(match truthiness(operand) {
+            Some(truthy) => Type::Literal(TypeLiteral::BooleanLiteral(!truthy)),
+            None => Type::Bool,
+        }),
+        (_, Type::Any) => Some(Type::Any),
+        (_, Type::Unknown) => Some(Type::Unknown),
+        (op, operand) => {
+            if let Some(value) = int_value(operand) {
+                let folded = match op {
+                    UnaryOp::USub => value.checked_neg(),
+                    UnaryOp::UAdd => Some(value),
+                    _ => Some(!value),
+                };
+                return Some(match folded {
+                    Some(value) => Type::Literal(TypeLiteral::IntLiteral(value)),
+                    None => Type::Int,
+                });
+            }
+            if let Type::Literal(TypeLiteral::FloatLiteral(value)) = operand {
+                let value: f64 = value.parse().ok()?;
+                return match op {
+                    UnaryOp::USub => Some(Type::Literal(TypeLiteral::FloatLiteral(
+                        (-value).to_string(),
+                    ))),
+                    UnaryOp::UAdd => Some(operand.clone()),
+                    _ => None,
+                };
+            }
+            match (op, numeric_rank(operand)?) {
+                (UnaryOp::Invert, 2) => None,
+                (_, 2) => Some(Type::Float),
+                _ => Some(Type::Int),
+            }
+        }
+    }
+}
+
+/// Whether the type is a builtin scalar, where an operator this module can't combine is a type
+/// error rather than something that might be overloaded.
+pub fn is_scalar(typ: &Type) -> bool {
+    is_str(typ)
+        || numeric_rank(typ).is_some()
+        || typ.is_none()
+        || matches!(typ, Type::Literal(TypeLiteral::BytesLiteral(_)))
+}
+
+/// Order two literal values the way Python would, if both are known and comparable.
+fn order(left: &Type, right: &Type) -> Option<Ordering> {
+    if let (Some(l), Some(r)) = (int_value(left), int_value(right)) {
+        return Some(l.cmp(&r));
+    }
+    match (left, right) {
+        (
+            Type::Literal(TypeLiteral::StringLiteral(l)),
+            Type::Literal(TypeLiteral::StringLiteral(r)),
+        ) => Some(l.cmp(r)),
+        (Type::Tuple(l), Type::Tuple(r)) => {
+            for (l, r) in l.iter().zip(r.iter()) {
+                match order(l, r)? {
+                    Ordering::Equal => continue,
+                    other => return Some(other),
+                }
+            }
+            Some(l.len().cmp(&r.len()))
+        }
+        _ => None,
+    }
+}
+
+/// Whether two literal values are equal, if that is known.
+fn equal(left: &Type, right: &Type) -> Option<bool> {
+    if let (Some(l), Some(r)) = (int_value(left), int_value(right)) {
+        return Some(l == r);
+    }
+    match (left, right) {
+        (Type::Tuple(l), Type::Tuple(r)) => {
+            if l.len() != r.len() {
+                return Some(false);
+            }
+            let mut all_equal = true;
+            for (l, r) in l.iter().zip(r.iter()) {
+                all_equal &= equal(l, r)?;
+            }
+            Some(all_equal)
+        }
+        // Floats can equal ints, leave them alone
+        (Type::Literal(TypeLiteral::FloatLiteral(_)), _)
+        | (_, Type::Literal(TypeLiteral::FloatLiteral(_))) => None,
+        (Type::Literal(l), Type::Literal(r)) => Some(l == r),
+        (l, r) if l.is_none() && r.is_none() => Some(true),
+        _ => None,
+    }
+}
+
+fn compare_pair(left: &Type, op: CmpOp, right: &Type) -> Option<bool> {
+    match op {
+        CmpOp::Eq => equal(left, right),
+        CmpOp::NotEq => equal(left, right).map(|e| !e),
+        CmpOp::Lt => order(left, right).map(Ordering::is_lt),
+        CmpOp::LtE => order(left, right).map(Ordering::is_le),
+        CmpOp::Gt => order(left, right).map(Ordering::is_gt),
+        CmpOp::GtE => order(left, right).map(Ordering::is_ge),
+        CmpOp::Is | CmpOp::IsNot => {
+            let is = match (left.is_none(), right.is_none()) {
+                (true, true) => true,
+                (true, false) if matches!(right, Type::Literal(_)) => false,
+                (false, true) if matches!(left, Type::Literal(_)) => false,
+                _ => return None,
+            };
+            Some(is == (op == CmpOp::Is))
+        }
+        CmpOp::In | CmpOp::NotIn => {
+            let contained = match (left, right) {
+                (
+                    Type::Literal(TypeLiteral::StringLiteral(l)),
+                    Type::Literal(TypeLiteral::StringLiteral(r)),
+                ) => r.contains(l.as_str()),
+                (left, Type::Tuple(elts)) => {
+                    let mut contained = false;
+                    for elt in elts {
+                        contained |= equal(left, elt)?;
+                    }
+                    contained
+                }
+                _ => return None,
+            };
+            Some(contained == (op == CmpOp::In))
+        }
+    }
+}
+
+/// The type of a possibly chained comparison, `a < b < c` is `a < b and b < c`.
+///
+/// Comparisons always give a bool, a literal one when every link can be folded or one of them is
+/// known to be false.
+pub fn comparison(left: &Type, ops: &[CmpOp], comparators: &[Type]) -> Type {
+    let mut all_true = true;
+    let mut previous = left;
+    for (op, right) in ops.iter().zip(comparators.iter()) {
+        match compare_pair(previous, *op, right) {
+            Some(false) => return Type::Literal(TypeLiteral::BooleanLiteral(false)),
+            Some(true) => {}
+            None => all_true = false,
+        }
+        previous = right;
+    }
+    if all_true {
+        Type::Literal(TypeLiteral::BooleanLiteral(true))
+    } else {
+        Type::Bool
+    }
+}
+
+/// The members of the type that aren't always falsy, or always truthy when `truthy` is false.
+fn part_with_truthiness(typ: &Type, truthy: bool) -> Type {
+    let members = match typ {
+        Type::Union(members) => members.as_slice(),
+        typ => std::slice::from_ref(typ),
+    };
+    union(
+        members
+            .iter()
+            .filter(|member| truthiness(member) != Some(!truthy))
+            .cloned()
+            .collect(),
+    )
+}
+
+/// The type of `a and b` or `a or b`, which is one of the operands.
+///
+/// An operand is only given when the ones before it didn't short circuit, so `x or default` with
+/// `x: str | None` is a `str` or the default, and an operand that is always truthy or falsy decides
+/// which operands are reached at all.
+pub fn boolean_operation(op: BoolOp, values: &[Type]) -> Type {
+    // `or` gives the first truthy operand, `and` the first falsy one
+    let stops_on = op == BoolOp::Or;
+    let mut results = vec![];
+    for (i, value) in values.iter().enumerate() {
+        if i + 1 == values.len() {
+            results.push(value.clone());
+            break;
+        }
+        match truthiness(value) {
+            Some(truthy) if truthy == stops_on => {
+                results.push(value.clone());
+                break;
+            }
+            Some(_) => {}
+            None => results.push(part_with_truthiness(value, stops_on)),
+        }
+    }
+    union(results)
+}
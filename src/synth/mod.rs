@@ -15,6 +15,7 @@
 
 mod annotation;
 mod expression;
+mod fold;
 mod statement;
 
 pub use annotation::*;
@@ -13,19 +13,36 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use core::panic;
-use ruff_python_ast::{Expr, ExprContext, Stmt};
-use std::collections::HashMap;
+use ruff_python_ast::{
+    Decorator, ExceptHandler, Expr, ExprContext, Identifier, ParameterWithDefault, Parameters,
+    Stmt, StmtFunctionDef, StmtTry,
+};
+use ruff_text_size::{Ranged, TextRange};
+use std::collections::{HashMap, HashSet};
+use std::iter;
 use std::mem;
 use std::sync::Arc;
 
-use crate::diagnostics::custom::{CantReassignLockedDiag, NotInScopeDiag};
+use crate::analysis::{ScopeSnapshot, TokenKind, UnannotatedFunction, UnannotatedParameter};
+use crate::builtins::builtins;
+use crate::diagnostics::custom::{
+    CantReassignLockedDiag, ConditionalDefinitionDiag, DuplicateDefinitionDiag, DynamicGlobalDiag,
+    ExpectedButGotDiag, ModuleAttributeAssignDiag, MutableDefaultDiag, NotInModuleDiag,
+    ReassignDiag, SpecialMethodDiag, StarImportCollisionDiag, UnknownAttributeDiag,
+    UnreachableBranchDiag, UnsupportedSyntaxDiag, UntypedDefDiag, UnusedParameterDiag,
+};
+use crate::modules::local_module;
 use crate::scope::{Scope, ScopedType};
 use crate::state::{Info, PartialItem, StatementSynthData, StatementSynthDataReturn};
+use crate::stubs::stub_module;
+use crate::symbol::Symbol;
 use crate::synth::synth;
-use crate::types::{union, Class, Function, PartialFunction, Type, TypeLiteral};
+use crate::types::{
+    is_subtype, iterable_element, union, widen, Class, Function, PartialFunction, Type, TypeLiteral,
+};
 
-use super::{check, synth_annotation};
+use super::fold::truthiness;
+use super::{check, module_attribute, synth_annotation};
 
 fn check_func(
     info: &Info,
@@ -33,34 +50,124 @@ fn check_func(
     scope: &mut Scope,
     func: &mut PartialFunction,
 ) {
+    // The signature and body of a @no_type_check function are ignored
+    if has_decorator(&func.ast, "no_type_check") {
+        let params = positional_parameters(&func.ast.parameters);
+        func.args = Some(params.iter().map(|_| Type::Unknown).collect());
+        func.arg_names = Some(
+            params
+                .iter()
+                .map(|a| Symbol::new(a.parameter.name.id.as_str()))
+                .collect(),
+        );
+        func.defaults = Some(params.iter().map(|a| default_text(info, a)).collect());
+        func.ret = Some(Box::new(Type::Unknown));
+        let parameters = &func.ast.parameters;
+        func.positional_only = parameters.posonlyargs.len();
+        func.variadic = parameters
+            .vararg
+            .as_ref()
+            .map(|p| (Symbol::new(p.name.id.as_str()), Box::new(Type::Unknown)));
+        func.keyword_only = parameters
+            .kwonlyargs
+            .iter()
+            .map(|a| {
+                let name = Symbol::new(a.parameter.name.id.as_str());
+                (name, Type::Unknown, default_text(info, a))
+            })
+            .collect();
+        func.keywords = parameters
+            .kwarg
+            .as_ref()
+            .map(|p| (Symbol::new(p.name.id.as_str()), Box::new(Type::Unknown)));
+        return;
+    }
+    if info.is_strict() {
+        check_untyped_def(info, &func.ast);
+    }
     let expected_ret = synth_annotation(info, scope, func.ast.returns.clone().map(|i| *i));
 
     scope.add_scope();
     // Load function arguments
     let mut args = vec![];
     let mut arg_names = vec![];
-    for arg in func.ast.parameters.args.iter() {
+    let mut defaults = vec![];
+    let mut unannotated = vec![];
+    let parameters = func.ast.parameters.clone();
+    for (index, arg) in positional_parameters(&parameters).into_iter().enumerate() {
         let annotation =
             synth_annotation(info, scope, arg.parameter.annotation.clone().map(|i| *i));
-        let mut arg_type_added = false;
-        if let Some(default) = arg.default.clone() {
-            let t = check(info, scope, *default, annotation.clone()).unwrap_or(Type::Unknown);
-            args.push(t);
-            arg_type_added = true;
+        if let Some(kind) = arg.default.as_deref().and_then(mutable_kind) {
+            info.reporter.add(MutableDefaultDiag::new(
+                kind,
+                arg.default.as_ref().unwrap().range(),
+            ));
         }
-        if !arg_type_added {
-            args.push(annotation.clone());
+        let default_type = arg.default.clone().map(|default| {
+            check(info, scope, *default, annotation.clone()).unwrap_or(Type::Unknown)
+        });
+        let typ = parameter_type(arg, annotation, default_type.as_ref());
+        let name = &arg.parameter.name;
+        if arg.parameter.annotation.is_none() && !matches!(name.as_str(), "self" | "cls") {
+            let end = arg.default.as_ref().map_or(name.end(), |d| d.start());
+            unannotated.push(UnannotatedParameter {
+                index,
+                range: TextRange::new(name.end(), end),
+                default: default_type,
+            });
         }
-        let arg_name = Arc::new(arg.parameter.name.id.to_string());
-        scope.set(arg_name.clone(), annotation);
+        args.push(typ.clone());
+        let arg_name = Symbol::new(arg.parameter.name.id.as_str());
+        scope.set(arg_name.clone(), typ);
+        record_binding(
+            info,
+            scope,
+            &arg_name,
+            arg.parameter.name.range,
+            TokenKind::Parameter,
+        );
         arg_names.push(arg_name);
+        defaults.push(default_text(info, arg));
+    }
+
+    func.positional_only = parameters.posonlyargs.len();
+
+    // Extra positional arguments are a tuple and extra keyword arguments a dict in the body
+    if let Some(vararg) = &parameters.vararg {
+        let typ = synth_annotation(info, scope, vararg.annotation.clone().map(|i| *i));
+        let name = bind_parameter(info, scope, &vararg.name, tuple_of(typ.clone()));
+        func.variadic = Some((name, Box::new(typ)));
+    }
+    for arg in parameters.kwonlyargs.iter() {
+        let annotation =
+            synth_annotation(info, scope, arg.parameter.annotation.clone().map(|i| *i));
+        if let Some(kind) = arg.default.as_deref().and_then(mutable_kind) {
+            info.reporter.add(MutableDefaultDiag::new(
+                kind,
+                arg.default.as_ref().unwrap().range(),
+            ));
+        }
+        let default = arg
+            .default
+            .clone()
+            .map(|default| check(info, scope, *default, annotation.clone()).unwrap_or_default());
+        let typ = parameter_type(arg, annotation, default.as_ref());
+        let name = bind_parameter(info, scope, &arg.parameter.name, typ.clone());
+        func.keyword_only.push((name, typ, default_text(info, arg)));
+    }
+    if let Some(kwarg) = &parameters.kwarg {
+        let typ = synth_annotation(info, scope, kwarg.annotation.clone().map(|i| *i));
+        let dict = Type::Generic(Symbol::new("dict"), vec![Type::String, typ.clone()]);
+        let name = bind_parameter(info, scope, &kwarg.name, dict);
+        func.keywords = Some((name, Box::new(typ)));
     }
 
     // Get ready for synthasizing the statements
     func.args = Some(args);
     func.arg_names = Some(arg_names);
+    func.defaults = Some(defaults);
     func.ret = Some(Box::new(Type::Unknown));
-    let new_ret_data = StatementSynthDataReturn::new(expected_ret);
+    let new_ret_data = StatementSynthDataReturn::new(expected_ret.clone());
     let prev_data = mem::replace(&mut data.returns, Some(new_ret_data));
 
     // Synth statements
@@ -71,43 +178,363 @@ fn check_func(
     // Put the data back for the potential outer function
     let this_func_data = mem::replace(&mut data.returns, prev_data);
     func.ret = Some(Box::new(union(this_func_data.unwrap().found_types)));
+    // Stubs and protocols only declare the return type
+    if func.ast.returns.is_some() && is_stub_body(&func.ast.body) {
+        func.ret = Some(Box::new(expected_ret));
+    }
+
+    let returns = func.ast.returns.is_none().then(|| {
+        let found = func.ret.as_deref().cloned().unwrap_or_default();
+        // Falling off the end of the function returns None
+        let ret = match (always_exits(&func.ast.body), found) {
+            (true, found) => found,
+            (false, Type::Never) => Type::None,
+            (false, found) => union(vec![found, Type::None]),
+        };
+        (func.ast.parameters.end(), ret)
+    });
+    if !unannotated.is_empty() || returns.is_some() {
+        info.analysis.add_unannotated(UnannotatedFunction {
+            name: Symbol::new(func.ast.name.id.as_str()),
+            parameters: unannotated,
+            returns,
+        });
+    }
 
+    info.analysis.add_scope(ScopeSnapshot {
+        range: func.ast.range,
+        names: scope.visible(),
+    });
+    if info.config.warn_unused_parameters {
+        check_unused_parameters(info, scope, &func.ast);
+    }
     scope.pop_scope();
+
+    let disabled = disabled_codes(info, &func.ast);
+    if !disabled.is_empty() {
+        info.reporter.suppress(func.ast.range, &disabled);
+    }
+}
+
+/// The type of a parameter, both in the signature and in the function body. An unannotated
+/// parameter takes anything like its default, not just that one value, and a None default says
+/// nothing about the other values it takes.
+fn parameter_type(arg: &ParameterWithDefault, annotation: Type, default: Option<&Type>) -> Type {
+    match default {
+        Some(default) if arg.parameter.annotation.is_none() => match widen(default) {
+            Type::None => Type::Unknown,
+            typ => typ,
+        },
+        _ => annotation,
+    }
+}
+
+/// Add a parameter to the scope of the function body.
+fn bind_parameter(info: &Info, scope: &mut Scope, name: &Identifier, typ: Type) -> Symbol {
+    let symbol = Symbol::new(name.id.as_str());
+    scope.set(symbol.clone(), typ);
+    record_binding(info, scope, &symbol, name.range, TokenKind::Parameter);
+    symbol
+}
+
+/// The type of a tuple of any length with elements of the type.
+fn tuple_of(typ: Type) -> Type {
+    Type::Generic(Symbol::new("tuple"), vec![typ])
+}
+
+/// The variables after one of several branches has run, each with the union of its types at the
+/// end of the branches that bind it.
+fn merge_branches(
+    start: HashMap<Symbol, ScopedType>,
+    ends: Vec<HashMap<Symbol, ScopedType>>,
+) -> HashMap<Symbol, ScopedType> {
+    // Nothing after the branches can run if all of them return or raise
+    if ends.is_empty() {
+        return start;
+    }
+    let mut merged: HashMap<Symbol, ScopedType> = HashMap::new();
+    for end in ends {
+        for (name, scoped) in end {
+            match merged.get_mut(&name) {
+                Some(existing) => {
+                    let typ = union(vec![existing.typ.clone(), scoped.typ.clone()]);
+                    *existing = ScopedType { typ, ..scoped };
+                }
+                None => {
+                    merged.insert(name, scoped);
+                }
+            }
+        }
+    }
+    merged
+}
+
+/// Check the body of a loop, which might run any number of times including none. The variables
+/// at each `break` are kept in `data.breaks` for [`check_loop_else`].
+fn check_loop_body(
+    info: &Info,
+    data: &mut StatementSynthData,
+    scope: &mut Scope,
+    start: HashMap<Symbol, ScopedType>,
+    body: Vec<Stmt>,
+) {
+    let exits = always_exits(&body);
+    data.breaks.push(vec![]);
+    for stmt in body {
+        check_statement(info, data, scope, stmt);
+    }
+    let mut ends = vec![start.clone()];
+    if !exits {
+        ends.push(scope.top_variables());
+    }
+    scope.set_top_variables(merge_branches(start, ends));
+}
+
+/// Check the else clause of a loop, which only runs when the loop ends without a break, and merge
+/// the variables after it with the ones at each break.
+fn check_loop_else(
+    info: &Info,
+    data: &mut StatementSynthData,
+    scope: &mut Scope,
+    start: HashMap<Symbol, ScopedType>,
+    orelse: Vec<Stmt>,
+) {
+    let mut ends = data.breaks.pop().unwrap_or_default();
+    let exits = always_exits(&orelse);
+    for stmt in orelse {
+        check_statement(info, data, scope, stmt);
+    }
+    if !exits {
+        ends.push(scope.top_variables());
+    }
+    scope.set_top_variables(merge_branches(start, ends));
+}
+
+/// Whether the body is only `...`, possibly after a docstring, like the functions in stubs.
+fn is_stub_body(body: &[Stmt]) -> bool {
+    let body = match body.first() {
+        Some(Stmt::Expr(e)) if e.value.is_string_literal_expr() => &body[1..],
+        _ => body,
+    };
+    matches!(body, [Stmt::Expr(e)] if matches!(*e.value, Expr::EllipsisLiteral(_)))
+}
+
+/// Whether running the statements always ends in a return, raise or break, instead of continuing
+/// after them.
+fn always_exits(body: &[Stmt]) -> bool {
+    match body.last() {
+        Some(Stmt::Return(_) | Stmt::Raise(_) | Stmt::Break(_)) => true,
+        Some(Stmt::If(s)) => {
+            always_exits(&s.body)
+                && s.elif_else_clauses.last().is_some_and(|c| c.test.is_none())
+                && s.elif_else_clauses.iter().all(|c| always_exits(&c.body))
+        }
+        Some(Stmt::With(s)) => always_exits(&s.body),
+        _ => false,
+    }
+}
+
+/// Report the parameters of a function that were never read, while its scope is still the top one.
+fn check_unused_parameters(info: &Info, scope: &Scope, def: &StmtFunctionDef) {
+    let function = Symbol::new(def.name.id.as_str());
+    for arg in positional_parameters(&def.parameters) {
+        let name = Symbol::new(arg.parameter.name.id.as_str());
+        if matches!(name.as_str(), "self" | "cls") || name.starts_with('_') {
+            continue;
+        }
+        if !info.analysis.is_read(&scope.top_binding(&name)) {
+            info.reporter.add(UnusedParameterDiag::new(
+                name,
+                function.clone(),
+                arg.parameter.name.range,
+            ));
+        }
+    }
+}
+
+/// The kind of value a mutable container expression creates, for mutable default warnings.
+fn mutable_kind(expr: &Expr) -> Option<&'static str> {
+    match expr {
+        Expr::List(_) | Expr::ListComp(_) => Some("list"),
+        Expr::Dict(_) | Expr::DictComp(_) => Some("dict"),
+        Expr::Set(_) | Expr::SetComp(_) => Some("set"),
+        Expr::Call(call) => match &*call.func {
+            Expr::Name(name) => match name.id.as_str() {
+                "list" => Some("list"),
+                "dict" => Some("dict"),
+                "set" => Some("set"),
+                "bytearray" => Some("bytearray"),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The source text of a parameter's default value.
+fn default_text(info: &Info, arg: &ParameterWithDefault) -> Option<Arc<String>> {
+    arg.default
+        .as_ref()
+        .map(|d| Arc::new(info.file_content[d.range()].to_owned()))
+}
+
+/// The codes in a `# pycavalry: disable=PCV001,PCV002` comment on the line a function is defined
+/// on, which are then not reported anywhere in the function.
+fn disabled_codes(info: &Info, def: &StmtFunctionDef) -> Vec<String> {
+    let content = info.file_content.as_str();
+    let start = def.name.range.start().to_usize();
+    let line_end = content[start..]
+        .find('\n')
+        .map_or(content.len(), |i| start + i);
+    let Some((_, codes)) = content[start..line_end].split_once("# pycavalry: disable=") else {
+        return vec![];
+    };
+    codes
+        .split(',')
+        .map(|c| c.trim().to_owned())
+        .filter(|c| !c.is_empty())
+        .collect()
 }
 
-fn load_module(path: &str) -> HashMap<Arc<String>, ScopedType> {
-    let mut module = HashMap::new();
+fn load_module(info: &Info, path: &str) -> HashMap<Symbol, ScopedType> {
+    // The hardcoded entries below take priority over the stubs
+    let mut module = stub_module(info, path).unwrap_or_default();
 
     // Add any hardcoded extras to built in modules
     match path {
+        "builtins" => module.extend(builtins().as_ref().clone()),
         "sys" => {
             module.insert(
-                Arc::new("version_info".to_owned()),
+                Symbol::new("version_info"),
                 ScopedType::new(Type::Tuple(vec![
                     Type::Literal(TypeLiteral::IntLiteral(3)),
                     Type::Literal(TypeLiteral::IntLiteral(13)),
                 ])),
             );
         }
+        "os" => {
+            module.insert(
+                Symbol::new("getenv"),
+                ScopedType::new(Type::Function(
+                    Function::new(
+                        vec![Type::String, union(vec![Type::String, Type::None])],
+                        vec![Symbol::new("key"), Symbol::new("default")],
+                        Box::new(union(vec![Type::String, Type::None])),
+                    )
+                    .with_defaults(vec![None, Some(Arc::new("None".to_owned()))]),
+                )),
+            );
+        }
+        // Test helpers, calls to them are handled specially when synthesizing
+        "pycavalry" => module.extend(type_checking_helpers()),
+        "collections.abc" => module.extend(special_forms(&[
+            ("Callable", "Callable"),
+            ("Iterable", "Iterable"),
+            ("Iterator", "Iterator"),
+            ("Collection", "Collection"),
+            ("Sequence", "Sequence"),
+            ("MutableSequence", "MutableSequence"),
+            ("Mapping", "Mapping"),
+            ("MutableMapping", "MutableMapping"),
+            ("Set", "AbstractSet"),
+            ("MutableSet", "MutableSet"),
+        ])),
         "typing" => {
+            module.extend(special_forms(&[
+                ("Any", "Any"),
+                ("Union", "Union"),
+                ("Optional", "Optional"),
+                ("Literal", "Literal"),
+                ("Annotated", "Annotated"),
+                ("Never", "Never"),
+                ("NoReturn", "NoReturn"),
+                ("Tuple", "Tuple"),
+                ("Type", "Type"),
+                ("Callable", "Callable"),
+                ("List", "List"),
+                ("Dict", "Dict"),
+                ("Set", "Set"),
+                ("FrozenSet", "FrozenSet"),
+                ("Iterable", "Iterable"),
+                ("Iterator", "Iterator"),
+                ("Collection", "Collection"),
+                ("Sequence", "Sequence"),
+                ("MutableSequence", "MutableSequence"),
+                ("Mapping", "Mapping"),
+                ("MutableMapping", "MutableMapping"),
+                ("AbstractSet", "AbstractSet"),
+                ("MutableSet", "MutableSet"),
+            ]));
+            module.extend(
+                type_checking_helpers()
+                    .into_iter()
+                    .filter(|(name, _)| name.as_str() != "reveal_locals"),
+            );
+            // Only accepts Never, so it errors for any case that hasn't been handled
+            module.insert(
+                Symbol::new("assert_never"),
+                ScopedType::new(Type::Function(Function::new(
+                    vec![Type::Never],
+                    vec![Symbol::new("arg")],
+                    Box::new(Type::Never),
+                ))),
+            );
+            module.insert(Symbol::new("TYPE_CHECKING"), ScopedType::new(Type::Bool));
             module.insert(
-                Arc::new("reveal_type".to_owned()),
+                Symbol::new("no_type_check"),
                 ScopedType::new(Type::Function(Function::new(
                     vec![Type::Any],
-                    vec![Arc::new("obj".to_owned())],
+                    vec![Symbol::new("arg")],
                     Box::new(Type::Any),
                 ))),
             );
         }
+        // Modules of the project being checked, if there are no stubs for them
+        path if module.is_empty() => module.extend(local_module(info, path).unwrap_or_default()),
         _ => {}
     }
 
     module
 }
 
+/// Module members that are special forms, by the name they have in the module and the name of the
+/// special form.
+fn special_forms(names: &[(&str, &str)]) -> Vec<(Symbol, ScopedType)> {
+    names
+        .iter()
+        .map(|(name, form)| {
+            (
+                Symbol::new(name),
+                ScopedType::new(Type::SpecialForm(Symbol::new(form))),
+            )
+        })
+        .collect()
+}
+
+/// `reveal_type`, `assert_type` and `reveal_locals`.
+fn type_checking_helpers() -> Vec<(Symbol, ScopedType)> {
+    let function = |args: Vec<&str>, ret: Type| {
+        ScopedType::new(Type::Function(Function::new(
+            args.iter().map(|_| Type::Any).collect(),
+            args.into_iter().map(Symbol::new).collect(),
+            Box::new(ret),
+        )))
+    };
+    vec![
+        (Symbol::new("reveal_type"), function(vec!["obj"], Type::Any)),
+        (
+            Symbol::new("assert_type"),
+            function(vec!["val", "typ"], Type::Any),
+        ),
+        (Symbol::new("reveal_locals"), function(vec![], Type::None)),
+    ]
+}
+
 pub fn check_statement(info: &Info, data: &mut StatementSynthData, scope: &mut Scope, stmt: Stmt) {
     match stmt {
         Stmt::AnnAssign(ass) => {
+            let annotation_range = ass.annotation.range();
             let annotation = synth_annotation(info, scope, Some(*ass.annotation));
             if let Some(value) = ass.value {
                 check(info, scope, *value, annotation.clone());
@@ -115,21 +542,30 @@ pub fn check_statement(info: &Info, data: &mut StatementSynthData, scope: &mut S
             match *ass.target {
                 Expr::Name(name) => {
                     assert_eq!(name.ctx, ExprContext::Store);
-                    let name_str = Arc::new(name.id.to_string());
+                    let name_str = Symbol::new(name.id.as_str());
                     if let Some(scoped) = scope.get_top_ref(&name_str) {
                         if scoped.is_locked {
                             info.reporter.add(CantReassignLockedDiag::new(
                                 scoped.typ.clone(),
                                 annotation.clone(),
                                 name_str.clone(),
+                                scoped.declared_at,
                                 ass.range,
                             ));
                             return;
                         }
                     };
-                    scope.set(name_str, ScopedType::locked(annotation));
+                    record_binding(info, scope, &name_str, name.range, TokenKind::Variable);
+                    scope.set(
+                        name_str,
+                        ScopedType::locked(annotation, annotation_range)
+                            .with_definition(name.range),
+                    );
                 }
-                node => panic!("Node {:?} not expected in type assignment.", node),
+                node => info.reporter.add(UnsupportedSyntaxDiag::new(
+                    annotated_target_kind(&node),
+                    node.range(),
+                )),
             }
         }
         Stmt::Assign(ass) => {
@@ -137,7 +573,10 @@ pub fn check_statement(info: &Info, data: &mut StatementSynthData, scope: &mut S
                 match target {
                     Expr::Name(name) => {
                         assert_eq!(name.ctx, ExprContext::Store);
-                        let name_str = Arc::new(name.id.to_string());
+                        let name_str = Symbol::new(name.id.as_str());
+                        if info.config.warn_reassign {
+                            check_reassign(info, scope, &name_str, name.range);
+                        }
                         let typ = match scope.get_top_ref(&name_str) {
                             // You are allowed to reassign a variable to a different type, unless it is locked
                             Some(scoped) if scoped.is_locked => {
@@ -150,9 +589,80 @@ pub fn check_statement(info: &Info, data: &mut StatementSynthData, scope: &mut S
                             }
                             _ => synth(info, scope, *ass.value.clone()),
                         };
-                        scope.set(name_str, typ);
+                        record_binding(info, scope, &name_str, name.range, TokenKind::of(&typ));
+                        scope.set(name_str, ScopedType::new(typ).with_definition(name.range));
+                    }
+                    Expr::Attribute(attr) => {
+                        let target_range = attr.range;
+                        match synth(info, scope, *attr.value) {
+                            Type::Module(module_name, module) => {
+                                let name = Symbol::new(attr.attr.id.as_str());
+                                match module.get(&name) {
+                                    Some(member) => {
+                                        check(info, scope, *ass.value.clone(), member.typ.clone());
+                                    }
+                                    None => {
+                                        synth(info, scope, *ass.value.clone());
+                                    }
+                                }
+                                let exists = module.contains_key(&name);
+                                // Modules with a __getattr__ expect attributes they don't define
+                                let is_dynamic = module_attribute(&module, &name).is_some();
+                                if exists || !is_dynamic {
+                                    info.reporter.add(ModuleAttributeAssignDiag::new(
+                                        module_name,
+                                        name,
+                                        exists,
+                                        target_range,
+                                    ));
+                                }
+                            }
+                            Type::Instance(cls) => {
+                                let name = attr.attr.id.as_str();
+                                // Attributes the class doesn't define go through its __setattr__
+                                let expected = match (cls.member(name), cls.member("__setattr__")) {
+                                    (Some(typ), _) => Some(typ),
+                                    (None, Some(Type::Function(setattr))) => {
+                                        Some(setattr.args.get(1).cloned().unwrap_or_default())
+                                    }
+                                    (None, _) if !cls.has_known_members() => None,
+                                    (None, _) => {
+                                        info.reporter.add(UnknownAttributeDiag::new(
+                                            Type::Instance(cls.clone()),
+                                            Symbol::new(name),
+                                            attr.attr.range,
+                                        ));
+                                        None
+                                    }
+                                };
+                                match expected {
+                                    Some(expected) => {
+                                        check(info, scope, *ass.value.clone(), expected);
+                                    }
+                                    None => {
+                                        synth(info, scope, *ass.value.clone());
+                                    }
+                                }
+                            }
+                            _ => {
+                                synth(info, scope, *ass.value.clone());
+                                info.reporter.add(UnsupportedSyntaxDiag::new(
+                                    "Assignment to an attribute",
+                                    target_range,
+                                ));
+                            }
+                        }
+                    }
+                    Expr::Subscript(sub) if is_globals_call(&sub.value) => {
+                        synth(info, scope, *ass.value.clone());
+                        info.reporter.add(DynamicGlobalDiag::new(sub.range));
+                    }
+                    node => {
+                        // Still check the value, even if the target can't be assigned
+                        synth(info, scope, *ass.value.clone());
+                        info.reporter
+                            .add(UnsupportedSyntaxDiag::new(target_kind(&node), node.range()));
                     }
-                    node => panic!("Node {:?} not expected in assignment.", node),
                 }
             }
         }
@@ -176,66 +686,794 @@ pub fn check_statement(info: &Info, data: &mut StatementSynthData, scope: &mut S
             // TODO: Add the new return value into returns
         }
         Stmt::FunctionDef(def) => {
-            let func_name = Arc::new(def.name.id.to_string());
+            let func_name = Symbol::new(def.name.id.as_str());
+            let name_range = def.name.range;
+            let doc = docstring(&def.body);
 
             let mut partial_func = PartialFunction {
-                ast: def,
+                ast: Box::new(def),
                 args: None,
                 arg_names: None,
+                defaults: None,
+                positional_only: 0,
                 ret: None,
+                variadic: None,
+                keyword_only: vec![],
+                keywords: None,
             };
             check_func(info, data, scope, &mut partial_func);
             let typ = match Function::try_from(partial_func) {
-                Ok(func) => Type::Function(func),
+                Ok(func) => Type::Function(func.with_docstring(doc.clone())),
                 Err(func) => {
                     data.partial_list
                         .push_back(PartialItem::new(info.file_name.clone(), func_name.clone()));
                     Type::PartialFunction(func)
                 }
             };
-            scope.set(func_name, typ);
+            record_binding(info, scope, &func_name, name_range, TokenKind::Function);
+            scope.set(
+                func_name,
+                ScopedType::new(typ)
+                    .with_definition(name_range)
+                    .with_docstring(doc),
+            );
         }
         Stmt::ClassDef(def) => {
-            let cls_name = Arc::new(def.name.id.to_string());
+            let cls_name = Symbol::new(def.name.id.as_str());
+            let doc = docstring(&def.body);
+            check_duplicate_members(info, &def.body);
+            check_special_methods(info, &def.body);
+            let bases = def
+                .arguments
+                .iter()
+                .flat_map(|args| args.args.iter())
+                .map(|base| synth_annotation(info, scope, Some(base.clone())))
+                .collect();
+            let class = Class::new(info.module_name(), cls_name.clone(), vec![], vec![])
+                .with_bases(bases)
+                .with_docstring(doc.clone());
+            // The annotations of the methods can refer to the class itself
             scope.set(
                 cls_name.clone(),
-                Type::Class(Class::new(cls_name.clone(), vec![], vec![])),
+                ScopedType::new(Type::Class(class.clone())),
+            );
+            let typ = Type::Class(with_members(info, scope, class, &def.body));
+            record_binding(info, scope, &cls_name, def.name.range, TokenKind::Class);
+            scope.set(
+                cls_name,
+                ScopedType::new(typ)
+                    .with_definition(def.name.range)
+                    .with_docstring(doc),
             );
         }
         Stmt::Pass(_) => (),
         // TODO: Implement imports
         Stmt::Import(import) => {
             for alias in import.names {
-                let module = load_module(&alias.name.id);
-                let name = Arc::new(alias.name.id.to_string());
+                let module = load_module(info, &alias.name.id);
+                let module_name = Symbol::new(alias.name.id.as_str());
+                let bound = alias.asname.as_ref().unwrap_or(&alias.name);
+                let name = Symbol::new(bound.id.as_str());
+                let typ = Type::Module(module_name.clone(), module);
+                record_binding(info, scope, &name, bound.range, TokenKind::Module);
                 scope.set(
-                    name.clone(),
-                    Type::Module(
-                        alias
-                            .asname
-                            .map(|i| Arc::new(i.id.to_string()))
-                            .unwrap_or(name),
-                        module,
-                    ),
+                    name,
+                    ScopedType::new(typ)
+                        .with_definition(alias.range)
+                        .with_import(module_name),
                 );
             }
         }
         Stmt::ImportFrom(import) => {
-            let module = load_module(&import.module.expect("From import without module?"));
+            let Some(module) = import.module else {
+                info.reporter
+                    .add(UnsupportedSyntaxDiag::new("Relative import", import.range));
+                return;
+            };
+            let module_name = Symbol::new(module.id.as_str());
+            let module = load_module(info, &module_name);
             for alias in import.names {
-                let Some(submodule) = module.get(&alias.name.id.to_string()) else {
-                    info.reporter.add(NotInScopeDiag::new(
+                if alias.name.id == "*" {
+                    import_star(info, scope, &module_name, &module, alias.range);
+                    continue;
+                }
+                let Some(submodule) = module.get(&Symbol::new(alias.name.id.as_str())) else {
+                    let mut available: Vec<Symbol> = module.keys().cloned().collect();
+                    available.sort();
+                    info.reporter.add(NotInModuleDiag::new(
+                        module_name.clone(),
                         alias.name.id.to_string().into(),
+                        available,
                         alias.range,
                     ));
 
                     continue;
                 };
 
-                let name = Arc::new(alias.name.id.to_string());
-                scope.set(name.clone(), submodule.clone());
+                let bound = alias.asname.as_ref().unwrap_or(&alias.name);
+                let name = Symbol::new(bound.id.as_str());
+                let kind = TokenKind::of(&submodule.typ);
+                record_binding(info, scope, &name, bound.range, kind);
+                let imported = submodule
+                    .clone()
+                    .with_definition(alias.range)
+                    .with_import(module_name.clone());
+                scope.set(name, imported);
+            }
+        }
+        Stmt::While(s) => {
+            synth(info, scope, *s.test);
+            let start = scope.top_variables();
+            check_loop_body(info, data, scope, start.clone(), s.body);
+            check_loop_else(info, data, scope, start, s.orelse);
+        }
+        Stmt::For(s) if !s.is_async => {
+            let typ = match synth(info, scope, *s.iter) {
+                Type::Tuple(types) => union(types),
+                other => iterable_element(&other).unwrap_or(Type::Unknown),
+            };
+            let start = scope.top_variables();
+            match *s.target {
+                Expr::Name(name) => {
+                    let name_str = Symbol::new(name.id.as_str());
+                    record_binding(info, scope, &name_str, name.range, TokenKind::of(&typ));
+                    match scope.get_top_ref(&name_str) {
+                        // An annotated loop variable keeps its declared type
+                        Some(scoped) if scoped.is_locked => {
+                            if !is_subtype(&typ, &scoped.typ) {
+                                info.reporter.add(ExpectedButGotDiag::new(
+                                    scoped.typ.clone(),
+                                    typ,
+                                    name.range,
+                                ));
+                            }
+                        }
+                        _ => scope.set(name_str, ScopedType::new(typ).with_definition(name.range)),
+                    }
+                }
+                target => info.reporter.add(UnsupportedSyntaxDiag::new(
+                    target_kind(&target),
+                    target.range(),
+                )),
+            }
+            check_loop_body(info, data, scope, start.clone(), s.body);
+            check_loop_else(info, data, scope, start, s.orelse);
+        }
+        Stmt::Break(_) => {
+            if let Some(breaks) = data.breaks.last_mut() {
+                breaks.push(scope.top_variables());
+            }
+        }
+        Stmt::Continue(_) => (),
+        Stmt::If(s)
+            if is_type_checking(&s.test)
+                && s.elif_else_clauses.iter().all(|c| c.test.is_none()) =>
+        {
+            for stmt in s.body {
+                check_statement(info, data, scope, stmt);
+            }
+            let fallback = s.elif_else_clauses.into_iter().flat_map(|c| c.body);
+            check_fallback(info, data, scope, fallback.collect());
+        }
+        Stmt::Try(s) if is_import_guard(&s) => {
+            for stmt in s.body {
+                check_statement(info, data, scope, stmt);
+            }
+            let fallback = s
+                .handlers
+                .into_iter()
+                .flat_map(|ExceptHandler::ExceptHandler(h)| h.body);
+            check_fallback(info, data, scope, fallback.collect());
+            for stmt in s.orelse.into_iter().chain(s.finalbody) {
+                check_statement(info, data, scope, stmt);
+            }
+        }
+        Stmt::Try(s) if !s.is_star => {
+            // The else clause runs after the body, and each handler from the variables before the
+            // try statement since the exception could have come from any part of the body. Those
+            // that don't return or raise are merged before the finally clause.
+            let start = scope.top_variables();
+            let mut ends = vec![];
+            let body: Vec<Stmt> = s.body.into_iter().chain(s.orelse).collect();
+            let exits = always_exits(&body);
+            for stmt in body {
+                check_statement(info, data, scope, stmt);
+            }
+            if !exits {
+                ends.push(scope.top_variables());
+            }
+            for ExceptHandler::ExceptHandler(handler) in s.handlers {
+                scope.set_top_variables(start.clone());
+                let typ = match handler.type_.map(|t| *t) {
+                    Some(Expr::Tuple(tuple)) => union(
+                        tuple
+                            .elts
+                            .into_iter()
+                            .map(|t| synth_annotation(info, scope, Some(t)))
+                            .collect(),
+                    ),
+                    Some(t) => synth_annotation(info, scope, Some(t)),
+                    None => Type::Unknown,
+                };
+                let exits = always_exits(&handler.body);
+                let Some(name) = handler.name else {
+                    for stmt in handler.body {
+                        check_statement(info, data, scope, stmt);
+                    }
+                    if !exits {
+                        ends.push(scope.top_variables());
+                    }
+                    continue;
+                };
+                let name_str = Symbol::new(name.id.as_str());
+                record_binding(info, scope, &name_str, name.range, TokenKind::of(&typ));
+                scope.set(
+                    name_str.clone(),
+                    ScopedType::new(typ).with_definition(name.range),
+                );
+                for stmt in handler.body {
+                    check_statement(info, data, scope, stmt);
+                }
+                // Python deletes the exception variable at the end of the handler
+                scope.remove(&name_str);
+                if !exits {
+                    ends.push(scope.top_variables());
+                }
+            }
+            scope.set_top_variables(merge_branches(start, ends));
+            for stmt in s.finalbody {
+                check_statement(info, data, scope, stmt);
+            }
+        }
+        Stmt::If(s) => {
+            let branches = iter::once((Some(*s.test), s.body))
+                .chain(s.elif_else_clauses.into_iter().map(|c| (c.test, c.body)));
+            // Every branch starts with the variables from before the if statement, and the ones
+            // that don't return or raise are merged at the end
+            let start = scope.top_variables();
+            let mut ends = vec![];
+            // Whether an earlier branch always runs, so none of the later ones can
+            let mut earlier_taken = false;
+            for (test, body) in branches {
+                scope.set_top_variables(start.clone());
+                let truthiness = match test {
+                    _ if earlier_taken => None,
+                    Some(test) => truthiness(&synth(info, scope, test)),
+                    None => Some(true),
+                };
+                if earlier_taken || truthiness == Some(false) {
+                    if let (true, Some(first), Some(last)) =
+                        (info.config.warn_unreachable, body.first(), body.last())
+                    {
+                        info.reporter.add(UnreachableBranchDiag::new(
+                            earlier_taken,
+                            TextRange::new(first.start(), last.end()),
+                        ));
+                    }
+                    continue;
+                }
+                earlier_taken = truthiness == Some(true);
+                let exits = always_exits(&body);
+                for stmt in body {
+                    check_statement(info, data, scope, stmt);
+                }
+                if !exits {
+                    ends.push(scope.top_variables());
+                }
+            }
+            // Without an else branch none of the branches might run
+            if !earlier_taken {
+                ends.push(start.clone());
+            }
+            scope.set_top_variables(merge_branches(start, ends));
+        }
+        node => info
+            .reporter
+            .add(UnsupportedSyntaxDiag::new(stmt_kind(&node), node.range())),
+    }
+}
+
+/// Whether an if condition is `TYPE_CHECKING` or `typing.TYPE_CHECKING`.
+fn is_type_checking(test: &Expr) -> bool {
+    match test {
+        Expr::Name(name) => name.id == "TYPE_CHECKING",
+        Expr::Attribute(attr) => attr.attr.id == "TYPE_CHECKING",
+        _ => false,
+    }
+}
+
+/// Whether a try statement only catches failed imports, like `except ImportError:`.
+fn is_import_guard(s: &StmtTry) -> bool {
+    let is_import_error = |expr: &Expr| match expr {
+        Expr::Name(name) => matches!(name.id.as_str(), "ImportError" | "ModuleNotFoundError"),
+        _ => false,
+    };
+    !s.is_star
+        && !s.handlers.is_empty()
+        && s.handlers
+            .iter()
+            .all(|ExceptHandler::ExceptHandler(h)| match h.type_.as_deref() {
+                Some(Expr::Tuple(tuple)) => tuple.elts.iter().all(is_import_error),
+                Some(expr) => is_import_error(expr),
+                None => false,
+            })
+}
+
+/// Check the fallback branch of a TYPE_CHECKING or import guard in its own scope.
+///
+/// The names bound in the main branch keep their types, with a diagnostic when the fallback binds
+/// them to something else. A fallback of None makes them optional instead.
+fn check_fallback(info: &Info, data: &mut StatementSynthData, scope: &mut Scope, body: Vec<Stmt>) {
+    scope.add_scope();
+    for stmt in body {
+        check_statement(info, data, scope, stmt);
+    }
+    let mut fallbacks: Vec<(Symbol, ScopedType)> = scope.pop_scope().into_iter().collect();
+    fallbacks.sort_by_key(|(_, f)| f.defined_at.map(|r| r.start()));
+    for (name, fallback) in fallbacks {
+        let Some(main) = scope.get_top(&name) else {
+            scope.set(name, fallback);
+            continue;
+        };
+        if fallback.typ.is_none() {
+            let typ = union(vec![main.typ.clone(), Type::None]);
+            scope.set(name, ScopedType { typ, ..main });
+        } else if !is_subtype(&fallback.typ, &main.typ) {
+            info.reporter.add(ConditionalDefinitionDiag::new(
+                name,
+                main.typ,
+                fallback.typ,
+                main.defined_at,
+                fallback.defined_at.unwrap_or_default(),
+            ));
+        }
+    }
+}
+
+/// Report the missing annotations of a function for strict mode.
+fn check_untyped_def(info: &Info, def: &StmtFunctionDef) {
+    let exempt = info
+        .config
+        .strict_exempt_decorators
+        .iter()
+        .any(|d| has_decorator(def, d));
+    if exempt {
+        return;
+    }
+    let function = Symbol::new(def.name.id.as_str());
+    for arg in positional_parameters(&def.parameters) {
+        if arg.parameter.annotation.is_none() {
+            info.reporter.add(UntypedDefDiag::new(
+                function.clone(),
+                Some(Symbol::new(arg.parameter.name.id.as_str())),
+                arg.parameter.range,
+            ));
+        }
+    }
+    if def.returns.is_none() {
+        info.reporter
+            .add(UntypedDefDiag::new(function, None, def.name.range));
+    }
+}
+
+/// Whether the function has a decorator with the name.
+fn has_decorator(def: &StmtFunctionDef, name: &str) -> bool {
+    def.decorator_list
+        .iter()
+        .any(|d| decorator_name(d) == Some(name))
+}
+
+/// The name a decorator is referred to by, `wraps` for both `@wraps(f)` and `@functools.wraps(f)`.
+fn decorator_name(decorator: &Decorator) -> Option<&str> {
+    let mut expr = &decorator.expression;
+    if let Expr::Call(call) = expr {
+        expr = &call.func;
+    }
+    match expr {
+        Expr::Name(name) => Some(name.id.as_str()),
+        Expr::Attribute(attr) => Some(attr.attr.id.as_str()),
+        _ => None,
+    }
+}
+
+/// Warn about assigning to a name that was imported or is a module level constant.
+fn check_reassign(info: &Info, scope: &Scope, name: &Symbol, range: TextRange) {
+    let Some(scoped) = scope.get_top_ref(name) else {
+        return;
+    };
+    let is_constant = scope.top_binding(name).scope == 0
+        && name.chars().any(char::is_alphabetic)
+        && !name.chars().any(char::is_lowercase);
+    if scoped.imported_from.is_some() || is_constant {
+        info.reporter.add(ReassignDiag::new(
+            name.clone(),
+            scoped.imported_from.clone(),
+            scoped.defined_at,
+            range,
+        ));
+    }
+}
+
+/// Report methods and annotated fields that are defined more than once in a class body.
+fn check_duplicate_members(info: &Info, body: &[Stmt]) {
+    let mut seen: HashMap<Symbol, TextRange> = HashMap::new();
+    for stmt in body {
+        let (kind, name, range) = match stmt {
+            Stmt::FunctionDef(def)
+                if !["overload", "setter", "deleter"]
+                    .iter()
+                    .any(|d| has_decorator(def, d)) =>
+            {
+                ("method", def.name.id.to_string(), def.name.range)
+            }
+            Stmt::AnnAssign(ass) => match &*ass.target {
+                Expr::Name(name) => ("field", name.id.to_string(), name.range),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        let name = Symbol::from(name);
+        match seen.get(&name) {
+            Some(first) => info
+                .reporter
+                .add(DuplicateDefinitionDiag::new(kind, name, *first, range)),
+            None => {
+                seen.insert(name, range);
+            }
+        }
+    }
+}
+
+/// Add the methods and attributes of a class from its body, without checking the bodies of the
+/// methods. Attributes without an annotation are Unknown.
+fn with_members(info: &Info, scope: &mut Scope, class: Class, body: &[Stmt]) -> Class {
+    let mut methods = vec![];
+    let mut attributes = vec![];
+    for stmt in body {
+        match stmt {
+            Stmt::AnnAssign(ass) => {
+                if let Expr::Name(name) = &*ass.target {
+                    let typ = synth_annotation(info, scope, Some(*ass.annotation.clone()));
+                    attributes.push((Symbol::new(name.id.as_str()), typ));
+                }
+            }
+            Stmt::Assign(ass) => {
+                for target in &ass.targets {
+                    if let Expr::Name(name) = target {
+                        attributes.push((Symbol::new(name.id.as_str()), Type::Unknown));
+                    }
+                }
+            }
+            Stmt::FunctionDef(def) => {
+                let name = Symbol::new(def.name.id.as_str());
+                if has_decorator(def, "property") {
+                    let typ = synth_annotation(info, scope, def.returns.clone().map(|r| *r));
+                    attributes.push((name, typ));
+                } else if !["overload", "setter", "deleter"]
+                    .iter()
+                    .any(|d| has_decorator(def, d))
+                {
+                    methods.push((name, method_signature(info, scope, def)));
+                }
+                if !has_decorator(def, "staticmethod") && !has_decorator(def, "classmethod") {
+                    if let Some(this) = positional_parameters(&def.parameters).first() {
+                        let this = this.parameter.name.id.as_str();
+                        self_attributes(info, scope, this, &def.body, &mut attributes);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    // Attributes declared in the class body win over ones assigned in methods
+    let mut seen = HashSet::new();
+    attributes.retain(|(name, _)| seen.insert(name.clone()));
+    Class {
+        functions: methods,
+        parameters: attributes,
+        ..class
+    }
+}
+
+/// The parameters that can be passed by position, the positional only ones before the others.
+fn positional_parameters(parameters: &Parameters) -> Vec<&ParameterWithDefault> {
+    parameters
+        .posonlyargs
+        .iter()
+        .chain(parameters.args.iter())
+        .collect()
+}
+
+/// The signature of a method from its annotations, without `self` or `cls`.
+fn method_signature(info: &Info, scope: &mut Scope, def: &StmtFunctionDef) -> Function {
+    let skip = usize::from(!has_decorator(def, "staticmethod"));
+    let params = positional_parameters(&def.parameters);
+    let params = &params[skip.min(params.len())..];
+    let args = params
+        .iter()
+        .map(|arg| synth_annotation(info, scope, arg.parameter.annotation.clone().map(|a| *a)))
+        .collect();
+    let arg_names = params
+        .iter()
+        .map(|arg| Symbol::new(arg.parameter.name.id.as_str()))
+        .collect();
+    let ret = synth_annotation(info, scope, def.returns.clone().map(|r| *r));
+    let mut func = Function::new(args, arg_names, Box::new(ret))
+        .with_defaults(params.iter().map(|arg| default_text(info, arg)).collect())
+        .with_positional_only(def.parameters.posonlyargs.len().saturating_sub(skip))
+        .with_docstring(docstring(&def.body));
+    if let Some(vararg) = &def.parameters.vararg {
+        let typ = synth_annotation(info, scope, vararg.annotation.clone().map(|a| *a));
+        func = func.with_variadic(Symbol::new(vararg.name.id.as_str()), typ);
+    }
+    let keyword_only = def
+        .parameters
+        .kwonlyargs
+        .iter()
+        .map(|arg| {
+            let name = Symbol::new(arg.parameter.name.id.as_str());
+            let typ = synth_annotation(info, scope, arg.parameter.annotation.clone().map(|a| *a));
+            (name, typ, default_text(info, arg))
+        })
+        .collect();
+    func = func.with_keyword_only(keyword_only);
+    if let Some(kwarg) = &def.parameters.kwarg {
+        let typ = synth_annotation(info, scope, kwarg.annotation.clone().map(|a| *a));
+        func = func.with_keywords(Symbol::new(kwarg.name.id.as_str()), typ);
+    }
+    func
+}
+
+/// The attributes assigned to `self` in the body of a method, anywhere but in nested functions.
+fn self_attributes(
+    info: &Info,
+    scope: &mut Scope,
+    this: &str,
+    body: &[Stmt],
+    attributes: &mut Vec<(Symbol, Type)>,
+) {
+    let attribute_of_self = |target: &Expr| match target {
+        Expr::Attribute(attr) => match &*attr.value {
+            Expr::Name(name) if name.id == this => Some(Symbol::new(attr.attr.id.as_str())),
+            _ => None,
+        },
+        _ => None,
+    };
+    for stmt in body {
+        match stmt {
+            Stmt::AnnAssign(ass) => {
+                if let Some(name) = attribute_of_self(&ass.target) {
+                    let typ = synth_annotation(info, scope, Some(*ass.annotation.clone()));
+                    attributes.push((name, typ));
+                }
+            }
+            Stmt::Assign(ass) => {
+                for target in &ass.targets {
+                    if let Some(name) = attribute_of_self(target) {
+                        attributes.push((name, Type::Unknown));
+                    }
+                }
+            }
+            Stmt::If(s) => {
+                self_attributes(info, scope, this, &s.body, attributes);
+                for clause in &s.elif_else_clauses {
+                    self_attributes(info, scope, this, &clause.body, attributes);
+                }
+            }
+            Stmt::For(s) => {
+                self_attributes(info, scope, this, &s.body, attributes);
+                self_attributes(info, scope, this, &s.orelse, attributes);
+            }
+            Stmt::While(s) => {
+                self_attributes(info, scope, this, &s.body, attributes);
+                self_attributes(info, scope, this, &s.orelse, attributes);
+            }
+            Stmt::With(s) => self_attributes(info, scope, this, &s.body, attributes),
+            Stmt::Try(s) => {
+                self_attributes(info, scope, this, &s.body, attributes);
+                for ExceptHandler::ExceptHandler(handler) in &s.handlers {
+                    self_attributes(info, scope, this, &handler.body, attributes);
+                }
+                self_attributes(info, scope, this, &s.orelse, attributes);
+                self_attributes(info, scope, this, &s.finalbody, attributes);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Bind every public name of a module for `from module import *`, warning about names that
+/// replace ones imported from another module.
+fn import_star(
+    info: &Info,
+    scope: &mut Scope,
+    module_name: &Symbol,
+    module: &HashMap<Symbol, ScopedType>,
+    range: TextRange,
+) {
+    let mut names: Vec<&Symbol> = module.keys().filter(|n| !n.starts_with('_')).collect();
+    names.sort();
+    for name in names {
+        let previous = scope
+            .get_top_ref(name)
+            .and_then(|s| s.imported_from.clone());
+        if let Some(previous) = previous.filter(|p| p != module_name) {
+            info.reporter.add(StarImportCollisionDiag::new(
+                name.clone(),
+                previous,
+                module_name.clone(),
+                range,
+            ));
+        }
+        let imported = module[name]
+            .clone()
+            .with_definition(range)
+            .with_import(module_name.clone());
+        scope.set(name.clone(), imported);
+    }
+}
+
+/// Report constructor related methods in a class body that Python can't call the way it expects.
+fn check_special_methods(info: &Info, body: &[Stmt]) {
+    for stmt in body {
+        let Stmt::FunctionDef(def) = stmt else {
+            continue;
+        };
+        let method = Symbol::new(def.name.id.as_str());
+        match method.as_str() {
+            "__init__" => {
+                let returns = def.returns.as_deref();
+                if let Some(ret) = returns.filter(|r| !matches!(r, Expr::NoneLiteral(_))) {
+                    info.reporter.add(SpecialMethodDiag::new(
+                        method.clone(),
+                        "must return None",
+                        ret.range(),
+                    ));
+                }
+                let mut ranges = vec![];
+                value_returns(&def.body, &mut ranges);
+                for range in ranges {
+                    info.reporter.add(SpecialMethodDiag::new(
+                        method.clone(),
+                        "must return None",
+                        range,
+                    ));
+                }
+            }
+            "__new__" | "__init_subclass__" => {
+                let params = &def.parameters;
+                if params.posonlyargs.is_empty()
+                    && params.args.is_empty()
+                    && params.vararg.is_none()
+                {
+                    info.reporter.add(SpecialMethodDiag::new(
+                        method,
+                        "must take the class as its first parameter",
+                        def.parameters.range,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The return statements with a value other than None in a function body, not counting the ones
+/// in nested functions and classes.
+fn value_returns(body: &[Stmt], ranges: &mut Vec<TextRange>) {
+    for stmt in body {
+        match stmt {
+            Stmt::Return(ret)
+                if ret
+                    .value
+                    .as_deref()
+                    .is_some_and(|v| !matches!(v, Expr::NoneLiteral(_))) =>
+            {
+                ranges.push(ret.range);
+            }
+            Stmt::If(s) => {
+                value_returns(&s.body, ranges);
+                for clause in s.elif_else_clauses.iter() {
+                    value_returns(&clause.body, ranges);
+                }
             }
+            Stmt::For(s) => {
+                value_returns(&s.body, ranges);
+                value_returns(&s.orelse, ranges);
+            }
+            Stmt::While(s) => {
+                value_returns(&s.body, ranges);
+                value_returns(&s.orelse, ranges);
+            }
+            Stmt::With(s) => value_returns(&s.body, ranges),
+            Stmt::Try(s) => {
+                value_returns(&s.body, ranges);
+                for ExceptHandler::ExceptHandler(handler) in s.handlers.iter() {
+                    value_returns(&handler.body, ranges);
+                }
+                value_returns(&s.orelse, ranges);
+                value_returns(&s.finalbody, ranges);
+            }
+            Stmt::Match(s) => {
+                for case in s.cases.iter() {
+                    value_returns(&case.body, ranges);
+                }
+            }
+            _ => {}
         }
-        node => panic!("Statement not yet supported: {:?}", node),
+    }
+}
+
+/// Record the name being bound in the top scope as a reference to its variable.
+fn record_binding(info: &Info, scope: &Scope, name: &Symbol, range: TextRange, kind: TokenKind) {
+    info.analysis
+        .add_reference(scope.top_binding(name), range, kind);
+}
+
+/// The docstring of a function or class, the string literal its body starts with.
+fn docstring(body: &[Stmt]) -> Option<Arc<String>> {
+    match body.first()? {
+        Stmt::Expr(expr) => match expr.value.as_ref() {
+            Expr::StringLiteral(s) => Some(Arc::new(s.value.to_str().to_owned())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether the expression is a call to `globals()`.
+fn is_globals_call(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call(call) => matches!(call.func.as_ref(), Expr::Name(name) if name.id == "globals"),
+        _ => false,
+    }
+}
+
+fn annotated_target_kind(target: &Expr) -> &'static str {
+    match target {
+        Expr::Attribute(_) => "Annotated assignment to an attribute",
+        Expr::Subscript(_) => "Annotated assignment to a subscript",
+        _ => "Annotated assignment target",
+    }
+}
+
+fn target_kind(target: &Expr) -> &'static str {
+    match target {
+        Expr::Attribute(_) => "Assignment to an attribute",
+        Expr::Subscript(_) => "Assignment to a subscript",
+        Expr::Tuple(_) | Expr::List(_) => "Unpacking assignment",
+        Expr::Starred(_) => "Starred assignment",
+        _ => "Assignment target",
+    }
+}
+
+/// Human readable name of the kind of statement, for diagnostics.
+fn stmt_kind(stmt: &Stmt) -> &'static str {
+    match stmt {
+        Stmt::FunctionDef(_) => "Function definition",
+        Stmt::ClassDef(_) => "Class definition",
+        Stmt::Return(_) => "Return statement",
+        Stmt::Delete(_) => "Delete statement",
+        Stmt::Assign(_) => "Assignment",
+        Stmt::AugAssign(_) => "Augmented assignment",
+        Stmt::AnnAssign(_) => "Annotated assignment",
+        Stmt::TypeAlias(_) => "Type alias statement",
+        Stmt::For(_) => "For loop",
+        Stmt::While(_) => "While loop",
+        Stmt::If(_) => "If statement",
+        Stmt::With(_) => "With statement",
+        Stmt::Match(_) => "Match statement",
+        Stmt::Raise(_) => "Raise statement",
+        Stmt::Try(_) => "Try statement",
+        Stmt::Assert(_) => "Assert statement",
+        Stmt::Import(_) => "Import statement",
+        Stmt::ImportFrom(_) => "From import statement",
+        Stmt::Global(_) => "Global statement",
+        Stmt::Nonlocal(_) => "Nonlocal statement",
+        Stmt::Expr(_) => "Expression statement",
+        Stmt::Pass(_) => "Pass statement",
+        Stmt::Break(_) => "Break statement",
+        Stmt::Continue(_) => "Continue statement",
+        Stmt::IpyEscapeCommand(_) => "IPython escape command",
     }
 }
@@ -0,0 +1,205 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Helpers for writing golden tests against the checker, enabled with the `testing` feature.
+
+use std::{
+    fs,
+    ops::{Deref, Range},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use ruff_python_parser::{parse, Mode};
+use ruff_text_size::{TextRange, TextSize};
+
+use crate::{error_check_file, synth_annotation, Diag, Info, Scope, Symbol, Type};
+
+/// Shorthand to quickly create an **a**rc **st**ring.
+pub fn ars(s: impl Into<String>) -> Arc<String> {
+    Arc::new(s.into())
+}
+/// Shorthand to quickly create a symbol.
+pub fn sym(s: &str) -> Symbol {
+    Symbol::new(s)
+}
+/// Quckly create a text range from a rust range.
+pub fn r(r: Range<u32>) -> TextRange {
+    TextRange::new(TextSize::from(r.start), TextSize::from(r.end))
+}
+/// Quckly create a type from a python annotation.
+pub fn ann(s: &str) -> Type {
+    let info = Info::default();
+    let module = parse(s, Mode::Expression).unwrap();
+    let parsed = match module.into_syntax() {
+        ruff_python_ast::Mod::Module(_) => unreachable!(),
+        ruff_python_ast::Mod::Expression(e) => e,
+    };
+    let typ = synth_annotation(&info, &mut Scope::new(), Some(*parsed.body));
+    assert_errors(&info, vec![]);
+    assert_ne!(typ, Type::Unknown);
+    typ
+}
+
+/// A directory in the system temporary directory for the files of a test, removed again when
+/// it's dropped. Every directory gets a unique path, so tests running at the same time, or in
+/// separate test binaries, never share one.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "pycavalry_test_{}_{}_{}",
+            name,
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        // Left over from an earlier run with the same process id that didn't clean up
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn assert_errors(info: &Info, expected: Vec<Box<dyn Diag>>) {
+    let errors_lock = info.reporter.errors();
+    let errors = errors_lock.lock().unwrap();
+    if errors.len() != expected.len() {
+        println!(
+            "\nDifferent count of expected vs received errors. Expected:\n{:?}\n\nReceived:\n{:?}",
+            expected, errors
+        );
+        panic!("");
+    }
+    for (error, expected) in errors.iter().zip(expected.iter()) {
+        assert_eq!(error, expected);
+    }
+}
+pub fn run_with_errors(
+    filename: impl Into<PathBuf>,
+    content: impl Into<String>,
+    expected: Vec<Box<dyn Diag>>,
+) {
+    let info = error_check_file(filename.into(), content.into()).unwrap();
+    assert_errors(&info, expected);
+}
+
+/// A diagnostic expected by a `# Debug:` comment.
+#[derive(Clone, Debug, PartialEq)]
+struct Expectation {
+    line: usize,
+    columns: Option<Range<usize>>,
+    message: String,
+}
+
+/// Parse the `# Debug: message` or `# Debug(start..end): message` comments on a line, where start
+/// and end are byte columns on the line. Several can follow each other for a line with more than
+/// one diagnostic.
+fn parse_expectations(line: usize, text: &str) -> Vec<Expectation> {
+    text.split("# Debug")
+        .skip(1)
+        .filter_map(|rest| parse_expectation(line, rest))
+        .collect()
+}
+
+fn parse_expectation(line: usize, rest: &str) -> Option<Expectation> {
+    let (columns, message) = match rest.strip_prefix('(') {
+        Some(rest) => {
+            let (range, message) = rest.split_once("):")?;
+            let (start, end) = range.split_once("..")?;
+            let columns = start.trim().parse().ok()?..end.trim().parse().ok()?;
+            (Some(columns), message)
+        }
+        None => (None, rest.strip_prefix(':')?),
+    };
+    Some(Expectation {
+        line,
+        columns,
+        message: message.trim().to_owned(),
+    })
+}
+
+/// Check a file and compare the diagnostics against the `# Debug:` comments in it.
+///
+/// Every diagnostic has to start on a line with a `# Debug:` comment containing its message, and
+/// every comment has to be matched by a diagnostic. A comment can also pin down the exact columns
+/// with `# Debug(start..end): message`, and one line can have several comments after each other.
+pub fn run_with_expectations(filename: impl Into<PathBuf>, content: impl Into<String>) {
+    let content = content.into();
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let mut expectations: Vec<Expectation> = content
+        .lines()
+        .enumerate()
+        .flat_map(|(line, text)| parse_expectations(line, text))
+        .collect();
+
+    let info = error_check_file(filename.into(), content.clone()).unwrap();
+    let errors_lock = info.reporter.errors();
+    let errors = errors_lock.lock().unwrap();
+    let mut unexpected = vec![];
+    for error in errors.iter() {
+        let start = error.range().start().to_usize();
+        let line = line_starts.partition_point(|&s| s <= start) - 1;
+        let columns = start - line_starts[line]..error.range().end().to_usize() - line_starts[line];
+        let message = error.message();
+        let found = expectations.iter().position(|e| {
+            e.line == line && e.message == message && e.columns.iter().all(|c| *c == columns)
+        });
+        match found {
+            Some(i) => {
+                expectations.remove(i);
+            }
+            None => unexpected.push(format!("{}:{:?}: {}", line + 1, columns, message)),
+        }
+    }
+
+    if !unexpected.is_empty() || !expectations.is_empty() {
+        let missing: Vec<String> = expectations
+            .iter()
+            .map(|e| format!("{}:{:?}: {}", e.line + 1, e.columns, e.message))
+            .collect();
+        panic!(
+            "\nDiagnostics didn't match the # Debug: comments.\n\nUnexpected:\n{}\n\nMissing:\n{}",
+            unexpected.join("\n"),
+            missing.join("\n")
+        );
+    }
+}
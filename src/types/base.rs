@@ -15,9 +15,15 @@
 
 use core::fmt;
 use ruff_python_ast::{LiteralExpressionRef, Number, StmtFunctionDef};
-use std::{collections::HashMap, hash::Hash, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    hash::Hash,
+    sync::Arc,
+};
 
 use crate::scope::ScopedType;
+use crate::symbol::Symbol;
 
 fn write_iter<I, T, F>(f: &mut fmt::Formatter<'_>, vals: I, func: F) -> fmt::Result
 where
@@ -35,6 +41,101 @@ where
     Ok(())
 }
 
+/// How unions that aren't only literals are displayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, clap::ValueEnum)]
+pub enum UnionStyle {
+    /// Union[int, None]
+    Union,
+    /// Optional[int] for a single type and None, Optional[Union[int, str]] for more and
+    /// Union[int, str] without None
+    #[default]
+    Optional,
+    /// int | None
+    Pipe,
+}
+
+thread_local! {
+    static UNION_STYLE: Cell<UnionStyle> = const { Cell::new(UnionStyle::Optional) };
+}
+
+/// Puts the previous union style back when dropped, so it is restored even if the function
+/// given to [`with_union_style`] panics.
+struct UnionStyleGuard(UnionStyle);
+
+impl Drop for UnionStyleGuard {
+    fn drop(&mut self) {
+        UNION_STYLE.with(|s| s.set(self.0));
+    }
+}
+
+/// Display unions with the given style while running the function.
+pub fn with_union_style<T>(style: UnionStyle, f: impl FnOnce() -> T) -> T {
+    let _guard = UnionStyleGuard(UNION_STYLE.with(|s| s.replace(style)));
+    f()
+}
+
+thread_local! {
+    static QUALIFIED_CLASSES: RefCell<Vec<Symbol>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Restores the qualified classes when dropped, also when the function panics.
+struct QualifiedClassesGuard(Vec<Symbol>);
+
+impl Drop for QualifiedClassesGuard {
+    fn drop(&mut self) {
+        QUALIFIED_CLASSES.with(|q| q.replace(std::mem::take(&mut self.0)));
+    }
+}
+
+/// Display classes with the module they were defined in while running the function, if a
+/// different class with the same name appears in the types.
+pub fn with_qualified_collisions<T>(types: &[&Type], f: impl FnOnce() -> T) -> T {
+    let mut classes = vec![];
+    for typ in types {
+        collect_classes(typ, &mut classes);
+    }
+    let colliding: Vec<Symbol> = classes
+        .iter()
+        .filter(|a| {
+            classes
+                .iter()
+                .any(|b| a.name == b.name && a.module != b.module)
+        })
+        .map(|c| c.name.clone())
+        .collect();
+
+    let _guard = QualifiedClassesGuard(QUALIFIED_CLASSES.with(|q| q.replace(colliding)));
+    f()
+}
+
+fn collect_classes<'a>(typ: &'a Type, classes: &mut Vec<&'a Class>) {
+    match typ {
+        Type::Class(cls) | Type::Instance(cls) => classes.push(cls),
+        Type::Tuple(types)
+        | Type::Union(types)
+        | Type::Intersection(types)
+        | Type::Generic(_, types) => {
+            for typ in types {
+                collect_classes(typ, classes);
+            }
+        }
+        Type::Function(func) => {
+            let variadic = func.variadic.iter().chain(func.keywords.iter());
+            let keyword_only = func.keyword_only.iter().map(|(_, typ, _)| typ);
+            for typ in func
+                .args
+                .iter()
+                .chain(variadic.map(|(_, typ)| &**typ))
+                .chain(keyword_only)
+            {
+                collect_classes(typ, classes);
+            }
+            collect_classes(&func.ret, classes);
+        }
+        _ => {}
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Default)]
 pub enum Type {
     Any,
@@ -53,10 +154,58 @@ pub enum Type {
     Literal(TypeLiteral),
     Function(Function),
     PartialFunction(PartialFunction),
+    /// The class object itself, `type[C]`
     Class(Class),
+    /// An instance of a class
+    Instance(Class),
 
     Union(Vec<Type>),
-    Module(Arc<String>, HashMap<Arc<String>, ScopedType>),
+    /// Values that are all of the types at once, only created by narrowing, see `intersection`
+    Intersection(Vec<Type>),
+    Module(Symbol, HashMap<Symbol, ScopedType>),
+    /// A builtin generic class or abstract base class with its type arguments, like `list[int]`
+    Generic(Symbol, Vec<Type>),
+    /// A name from `typing` like `Union` or `List`, which only has a meaning in annotations
+    SpecialForm(Symbol),
+}
+
+impl Type {
+    pub fn is_none(&self) -> bool {
+        matches!(self, Type::None | Type::Literal(TypeLiteral::NoneLiteral))
+    }
+}
+
+fn display_union(f: &mut fmt::Formatter<'_>, types: &[Type]) -> fmt::Result {
+    let style = UNION_STYLE.with(|s| s.get());
+    let not_none: Vec<Type> = types.iter().filter(|t| !t.is_none()).cloned().collect();
+    // None is written as itself instead of as Literal[None]
+    let write_member = |f: &mut fmt::Formatter<'_>, t: &Type| {
+        if t.is_none() {
+            write!(f, "None")
+        } else {
+            write!(f, "{}", t)
+        }
+    };
+    match style {
+        UnionStyle::Optional if not_none.len() < types.len() => match not_none.as_slice() {
+            [only] => write!(f, "Optional[{}]", only),
+            _ => write!(f, "Optional[{}]", Type::Union(not_none)),
+        },
+        UnionStyle::Union | UnionStyle::Optional => {
+            write!(f, "Union[")?;
+            write_iter(f, types.iter(), write_member)?;
+            write!(f, "]")
+        }
+        UnionStyle::Pipe => {
+            for (i, t) in types.iter().enumerate() {
+                if i != 0 {
+                    write!(f, " | ")?;
+                }
+                write_member(f, t)?;
+            }
+            Ok(())
+        }
+    }
 }
 
 impl fmt::Display for Type {
@@ -79,21 +228,40 @@ impl fmt::Display for Type {
             Type::Literal(l) => write!(f, "{}", l),
             Type::Function(func) => write!(f, "{}", func),
             Type::PartialFunction(_) => write!(f, "Partial Func"),
-            Type::Class(cls) => write!(f, "{}", cls),
+            Type::Class(cls) => write!(f, "type[{}]", cls),
+            Type::Instance(cls) => write!(f, "{}", cls),
             Type::Union(types) => {
-                if types.iter().all(|i| matches!(i, Type::Literal(_))) {
+                if types
+                    .iter()
+                    .all(|i| matches!(i, Type::Literal(_)) && !i.is_none())
+                {
                     write!(f, "Literal[")?;
                     write_iter(f, types.iter(), |f, t| match t {
                         Type::Literal(l) => display_type_literal_inside(f, l),
                         _ => unreachable!(),
                     })?;
+                    write!(f, "]")
                 } else {
-                    write!(f, "Union[")?;
-                    write_iter(f, types.iter(), |f, t| write!(f, "{}", t))?;
+                    display_union(f, types)
                 }
+            }
+            Type::Intersection(types) => {
+                write!(f, "Intersection[")?;
+                write_iter(f, types.iter(), |f, t| write!(f, "{}", t))?;
                 write!(f, "]")
             }
             Type::Module(name, _) => write!(f, "module[{}]", name),
+            Type::Generic(name, args) if name.as_str() == "tuple" => {
+                write!(f, "tuple[")?;
+                write_iter(f, args.iter(), |f, t| write!(f, "{}", t))?;
+                write!(f, ", ...]")
+            }
+            Type::Generic(name, args) => {
+                write!(f, "{}[", name)?;
+                write_iter(f, args.iter(), |f, t| write!(f, "{}", t))?;
+                write!(f, "]")
+            }
+            Type::SpecialForm(name) => write!(f, "typing.{}", name),
         }?;
         Ok(())
     }
@@ -102,16 +270,33 @@ impl fmt::Display for Type {
 #[derive(Clone, Debug, PartialEq)]
 pub struct Function {
     pub args: Vec<Type>,
-    pub arg_names: Vec<Arc<String>>,
+    pub arg_names: Vec<Symbol>,
+    /// Source code of the default value of each argument
+    pub defaults: Vec<Option<Arc<String>>>,
+    /// Number of leading arguments before a `/`, which can only be passed by position
+    pub positional_only: usize,
     pub ret: Box<Type>,
+    pub docstring: Option<Arc<String>>,
+    /// Name and type of the `*args` parameter taking any number of extra positional arguments
+    pub variadic: Option<(Symbol, Box<Type>)>,
+    /// Name, type and source of the default value of the parameters after `*` or `*args`, which
+    /// can only be passed by keyword
+    pub keyword_only: Vec<(Symbol, Type, Option<Arc<String>>)>,
+    /// Name and type of the `**kwargs` parameter taking any other keyword arguments
+    pub keywords: Option<(Symbol, Box<Type>)>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct PartialFunction {
-    pub ast: StmtFunctionDef,
+    pub ast: Box<StmtFunctionDef>,
     pub args: Option<Vec<Type>>,
-    pub arg_names: Option<Vec<Arc<String>>>,
+    pub arg_names: Option<Vec<Symbol>>,
+    pub defaults: Option<Vec<Option<Arc<String>>>>,
+    pub positional_only: usize,
     pub ret: Option<Box<Type>>,
+    pub variadic: Option<(Symbol, Box<Type>)>,
+    pub keyword_only: Vec<(Symbol, Type, Option<Arc<String>>)>,
+    pub keywords: Option<(Symbol, Box<Type>)>,
 }
 
 impl TryFrom<PartialFunction> for Function {
@@ -121,7 +306,13 @@ impl TryFrom<PartialFunction> for Function {
             Ok(Function {
                 args: value.args.unwrap(),
                 arg_names: value.arg_names.unwrap(),
+                defaults: value.defaults.unwrap_or_default(),
+                positional_only: value.positional_only,
                 ret: value.ret.unwrap(),
+                docstring: None,
+                variadic: value.variadic,
+                keyword_only: value.keyword_only,
+                keywords: value.keywords,
             })
         } else {
             Err(value)
@@ -130,51 +321,197 @@ impl TryFrom<PartialFunction> for Function {
 }
 
 impl Function {
-    pub fn new(args: Vec<Type>, arg_names: Vec<Arc<String>>, ret: Box<Type>) -> Function {
+    pub fn new(args: Vec<Type>, arg_names: Vec<Symbol>, ret: Box<Type>) -> Function {
         Function {
+            defaults: vec![None; args.len()],
+            positional_only: 0,
             args,
             arg_names,
             ret,
+            docstring: None,
+            variadic: None,
+            keyword_only: vec![],
+            keywords: None,
         }
     }
+
+    pub fn with_variadic(mut self, name: Symbol, typ: Type) -> Function {
+        self.variadic = Some((name, Box::new(typ)));
+        self
+    }
+
+    pub fn with_keyword_only(
+        mut self,
+        keyword_only: Vec<(Symbol, Type, Option<Arc<String>>)>,
+    ) -> Function {
+        self.keyword_only = keyword_only;
+        self
+    }
+
+    pub fn with_keywords(mut self, name: Symbol, typ: Type) -> Function {
+        self.keywords = Some((name, Box::new(typ)));
+        self
+    }
+
+    pub fn with_positional_only(mut self, positional_only: usize) -> Function {
+        self.positional_only = positional_only;
+        self
+    }
+
+    pub fn with_defaults(mut self, defaults: Vec<Option<Arc<String>>>) -> Function {
+        self.defaults = defaults;
+        self
+    }
+
+    /// Number of leading arguments without a default value, which every call has to pass.
+    pub fn required_args(&self) -> usize {
+        self.defaults
+            .iter()
+            .position(Option::is_some)
+            .unwrap_or(self.args.len())
+    }
+
+    pub fn with_docstring(mut self, docstring: Option<Arc<String>>) -> Function {
+        self.docstring = docstring;
+        self
+    }
 }
 
 impl fmt::Display for Function {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "(")?;
-        write_iter(
-            f,
-            self.arg_names.iter().zip(self.args.iter()),
-            |f, (name, typ)| write!(f, "{name}: {typ}"),
-        )?;
+        let mut first = true;
+        let mut separator = |f: &mut fmt::Formatter| {
+            let separator = if first { "" } else { ", " };
+            first = false;
+            write!(f, "{separator}")
+        };
+        for (i, (name, typ)) in self.arg_names.iter().zip(self.args.iter()).enumerate() {
+            if i > 0 && i == self.positional_only {
+                separator(f)?;
+                write!(f, "/")?;
+            }
+            separator(f)?;
+            if name.is_empty() {
+                write!(f, "{typ}")?;
+            } else {
+                write!(f, "{name}: {typ}")?;
+            }
+        }
+        if self.positional_only > 0 && self.positional_only >= self.args.len() {
+            separator(f)?;
+            write!(f, "/")?;
+        }
+        match &self.variadic {
+            Some((name, typ)) => {
+                separator(f)?;
+                write!(f, "*{name}: {typ}")?;
+            }
+            None if !self.keyword_only.is_empty() => {
+                separator(f)?;
+                write!(f, "*")?;
+            }
+            None => {}
+        }
+        for (name, typ, _) in &self.keyword_only {
+            separator(f)?;
+            write!(f, "{name}: {typ}")?;
+        }
+        if let Some((name, typ)) = &self.keywords {
+            separator(f)?;
+            write!(f, "**{name}: {typ}")?;
+        }
         write!(f, ") -> {}", self.ret)
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct Class {
-    pub name: Arc<String>,
-    pub functions: Vec<Function>,
-    pub parameters: Vec<(String, Type)>,
+    /// Name of the module the class was defined in
+    pub module: Symbol,
+    pub name: Symbol,
+    /// The methods, without the `self` parameter
+    pub functions: Vec<(Symbol, Function)>,
+    /// The attributes declared in the class body or assigned to `self` in a method
+    pub parameters: Vec<(Symbol, Type)>,
+    /// The classes and builtin types this class inherits from
+    pub bases: Vec<Type>,
+    pub docstring: Option<Arc<String>>,
 }
 
 impl Class {
     pub fn new(
-        name: Arc<String>,
-        functions: Vec<Function>,
-        parameters: Vec<(String, Type)>,
+        module: Symbol,
+        name: Symbol,
+        functions: Vec<(Symbol, Function)>,
+        parameters: Vec<(Symbol, Type)>,
     ) -> Class {
         Class {
+            module,
             name,
             functions,
             parameters,
+            bases: vec![],
+            docstring: None,
         }
     }
+
+    pub fn with_bases(mut self, bases: Vec<Type>) -> Class {
+        self.bases = bases;
+        self
+    }
+
+    pub fn with_docstring(mut self, docstring: Option<Arc<String>>) -> Class {
+        self.docstring = docstring;
+        self
+    }
+
+    /// The type of an attribute or method, looking through the base classes in order.
+    pub fn member(&self, name: &str) -> Option<Type> {
+        let attribute = self.parameters.iter().find(|(n, _)| n.as_str() == name);
+        let method = self.functions.iter().find(|(n, _)| n.as_str() == name);
+        attribute
+            .map(|(_, typ)| typ.clone())
+            .or_else(|| method.map(|(_, func)| Type::Function(func.clone())))
+            .or_else(|| {
+                self.bases.iter().find_map(|base| match base {
+                    Type::Instance(base) => base.member(name),
+                    _ => None,
+                })
+            })
+    }
+
+    /// Whether every attribute of the class is known, which isn't the case for builtin classes,
+    /// classes with a `__getattr__` and classes inheriting from something that isn't a class.
+    pub fn has_known_members(&self) -> bool {
+        self.module != "builtins"
+            && !self
+                .functions
+                .iter()
+                .any(|(name, _)| matches!(name.as_str(), "__getattr__" | "__getattribute__"))
+            && self.bases.iter().all(|base| match base {
+                Type::Instance(base) => base.has_known_members(),
+                _ => false,
+            })
+    }
+}
+
+/// Classes are compared by where they are defined, so the class its own methods see while it is
+/// being defined is the same as the finished one.
+impl PartialEq for Class {
+    fn eq(&self, other: &Self) -> bool {
+        self.module == other.module && self.name == other.name
+    }
 }
 
 impl fmt::Display for Class {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "type[{}]", self.name)
+        let qualify = QUALIFIED_CLASSES.with(|q| q.borrow().contains(&self.name));
+        if qualify {
+            write!(f, "{}.{}", self.module, self.name)
+        } else {
+            write!(f, "{}", self.name)
+        }
     }
 }
 
@@ -203,7 +540,7 @@ pub enum TypeLiteral {
 fn display_type_literal_inside(f: &mut fmt::Formatter, literal: &TypeLiteral) -> fmt::Result {
     match literal {
         TypeLiteral::StringLiteral(i) => write!(f, "\"{}\"", i),
-        TypeLiteral::BytesLiteral(i) => write!(f, "b\"{:?}\"", i),
+        TypeLiteral::BytesLiteral(i) => write!(f, "b\"{}\"", i.escape_ascii()),
         TypeLiteral::IntLiteral(i) => write!(f, "{}", i),
         TypeLiteral::FloatLiteral(i) => write!(f, "{}", i),
         TypeLiteral::BooleanLiteral(i) => write!(f, "{}", if *i { "True" } else { "False" }),
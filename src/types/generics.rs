@@ -0,0 +1,117 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The builtin generic containers and the abstract base classes from `collections.abc` they
+//! implement, with the type parameters each one passes on to its parent.
+
+use super::{is_subtype, union, Type};
+
+/// Name, number of type parameters and parent of every known generic class.
+const GENERICS: [(&str, usize, Option<&str>); 14] = [
+    // Tuples of any length, tuple[int, ...]
+    ("tuple", 1, Some("Sequence")),
+    ("list", 1, Some("MutableSequence")),
+    ("dict", 2, Some("MutableMapping")),
+    ("set", 1, Some("MutableSet")),
+    ("frozenset", 1, Some("AbstractSet")),
+    ("MutableSequence", 1, Some("Sequence")),
+    ("Sequence", 1, Some("Collection")),
+    ("MutableMapping", 2, Some("Mapping")),
+    // Mappings are collections of their keys
+    ("Mapping", 2, Some("Collection")),
+    ("MutableSet", 1, Some("AbstractSet")),
+    ("AbstractSet", 1, Some("Collection")),
+    ("Collection", 1, Some("Iterable")),
+    ("Iterator", 1, Some("Iterable")),
+    ("Iterable", 1, None),
+];
+
+/// The canonical name of a generic class, also accepting the capitalized aliases from `typing`.
+pub fn generic_name(name: &str) -> Option<&'static str> {
+    let name = match name {
+        "List" => "list",
+        "Dict" => "dict",
+        "Set" => "set",
+        "FrozenSet" => "frozenset",
+        name => name,
+    };
+    GENERICS.iter().find(|g| g.0 == name).map(|g| g.0)
+}
+
+/// How many type parameters a generic class takes.
+pub fn generic_arity(name: &str) -> usize {
+    GENERICS.iter().find(|g| g.0 == name).map_or(0, |g| g.1)
+}
+
+fn parent(name: &str, mut args: Vec<Type>) -> Option<(&'static str, Vec<Type>)> {
+    let parent = GENERICS.iter().find(|g| g.0 == name)?.2?;
+    args.truncate(generic_arity(parent));
+    Some((parent, args))
+}
+
+/// Whether a type parameter only ever comes out of the class, so it can be replaced by a subtype.
+/// Anything that can be modified has to keep the exact type.
+fn is_covariant(name: &str, param: usize) -> bool {
+    match name {
+        "list" | "dict" | "set" | "MutableSequence" | "MutableMapping" | "MutableSet" => false,
+        "Mapping" => param == 1,
+        _ => true,
+    }
+}
+
+/// The type arguments a is seen with as the generic class `target`, if it is one.
+fn as_generic(a: &Type, target: &str) -> Option<Vec<Type>> {
+    let (mut name, mut args) = match a {
+        Type::Generic(name, args) => (generic_name(name)?, args.clone()),
+        // An empty tuple is a tuple of anything
+        Type::Tuple(types) if types.is_empty() => ("tuple", vec![Type::Any]),
+        Type::Tuple(types) => ("tuple", vec![union(types.clone())]),
+        Type::String => ("Sequence", vec![Type::String]),
+        _ => return None,
+    };
+    while name != target {
+        (name, args) = parent(name, args)?;
+    }
+    Some(args)
+}
+
+/// The type of the elements a for loop over a value of the type gets, if it's iterable.
+pub fn iterable_element(typ: &Type) -> Option<Type> {
+    as_generic(typ, "Iterable")?.pop()
+}
+
+/// The type of the elements of a sequence like a list, tuple or string, if the type is one.
+pub fn sequence_element(typ: &Type) -> Option<Type> {
+    as_generic(typ, "Sequence")?.pop()
+}
+
+/// The key and value types of a mapping like a dict, if the type is one.
+pub fn mapping_items(typ: &Type) -> Option<(Type, Type)> {
+    let mut args = as_generic(typ, "Mapping")?;
+    let value = args.pop()?;
+    Some((args.pop()?, value))
+}
+
+/// Check if a is a subtype of the generic class `name` with the type arguments `args`.
+pub(super) fn is_generic_subtype(a: &Type, name: &str, args: &[Type]) -> bool {
+    let Some(a_args) = as_generic(a, name) else {
+        return false;
+    };
+    a_args
+        .iter()
+        .zip(args.iter())
+        .enumerate()
+        .all(|(i, (a, b))| is_subtype(a, b) && (is_covariant(name, i) || is_subtype(b, a)))
+}
@@ -13,7 +13,9 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use super::{Type, TypeLiteral};
+use std::cmp::Ordering;
+
+use super::{generics::is_generic_subtype, Type, TypeLiteral};
 
 /// Check if a is a subtype of b, A is a subtype of b if a can do everything b can.
 pub fn is_subtype(a: &Type, b: &Type) -> bool {
@@ -24,7 +26,8 @@ pub fn is_subtype(a: &Type, b: &Type) -> bool {
     if let Type::Literal(literal) = a {
         return match literal {
             TypeLiteral::StringLiteral(_) => is_subtype(&Type::String, b),
-            TypeLiteral::BytesLiteral(_) => unimplemented!(),
+            // TODO: Check against bytes once there is a type for it
+            TypeLiteral::BytesLiteral(_) => matches!(b, Type::Any | Type::Unknown),
             TypeLiteral::IntLiteral(_) => is_subtype(&Type::Int, b),
             TypeLiteral::FloatLiteral(_) => is_subtype(&Type::Float, b),
             TypeLiteral::BooleanLiteral(_) => is_subtype(&Type::Bool, b),
@@ -37,8 +40,11 @@ pub fn is_subtype(a: &Type, b: &Type) -> bool {
         (Type::Any | Type::Unknown, _) => true,
         (_, Type::Any | Type::Unknown) => true,
         (Type::Int, Type::Float) => true,
+        (Type::Bool, Type::Int | Type::Float) => true,
         (Type::Never, _) => false,
         (Type::Union(union), b) => union.iter().all(|a| is_subtype(a, b)),
+        (a, Type::Intersection(types)) => types.iter().all(|b| is_subtype(a, b)),
+        (Type::Intersection(types), b) => types.iter().any(|a| is_subtype(a, b)),
         (a, Type::Union(union)) => union.iter().any(|b| is_subtype(a, b)),
         (Type::Function(f1), Type::Function(f2)) => {
             f1.args.len() == f2.args.len()
@@ -47,8 +53,19 @@ pub fn is_subtype(a: &Type, b: &Type) -> bool {
                     .iter()
                     .enumerate()
                     .all(|(i, t1)| is_subtype(&f2.args[i], t1))
+                && match (&f1.variadic, &f2.variadic) {
+                    (Some((_, t1)), Some((_, t2))) => is_subtype(t2, t1),
+                    (None, Some(_)) => false,
+                    _ => true,
+                }
                 && is_subtype(&f1.ret, &f2.ret)
         }
+        (Type::Class(c1), Type::Class(_)) => c1.bases.iter().any(|base| match base {
+            Type::Instance(base) => is_subtype(&Type::Class(base.clone()), b),
+            _ => false,
+        }),
+        (Type::Instance(c1), b) => c1.bases.iter().any(|base| is_subtype(base, b)),
+        (a, Type::Generic(name, args)) => is_generic_subtype(a, name, args),
         (Type::Tuple(t1), Type::Tuple(t2)) => {
             if t1.len() == t2.len() {
                 t1.iter().zip(t2.iter()).all(|(t1, t2)| is_subtype(t1, t2))
@@ -94,8 +111,49 @@ fn collapse_union_types(mut types: Vec<Type>) -> Vec<Type> {
     types = collapse_subtypes(types);
     types
 }
+/// Where a kind of type goes in a union, literals first and None last.
+fn union_rank(typ: &Type) -> u8 {
+    match typ {
+        Type::Literal(TypeLiteral::BooleanLiteral(_)) => 0,
+        Type::Literal(TypeLiteral::IntLiteral(_)) => 1,
+        Type::Literal(TypeLiteral::FloatLiteral(_)) => 2,
+        Type::Literal(TypeLiteral::StringLiteral(_)) => 3,
+        Type::Literal(TypeLiteral::BytesLiteral(_)) => 4,
+        Type::Literal(TypeLiteral::EllipsisLiteral) => 5,
+        Type::Bool => 6,
+        Type::Int => 7,
+        Type::Float => 8,
+        Type::String => 9,
+        Type::Ellipsis => 10,
+        Type::Tuple(_) => 11,
+        Type::Generic(..) => 12,
+        Type::Instance(_) => 13,
+        Type::Class(_) => 14,
+        Type::Function(_) | Type::PartialFunction(_) => 15,
+        Type::Module(..) | Type::SpecialForm(_) => 16,
+        Type::Union(_) | Type::Intersection(_) => 17,
+        Type::Any | Type::Unknown | Type::Never => 18,
+        Type::None | Type::Literal(TypeLiteral::NoneLiteral) => 19,
+    }
+}
+
+/// The canonical order of union members, so unions with the same members are equal and display
+/// the same no matter what order they were created in.
+fn union_order(a: &Type, b: &Type) -> Ordering {
+    union_rank(a)
+        .cmp(&union_rank(b))
+        .then_with(|| match (a, b) {
+            (
+                Type::Literal(TypeLiteral::IntLiteral(a)),
+                Type::Literal(TypeLiteral::IntLiteral(b)),
+            ) => a.cmp(b),
+            _ => a.to_string().cmp(&b.to_string()),
+        })
+}
+
 pub fn union(mut types: Vec<Type>) -> Type {
     types = collapse_union_types(types);
+    types.sort_by(union_order);
 
     if types.is_empty() {
         Type::Never
@@ -105,3 +163,106 @@ pub fn union(mut types: Vec<Type>) -> Type {
         Type::Union(types)
     }
 }
+
+/// Replace literal types with the type of their value, what a variable initialized with the value
+/// would be annotated with.
+pub fn widen(typ: &Type) -> Type {
+    match typ {
+        Type::Literal(literal) => match literal {
+            TypeLiteral::StringLiteral(_) => Type::String,
+            TypeLiteral::IntLiteral(_) => Type::Int,
+            TypeLiteral::FloatLiteral(_) => Type::Float,
+            TypeLiteral::BooleanLiteral(_) => Type::Bool,
+            TypeLiteral::NoneLiteral => Type::None,
+            TypeLiteral::EllipsisLiteral => Type::Ellipsis,
+            TypeLiteral::BytesLiteral(_) => typ.clone(),
+        },
+        Type::Tuple(types) => Type::Tuple(types.iter().map(widen).collect()),
+        Type::Union(types) => union(types.iter().map(widen).collect()),
+        Type::Generic(name, args) => Type::Generic(name.clone(), args.iter().map(widen).collect()),
+        typ => typ.clone(),
+    }
+}
+
+/// Whether no value can have both types, for types where subclassing can't make one value both.
+fn is_disjoint(a: &Type, b: &Type) -> bool {
+    let is_closed = |t: &Type| {
+        matches!(
+            t,
+            Type::Literal(_)
+                | Type::String
+                | Type::Int
+                | Type::Float
+                | Type::Bool
+                | Type::None
+                | Type::Ellipsis
+                | Type::Tuple(_)
+                | Type::Module(..)
+        )
+    };
+    is_closed(a) && is_closed(b) && !is_subtype(a, b) && !is_subtype(b, a)
+}
+
+/// The type of values that are all of the given types at once, like after narrowing with several
+/// isinstance checks.
+///
+/// Members that are supertypes of another member are dropped, unions are distributed so the result
+/// is a union of intersections, and members that can't overlap make the result Never.
+pub fn intersection(types: Vec<Type>) -> Type {
+    let mut flattened: Vec<Type> = Vec::with_capacity(types.len());
+    for typ in types.into_iter() {
+        match typ {
+            Type::Intersection(types) => flattened.extend(types),
+            // Intersecting with Any doesn't tell anything new
+            Type::Any | Type::Unknown => {}
+            other => flattened.push(other),
+        }
+    }
+
+    // (A | B) & C is (A & C) | (B & C)
+    if let Some(i) = flattened.iter().position(|t| matches!(t, Type::Union(_))) {
+        let Type::Union(arms) = flattened.remove(i) else {
+            unreachable!()
+        };
+        return union(
+            arms.into_iter()
+                .map(|arm| {
+                    let mut types = flattened.clone();
+                    types.push(arm);
+                    intersection(types)
+                })
+                .filter(|t| *t != Type::Never)
+                .collect(),
+        );
+    }
+
+    if flattened.contains(&Type::Never)
+        || flattened
+            .iter()
+            .enumerate()
+            .any(|(i, a)| flattened[i + 1..].iter().any(|b| is_disjoint(a, b)))
+    {
+        return Type::Never;
+    }
+
+    // Keep the most specific of equivalent and related members
+    let mut keep = vec![true; flattened.len()];
+    for (i1, t1) in flattened.iter().enumerate() {
+        keep[i1] = flattened
+            .iter()
+            .enumerate()
+            .all(|(i2, t2)| i1 == i2 || !is_subtype(t2, t1) || (is_subtype(t1, t2) && i1 < i2));
+    }
+    let mut types: Vec<Type> = flattened
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(t, keep)| keep.then_some(t))
+        .collect();
+    types.sort_by(union_order);
+
+    match types.len() {
+        0 => Type::Any,
+        1 => types.pop().unwrap(),
+        _ => Type::Intersection(types),
+    }
+}
@@ -15,6 +15,10 @@
 
 mod helpers;
 mod base;
+mod generics;
 
 pub use self::helpers::*;
 pub use self::base::*;
+pub use self::generics::{
+    generic_arity, generic_name, iterable_element, mapping_items, sequence_element,
+};
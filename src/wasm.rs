@@ -0,0 +1,67 @@
+// This file is part of pycavalry.
+//
+// pycavalry is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The JavaScript API when built for WebAssembly, for running the checker in a browser.
+//!
+//! Build with `wasm-pack build --target web`, there is no file system in the browser so imports of
+//! other modules in the project resolve to nothing.
+
+use std::path::PathBuf;
+
+use js_sys::{Array, Object, Reflect};
+use ruff_text_size::TextSize;
+use wasm_bindgen::prelude::*;
+
+use crate::error_check_file_lossy;
+
+fn set(object: &Object, key: &str, value: impl Into<JsValue>) {
+    Reflect::set(object, &JsValue::from_str(key), &value.into()).unwrap();
+}
+
+/// Check the source of a single module, returning an array of its diagnostics as
+/// `{code, severity, message, start: {line, column}, end: {line, column}}` objects, with 1 based
+/// lines and columns counted in characters.
+#[wasm_bindgen]
+pub fn check_source(source: &str) -> JsValue {
+    let info = error_check_file_lossy(PathBuf::from("main.py"), source.to_owned());
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let position = |offset: TextSize| {
+        let offset = offset.to_usize();
+        let line = line_starts.partition_point(|&s| s <= offset);
+        let column = source[line_starts[line - 1]..offset].chars().count() + 1;
+        let position = Object::new();
+        set(&position, "line", line as u32);
+        set(&position, "column", column as u32);
+        position
+    };
+
+    let diagnostics = Array::new();
+    for error in info.reporter.errors().lock().unwrap().iter() {
+        let diagnostic = Object::new();
+        set(
+            &diagnostic,
+            "code",
+            error.code().map_or(JsValue::NULL, JsValue::from_str),
+        );
+        set(&diagnostic, "severity", error.typ().to_string());
+        set(&diagnostic, "message", error.message());
+        set(&diagnostic, "start", position(error.range().start()));
+        set(&diagnostic, "end", position(error.range().end()));
+        diagnostics.push(&diagnostic);
+    }
+    diagnostics.into()
+}
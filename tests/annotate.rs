@@ -0,0 +1,58 @@
+use indoc::indoc;
+use pycavalry::{annotation_edits, apply_edits, error_check_file};
+
+fn annotate(content: &str) -> String {
+    let info = error_check_file("annotate.py".into(), content.into()).unwrap();
+    apply_edits(content, &annotation_edits(&info))
+}
+
+#[test]
+fn test_annotate_inferred_types() {
+    let content = indoc! {r#"
+        def scaled(x, scale=2):
+            return scale * 2
+
+        def name(first, last):
+            if first:
+                return first + last
+            else:
+                return last
+
+        def log(message):
+            pass
+
+        def maybe(flag=False):
+            if flag:
+                return (1, "a")
+
+        scaled(3)
+        scaled(4)
+        name("a", "b")
+        name(1, "c")
+    "#};
+    assert_eq!(
+        annotate(content),
+        indoc! {r#"
+            def scaled(x: int, scale: int = 2) -> int:
+                return scale * 2
+
+            def name(first, last: str):
+                if first:
+                    return first + last
+                else:
+                    return last
+
+            def log(message) -> None:
+                pass
+
+            def maybe(flag: bool = False) -> tuple[int, str] | None:
+                if flag:
+                    return (1, "a")
+
+            scaled(3)
+            scaled(4)
+            name("a", "b")
+            name(1, "c")
+        "#}
+    );
+}
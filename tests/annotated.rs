@@ -0,0 +1,37 @@
+use indoc::indoc;
+use pycavalry::{annotated_metadata, error_check_file, Type};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_annotated_is_inner_type() {
+    run_with_expectations(
+        "test_annotated_is_inner_type.py",
+        indoc! {r#"
+            def positive(x: Annotated[int, "positive"]) -> int:
+                return x
+            reveal_type(positive)  # Debug: Type is (x: int) -> int
+            positive(1)
+            positive("a")  # Debug: Expected int but found Literal["a"].
+            y: Annotated[int]  # Debug: Annotated expects a type and at least one metadata argument
+        "#},
+    );
+}
+
+#[test]
+fn test_annotated_metadata() {
+    let content = indoc! {r#"
+        x: Annotated[str, "label", 3] = "a"
+    "#};
+    let info = error_check_file("test_annotated_metadata.py".into(), content.into()).unwrap();
+    let sites = annotated_metadata(&info);
+    assert_eq!(sites.len(), 1);
+    assert_eq!(sites[0].typ, Type::String);
+    let metadata: Vec<&str> = sites[0]
+        .metadata
+        .iter()
+        .map(|m| &content[ruff_text_size::Ranged::range(m)])
+        .collect();
+    assert_eq!(metadata, vec![r#""label""#, "3"]);
+}
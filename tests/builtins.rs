@@ -0,0 +1,52 @@
+use indoc::indoc;
+
+mod common;
+use common::*;
+
+#[test]
+fn test_builtins_resolve_without_import() {
+    run_with_expectations(
+        "test_builtins_resolve_without_import.py",
+        indoc! {r#"
+            import builtins
+            reveal_type(__debug__)  # Debug: Type is bool
+            reveal_type(builtins.__debug__)  # Debug: Type is bool
+        "#},
+    );
+}
+
+#[test]
+fn test_builtin_functions() {
+    run_with_expectations(
+        "test_builtin_functions.py",
+        indoc! {r#"
+            print()
+            print("a", 1, None)
+            reveal_type(print)  # Debug: Type is (*values: Any, sep: Optional[str], end: Optional[str], file: Any, flush: bool) -> None
+            reveal_type(len((1, 2)))  # Debug: Type is int
+            reveal_type(isinstance(1, KeyError))  # Debug: Type is bool
+            for i in range(3):
+                reveal_type(i)  # Debug: Type is int
+            range("a")  # Debug: Expected int but found Literal["a"].
+            chr(ord("a"))
+            len()  # Debug: expected 1 args, got 0 args
+        "#},
+    );
+}
+
+#[test]
+fn test_builtin_classes() {
+    run_with_expectations(
+        "test_builtin_classes.py",
+        indoc! {r#"
+            def f(x: object, items: list[int]) -> None:
+                reveal_type(isinstance(x, int))  # Debug: Type is bool
+                reveal_type(isinstance(x, (str, bytes)))  # Debug: Type is bool
+                reveal_type(str(1))  # Debug: Type is str
+                reveal_type(list())  # Debug: Type is list[Unknown]
+                reveal_type(int)  # Debug: Type is type[int]
+                reveal_type(items)  # Debug: Type is list[int]
+                count: int = float("1.5")  # Debug: Expected int but found float.
+        "#},
+    );
+}
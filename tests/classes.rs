@@ -0,0 +1,108 @@
+use indoc::indoc;
+
+mod common;
+use common::*;
+
+#[test]
+fn test_instance_attributes() {
+    run_with_expectations(
+        "test_instance_attributes.py",
+        indoc! {r#"
+            class Base:
+                kind: str = "base"
+                def describe(self, loud: bool = False) -> str:
+                    return self.kind
+
+            class Point(Base):
+                def __init__(self, x: int, y: int) -> None:
+                    if x > 0:
+                        self.x = x
+                    self.y: int = y
+
+                @property
+                def norm(self) -> float:
+                    return 0.0
+
+                def moved(self, dx: int) -> Point:
+                    return Point(self.x + dx, self.y)
+
+            p = Point(1, 2)
+            reveal_type(p)  # Debug: Type is Point
+            reveal_type(p.x)  # Debug: Type is Unknown
+            reveal_type(p.y)  # Debug: Type is int
+            reveal_type(p.norm)  # Debug: Type is float
+            reveal_type(p.moved(1).kind)  # Debug: Type is str
+            reveal_type(p.describe)  # Debug: Type is (loud: bool) -> str
+            p.z  # Debug: Point has no attribute "z".
+            Point.z  # Debug: type[Point] has no attribute "z".
+            p.__dict__
+
+            class Dynamic:
+                def __getattr__(self, name: str) -> int:
+                    return 1
+            reveal_type(Dynamic().anything)  # Debug: Type is int
+        "#},
+    );
+}
+
+#[test]
+fn test_constructor_arguments() {
+    run_with_expectations(
+        "test_constructor_arguments.py",
+        indoc! {r#"
+            class Point:
+                def __init__(self, x: int, y: int = 0) -> None:
+                    self.x = x
+                    self.y = y
+
+            reveal_type(Point(1))  # Debug: Type is Point
+            reveal_type(Point(1, y=2))  # Debug: Type is Point
+            Point("a")  # Debug: Expected int but found Literal["a"].
+            Point()  # Debug: expected 1 to 2 args, got 0 args
+            Point(1, 2, 3)  # Debug: expected 1 to 2 args, got 3 args
+            Point(1, z=3)  # Debug: Keyword argument "z" is not a parameter of the function.
+
+            class Empty:
+                pass
+
+            reveal_type(Empty())  # Debug: Type is Empty
+
+            class Options:
+                def __init__(self, **values: int) -> None:
+                    pass
+                def toggle(self, *, flag: bool) -> None:
+                    pass
+
+            options = Options(width=1, height=2)
+            Options(width="a")  # Debug: Expected int but found Literal["a"].
+            options.toggle(flag=True)
+            options.toggle(flag=1)  # Debug: Expected bool but found Literal[1].
+            options.toggle(other=True)  # Debug: Keyword argument "other" is not a parameter of the function. # Debug: Keyword argument "flag" is required.
+        "#},
+    );
+}
+
+#[test]
+fn test_attribute_assignment() {
+    run_with_expectations(
+        "test_attribute_assignment.py",
+        indoc! {r#"
+            class Point:
+                def __init__(self, x: int) -> None:
+                    self.x: int = x
+
+            p = Point(1)
+            p.x = 2
+            p.x = "a"  # Debug: Expected int but found Literal["a"].
+            p.z = 3  # Debug: Point has no attribute "z".
+
+            class Record:
+                def __setattr__(self, name: str, value: int) -> None:
+                    pass
+
+            r = Record()
+            r.count = 1
+            r.label = "a"  # Debug: Expected int but found Literal["a"].
+        "#},
+    );
+}
@@ -0,0 +1,41 @@
+use indoc::indoc;
+use pycavalry::{completions_at, error_check_file, Completion, CompletionKind};
+use ruff_text_size::TextSize;
+
+fn labels(completions: Vec<Completion>) -> Vec<(String, CompletionKind)> {
+    completions
+        .into_iter()
+        .map(|c| (c.label.to_string(), c.kind))
+        .collect()
+}
+
+#[test]
+fn test_completions() {
+    let content = indoc! {r#"
+        import sys
+        value = 1
+        def validate(vacancy: int):
+            return va
+        sys.version_info
+    "#};
+    let info = error_check_file("test_completions.py".into(), content.into()).unwrap();
+
+    let in_func = content.find("va\n").unwrap() + 2;
+    assert_eq!(
+        labels(completions_at(&info, TextSize::try_from(in_func).unwrap())),
+        vec![
+            ("vacancy".to_owned(), CompletionKind::Variable),
+            ("validate".to_owned(), CompletionKind::Function),
+            ("value".to_owned(), CompletionKind::Variable),
+        ]
+    );
+
+    let attribute = content.find("version_info").unwrap() + 3;
+    assert_eq!(
+        labels(completions_at(
+            &info,
+            TextSize::try_from(attribute).unwrap()
+        )),
+        vec![("version_info".to_owned(), CompletionKind::Variable)]
+    );
+}
@@ -0,0 +1,43 @@
+use std::{fs, sync::Arc};
+
+use pycavalry::{check_conformance, testing::TempDir, ConformanceOutcome};
+
+#[test]
+fn test_conformance_expected_error_comments() {
+    let dir = TempDir::new("conformance");
+    fs::write(
+        dir.join("basics_passing.py"),
+        "a: int = \"\"  # E\nb: str = 1  # E?\nc: int = 1  # E?\nd: int = \"\"  # E[pair]\ne: int = 1  # E[pair]\nreveal_type(e)\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("basics_failing.py"),
+        "a: int = 1  # E: should fail\nb: str = 1\nc: int = 1  # E[pair]\nd: int = 1  # E[pair]\n",
+    )
+    .unwrap();
+    fs::write(dir.join("other_syntax.py"), "def (:\n").unwrap();
+
+    let report = check_conformance(&dir, Arc::default()).unwrap();
+    assert_eq!(
+        report.files["basics_passing.py"],
+        ConformanceOutcome::Passed
+    );
+    assert_eq!(
+        report.files["basics_failing.py"],
+        ConformanceOutcome::Failed {
+            missing: vec![1, 3, 4],
+            unexpected: vec![2]
+        }
+    );
+    assert_eq!(
+        report.to_string(),
+        concat!(
+            "area    passed  total\n",
+            "basics       1      2\n",
+            "other        0      1\n",
+            "total        1      3\n",
+            "basics_failing.py: missing errors on lines 1, 3, 4, unexpected errors on lines 2\n",
+            "other_syntax.py: unexpected errors on lines 1\n",
+        )
+    );
+}
@@ -0,0 +1,16 @@
+"""
+Tests the validity of various annotation forms.
+"""
+
+# Specification: https://typing.readthedocs.io/en/latest/spec/annotations.html
+
+from typing import Optional, Union
+
+good1: int = 1
+good2: Optional[int] = None
+good3: Union[int, str] = "a"
+good4: int | None = None
+
+bad1: Optional[int] = "a"  # E
+bad2: Union[int, str] = None  # E
+bad3: int | str = None  # E
@@ -0,0 +1,20 @@
+"""
+Tests the handling of typing.Literal.
+"""
+
+# Specification: https://typing.readthedocs.io/en/latest/spec/literal.html
+
+from typing import Literal, assert_type
+
+good1: Literal[1] = 1
+good2: Literal["a"] = "a"
+good3: Literal[True] = True
+good4: Literal[-3] = -3
+good5: Literal[1, 2, 3] = 2
+
+bad1: Literal[1] = 2  # E
+bad2: Literal["a"] = "b"  # E
+bad3: Literal[True] = False  # E
+bad4: Literal[1, 2] = 3  # E
+
+assert_type(good1, Literal[1])
@@ -0,0 +1,14 @@
+"""
+Tests the handling of typing.Any.
+"""
+
+# Specification: https://typing.readthedocs.io/en/latest/spec/special-types.html#any
+
+from typing import Any
+
+a: Any = 1
+a = "a"
+a = (1, 2)
+
+b: int = a
+c: str = a
@@ -0,0 +1,19 @@
+"""
+Tests the annotation forms of tuple types.
+"""
+
+# Specification: https://typing.readthedocs.io/en/latest/spec/tuples.html
+
+from typing import Tuple
+
+t1: tuple[int] = (1,)
+t2: tuple[int, str] = (1, "a")
+t3: tuple[()] = ()
+t4: tuple[int, ...] = (1, 2, 3)
+t5: Tuple[int, int] = (1, 2)
+
+t10: tuple[int] = (1, 2)  # E
+t11: tuple[int, str] = ("a", 1)  # E
+t12: tuple[()] = (1,)  # E
+t13: tuple[int, ...] = (1, "a")  # E
+t14: tuple[int, ..., str]  # E
@@ -0,0 +1,42 @@
+use std::{fs, sync::Arc};
+
+use pycavalry::{check_corpus, testing::TempDir, CorpusReport, FileOutcome};
+
+#[test]
+fn test_corpus_report_round_trips_and_diffs() {
+    let dir = TempDir::new("corpus");
+    fs::create_dir_all(dir.join("project/.venv")).unwrap();
+    fs::write(dir.join("project/good.py"), "a: int = 1\n").unwrap();
+    fs::write(dir.join("project/bad.py"), "a: int = \"\"\nb: str = 1\n").unwrap();
+    fs::write(dir.join("project/broken.py"), "def (:\n").unwrap();
+    fs::write(dir.join("project/.venv/ignored.py"), "a: int = \"\"\n").unwrap();
+    fs::write(dir.join("project/notes.txt"), "not python").unwrap();
+
+    let report = check_corpus(&dir, Arc::default()).unwrap();
+    let snapshot = report.to_string();
+    assert_eq!(
+        snapshot,
+        "project/bad.py: PCV003=2\nproject/broken.py: -=1\nproject/good.py: ok\n"
+    );
+    assert_eq!(CorpusReport::parse(&snapshot), Some(report.clone()));
+    assert!(report.diff(&report).is_empty());
+
+    let mut changed = report.clone();
+    changed.files.remove("project/good.py");
+    changed.files.insert(
+        "project/broken.py".into(),
+        FileOutcome::Crashed("oops".into()),
+    );
+    changed.files.insert(
+        "project/new.py".into(),
+        FileOutcome::Checked(Default::default()),
+    );
+    assert_eq!(
+        changed.diff(&report),
+        vec![
+            "~ project/broken.py: -=1 -> crashed",
+            "- project/good.py: ok",
+            "+ project/new.py: ok",
+        ]
+    );
+}
@@ -0,0 +1,97 @@
+use pycavalry::{
+    union, with_union_style, Class, Diag, ExpectedButGotDiag, Symbol, Type, TypeLiteral, UnionStyle,
+};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_union_styles() {
+    let optional = Type::Union(vec![Type::Int, Type::None]);
+    let union = Type::Union(vec![Type::Int, Type::String, Type::None]);
+
+    assert_eq!(optional.to_string(), "Optional[int]");
+    assert_eq!(union.to_string(), "Optional[Union[int, str]]");
+    with_union_style(UnionStyle::Pipe, || {
+        assert_eq!(optional.to_string(), "int | None");
+        assert_eq!(union.to_string(), "int | str | None");
+    });
+    with_union_style(UnionStyle::Union, || {
+        assert_eq!(optional.to_string(), "Union[int, None]");
+        assert_eq!(union.to_string(), "Union[int, str, None]");
+    });
+    let literal_none = Type::Union(vec![Type::Int, Type::Literal(TypeLiteral::NoneLiteral)]);
+    assert_eq!(literal_none.to_string(), "Optional[int]");
+    with_union_style(UnionStyle::Pipe, || {
+        assert_eq!(literal_none.to_string(), "int | None");
+    });
+}
+
+#[test]
+fn test_union_style_is_restored_after_a_panic() {
+    let optional = Type::Union(vec![Type::Int, Type::None]);
+    let result = std::panic::catch_unwind(|| {
+        with_union_style(UnionStyle::Pipe, || panic!("while displaying"));
+    });
+    assert!(result.is_err());
+    assert_eq!(optional.to_string(), "Optional[int]");
+}
+
+#[test]
+fn test_union_order_is_canonical() {
+    let a = union(vec![
+        Type::None,
+        ann("str"),
+        ann("Literal[2]"),
+        Type::Int,
+        ann("Literal[1]"),
+    ]);
+    let b = union(vec![
+        ann("Literal[1]"),
+        Type::Int,
+        ann("Literal[2]"),
+        ann("str"),
+        Type::None,
+    ]);
+    assert_eq!(a, b);
+    assert_eq!(a.to_string(), "Optional[Union[int, str]]");
+    let literals = union(vec![
+        ann("Literal['b']"),
+        ann("Literal[10]"),
+        ann("Literal[9]"),
+    ]);
+    assert_eq!(literals.to_string(), "Literal[9, 10, \"b\"]");
+}
+
+#[test]
+fn test_colliding_classes_are_qualified() {
+    let class = |module: &str, name: &str| {
+        Type::Class(Class::new(
+            Symbol::new(module),
+            Symbol::new(name),
+            vec![],
+            vec![],
+        ))
+    };
+    let a_point = class("a", "Point");
+    let b_point = class("b", "Point");
+    let other = class("a", "Other");
+
+    let diag = ExpectedButGotDiag::new(a_point.clone(), b_point, r(0..0));
+    assert_eq!(
+        diag.message(),
+        "Expected type[a.Point] but found type[b.Point]."
+    );
+    let diag = ExpectedButGotDiag::new(a_point.clone(), other, r(0..0));
+    assert_eq!(
+        diag.message(),
+        "Expected type[Point] but found type[Other]."
+    );
+
+    let list_of = |typ: Type| Type::Generic(Symbol::new("list"), vec![typ]);
+    let diag = ExpectedButGotDiag::new(list_of(a_point), list_of(class("b", "Point")), r(0..0));
+    assert_eq!(
+        diag.message(),
+        "Expected list[type[a.Point]] but found list[type[b.Point]]."
+    );
+}
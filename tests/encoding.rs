@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use pycavalry::{error_check_bytes_with_config, EncodingDiag};
+
+mod common;
+use common::*;
+
+fn check_bytes(name: &str, bytes: &[u8]) -> pycavalry::Info {
+    error_check_bytes_with_config(name.into(), bytes.to_vec(), Arc::default()).unwrap()
+}
+
+#[test]
+fn test_bom_is_skipped() {
+    let info = check_bytes("test_bom_is_skipped.py", b"\xEF\xBB\xBFa: int = 1\n");
+    assert_eq!(info.file_content.as_str(), "a: int = 1\n");
+    assert_errors(&info, vec![]);
+}
+
+#[test]
+fn test_latin1_cookie() {
+    let info = check_bytes(
+        "test_latin1_cookie.py",
+        b"# -*- coding: latin-1 -*-\nname: str = 'J\xF3n'\n",
+    );
+    assert!(info.file_content.contains("'Jón'"));
+    assert_errors(&info, vec![]);
+}
+
+#[test]
+fn test_invalid_utf8_is_replaced() {
+    let info = check_bytes("test_invalid_utf8_is_replaced.py", b"name = 'J\xF3n'\n");
+    assert_errors(
+        &info,
+        vec![EncodingDiag::new(ars("UTF-8"), true, r(0..0)).into()],
+    );
+}
+
+#[test]
+fn test_unsupported_encoding() {
+    let info = check_bytes(
+        "test_unsupported_encoding.py",
+        b"#!/usr/bin/env python\n# vim: set fileencoding=shift_jis :\nx = 1\n",
+    );
+    assert_errors(
+        &info,
+        vec![EncodingDiag::new(ars("shift_jis"), false, r(0..0)).into()],
+    );
+}
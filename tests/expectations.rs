@@ -0,0 +1,16 @@
+use indoc::indoc;
+
+mod common;
+use common::*;
+
+#[test]
+fn test_debug_comment_expectations() {
+    run_with_expectations(
+        "test_debug_comment_expectations.py",
+        indoc! {r#"
+            a: int = 3
+            reveal_type(a)  # Debug(12..13): Type is int
+            b: str = 4  # Debug: Expected str but found Literal[4].
+        "#},
+    );
+}
@@ -0,0 +1,52 @@
+use std::{env, fs, path::Path};
+
+use pycavalry::{diagnostics_markdown, explain, DiagnosticType, EncodingDiag, DIAGNOSTICS};
+
+#[test]
+fn test_every_code_is_explained() {
+    for info in DIAGNOSTICS {
+        assert!(!info.title.is_empty(), "{} has no title", info.code);
+        assert!(
+            !info.description.is_empty(),
+            "{} has no description",
+            info.code
+        );
+    }
+}
+
+#[test]
+fn test_every_error_has_a_fix() {
+    for info in DIAGNOSTICS {
+        if info.typ == DiagnosticType::Error {
+            assert!(info.fix.is_some(), "{} has no fix", info.code);
+        }
+    }
+}
+
+#[test]
+fn test_explain_code() {
+    let explanation = explain("pcv003").unwrap().0;
+    assert_eq!(explanation.typ, DiagnosticType::Error);
+    assert_eq!(explanation.title, "Expected a type but found another");
+    assert_eq!(explanation.example, ["a: int = \"not an int\""]);
+    assert_eq!(
+        explanation.fix,
+        Some("Change the value or widen the expected type.")
+    );
+    assert_eq!(explain("PCV012").unwrap().0, EncodingDiag::INFO);
+    assert_eq!(explain("PCV999"), None);
+}
+
+#[test]
+fn test_docs_are_generated_from_the_explanations() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("docs/diagnostics.md");
+    let generated = diagnostics_markdown();
+    if env::var_os("PYCAVALRY_UPDATE_DOCS").is_some() {
+        fs::write(&path, &generated).unwrap();
+    }
+    assert!(
+        fs::read_to_string(&path).unwrap() == generated,
+        "docs/diagnostics.md is out of date, regenerate it with \
+        PYCAVALRY_UPDATE_DOCS=1 cargo test --test explain"
+    );
+}
@@ -0,0 +1,237 @@
+use indoc::indoc;
+use pycavalry::{module_cache, with_dependents, NotInModuleDiag};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_import_from_unknown_name() {
+    run_with_errors(
+        "test_import_from_unknown_name.py",
+        "from sys import foo",
+        vec![
+            NotInModuleDiag::new(sym("sys"), sym("foo"), vec![sym("version_info")], r(16..19))
+                .into(),
+        ],
+    );
+}
+
+#[test]
+fn test_module_attributes() {
+    run_with_expectations(
+        "test_module_attributes.py",
+        indoc! {r#"
+            import sys
+            sys.foo  # Debug: Name "foo" not found in module "sys".
+            sys.version_info = (3, 13)  # Debug: Assigning to "version_info" on module "sys" is not tracked.
+            sys.foo = 1  # Debug: Assigning to "foo" on module "sys" is not tracked.
+            globals()["bar"] = 1  # Debug: Names added through globals() are not tracked.
+        "#},
+    );
+}
+
+#[test]
+fn test_getenv_refinement() {
+    run_with_expectations(
+        "test_getenv_refinement.py",
+        indoc! {r#"
+            import os
+            from os import getenv
+            reveal_type(os.getenv("HOME"))  # Debug: Type is Optional[str]
+            reveal_type(os.getenv("HOME", "/root"))  # Debug: Type is str
+            reveal_type(getenv("HOME", None))  # Debug: Type is Optional[str]
+            getenv()  # Debug: expected 1 to 2 args, got 0 args
+        "#},
+    );
+}
+
+#[test]
+fn test_star_import_collision() {
+    run_with_expectations(
+        "test_star_import_collision.py",
+        indoc! {r#"
+            from builtins import *
+            from typing import *  # Debug: "reveal_type" from "typing" replaces the one imported from "builtins".
+            x: int = getenv  # Debug: Name "getenv" not found in scope.
+            y: bool = __debug__
+            assert_never
+        "#},
+    );
+}
+
+#[test]
+fn test_conditional_definitions() {
+    run_with_expectations(
+        "test_conditional_definitions.py",
+        indoc! {r#"
+            from typing import TYPE_CHECKING
+            if TYPE_CHECKING:
+                a = 1
+                b = "x"
+            else:
+                a = None
+                b = 2  # Debug: Fallback definition of "b" is Literal[2], which doesn't match Literal["x"].
+                c = 3
+            reveal_type(a)  # Debug: Type is Optional[Literal[1]]
+            reveal_type(c)  # Debug: Type is Literal[3]
+            try:
+                import os
+            except (ImportError, ModuleNotFoundError):
+                os = None
+            reveal_type(os)  # Debug: Type is Optional[module[os]]
+        "#},
+    );
+}
+
+#[test]
+fn test_pycavalry_helpers() {
+    run_with_expectations(
+        "test_pycavalry_helpers.py",
+        indoc! {r#"
+            import pycavalry
+            from pycavalry import assert_type, reveal_locals, reveal_type
+            x = 1
+            reveal_type(x)  # Debug: Type is Literal[1]
+            assert_type(x, Literal[1])
+            assert_type(x, int)  # Debug: Type is Literal[1], not int as asserted.
+            pycavalry.reveal_type("a")  # Debug: Type is Literal["a"]
+            def f(a: int) -> None:
+                b = "b"
+                reveal_locals()  # Debug: Local types are a: int, b: Literal["b"]
+        "#},
+    );
+}
+
+#[test]
+fn test_typing_aliases() {
+    run_with_expectations(
+        "test_typing_aliases.py",
+        indoc! {r#"
+            import typing
+            from collections.abc import Iterable, Set
+            from typing import Dict, List as L, Mapping, Optional, Sequence
+            def f(a: L[int], b: Dict[str, int], c: Optional[str], d: typing.Tuple[int, str]) -> None:
+                reveal_type(a)  # Debug: Type is list[int]
+                reveal_type(c)  # Debug: Type is Optional[str]
+                x: Sequence[int] = a
+                y: Mapping[str, float] = b
+                z: Iterable[str] = "abc"
+                w: Sequence[int | str] = d
+                v: list[float] = a  # Debug: Expected list[float] but found list[int].
+                s: Set[int] = a  # Debug: Expected AbstractSet[int] but found list[int].
+            g: Dict[str] = 1  # Debug: dict expects 2 type arguments, got 1
+            h: typing.Lisst[int] = 1  # Debug: Name "Lisst" not found in module "typing".
+        "#},
+    );
+}
+
+#[test]
+fn test_local_module_imports() {
+    let dir = TempDir::new("local_module_imports");
+    std::fs::create_dir_all(dir.join("pkg")).unwrap();
+    std::fs::write(
+        dir.join("helpers.py"),
+        "import cycle\nLIMIT: int = 3\ndef greet(name: str) -> str:\n    return name\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("cycle.py"), "import helpers\n").unwrap();
+    std::fs::write(dir.join("pkg/__init__.py"), "VERSION = (1, 2)\n").unwrap();
+
+    run_with_expectations(
+        dir.join("main.py"),
+        indoc! {r#"
+            import helpers
+            from helpers import greet, LIMIT
+            from pkg import VERSION
+            reveal_type(greet)  # Debug: Type is (name: str) -> str
+            reveal_type(helpers.LIMIT)  # Debug: Type is int
+            reveal_type(VERSION)  # Debug: Type is tuple[Literal[1], Literal[2]]
+            greet(LIMIT)  # Debug: Expected str but found int.
+        "#},
+    );
+}
+
+#[test]
+fn test_module_checked_again_when_import_changes() {
+    let dir = TempDir::new("import_changes");
+    std::fs::write(
+        dir.join("base.py"),
+        "def get(x: int) -> int:\n    return x\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("middle.py"), "import base\nvalue = base.get(1)\n").unwrap();
+    let main = indoc! {r#"
+        from middle import value
+        reveal_type(value)  # Debug: Type is int
+    "#};
+    run_with_expectations(dir.join("main.py"), main);
+
+    // Only the body changed, so middle keeps its types
+    std::fs::write(
+        dir.join("base.py"),
+        "def get(x: int) -> int:\n    return x + 1\n",
+    )
+    .unwrap();
+    run_with_expectations(dir.join("main.py"), main);
+
+    std::fs::write(
+        dir.join("base.py"),
+        "def get(x: float) -> float:\n    return x\n",
+    )
+    .unwrap();
+    run_with_expectations(
+        dir.join("main.py"),
+        main.replace("Type is int", "Type is float"),
+    );
+}
+
+#[test]
+fn test_invalidated_module_is_checked_again() {
+    let dir = TempDir::new("invalidate");
+    std::fs::write(dir.join("base.py"), "value = 1\n").unwrap();
+    let main = indoc! {r#"
+        from base import value
+        reveal_type(value)  # Debug: Type is Literal[1]
+    "#};
+    run_with_expectations(dir.join("main.py"), main);
+    assert!(module_cache().contains(&dir.join("base.py")));
+
+    module_cache().invalidate(&dir.join("base.py"));
+    assert!(!module_cache().contains(&dir.join("base.py")));
+    run_with_expectations(dir.join("main.py"), main);
+    assert!(module_cache().contains(&dir.join("base.py")));
+}
+
+#[test]
+fn test_changed_files_with_their_dependents() {
+    let dir = TempDir::new("dependents");
+    for (file, source) in [
+        ("base.py", "value = 1\n"),
+        ("middle.py", "from base import value\n"),
+        ("top.py", "from middle import value\n"),
+        ("other.py", "value = 2\n"),
+    ] {
+        std::fs::write(dir.join(file), source).unwrap();
+    }
+    let files: Vec<_> = ["base.py", "middle.py", "other.py", "top.py"]
+        .into_iter()
+        .map(|file| dir.join(file))
+        .collect();
+
+    assert_eq!(
+        with_dependents(&files, &[dir.join("base.py")], Default::default()),
+        vec![
+            dir.join("base.py"),
+            dir.join("middle.py"),
+            dir.join("top.py")
+        ]
+    );
+    assert_eq!(
+        with_dependents(&files, &[dir.join("middle.py")], Default::default()),
+        vec![dir.join("middle.py"), dir.join("top.py")]
+    );
+    assert_eq!(
+        with_dependents(&files, &[dir.join("deleted.py")], Default::default()),
+        Vec::<std::path::PathBuf>::new()
+    );
+}
@@ -0,0 +1,52 @@
+use pycavalry::{intersection, is_subtype, union, Class, Symbol, Type};
+
+mod common;
+use common::*;
+
+fn instance(name: &str) -> Type {
+    Type::Instance(Class::new(
+        Symbol::new("test"),
+        Symbol::new(name),
+        vec![],
+        vec![],
+    ))
+}
+
+#[test]
+fn test_intersection_simplification() {
+    let (a, b) = (instance("A"), instance("B"));
+
+    assert_eq!(intersection(vec![Type::Int, Type::Float]), Type::Int);
+    assert_eq!(intersection(vec![Type::Int, Type::String]), Type::Never);
+    assert_eq!(
+        intersection(vec![ann("Literal[1]"), Type::Int]),
+        ann("Literal[1]")
+    );
+    assert_eq!(intersection(vec![a.clone(), Type::Any]), a);
+    assert_eq!(
+        intersection(vec![b.clone(), intersection(vec![a.clone(), b.clone()])]),
+        intersection(vec![a.clone(), b.clone()])
+    );
+    assert_eq!(
+        intersection(vec![a.clone(), b.clone()]).to_string(),
+        "Intersection[A, B]"
+    );
+    // Distributes over unions and drops the impossible arms
+    assert_eq!(
+        intersection(vec![
+            union(vec![Type::Int, Type::String, Type::None]),
+            Type::Float
+        ]),
+        Type::Int
+    );
+}
+
+#[test]
+fn test_intersection_subtyping() {
+    let (a, b) = (instance("A"), instance("B"));
+    let both = intersection(vec![a.clone(), b.clone()]);
+
+    assert!(is_subtype(&both, &a));
+    assert!(is_subtype(&both, &b));
+    assert!(!is_subtype(&a, &both));
+}
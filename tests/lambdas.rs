@@ -1,3 +1,4 @@
+use indoc::indoc;
 use pycavalry::RevealTypeDiag;
 
 mod common;
@@ -11,3 +12,17 @@ fn test_lambda_return_no_args() {
         vec![RevealTypeDiag::new(ann("Literal[\"asdf\"]"), r(12..45)).into()],
     );
 }
+
+#[test]
+fn test_lambda_checked_against_callable() {
+    run_with_expectations(
+        "test_lambda_checked_against_callable.py",
+        indoc! {r#"
+            f: Callable[[int], int] = lambda x: x
+            reveal_type(f)  # Debug: Type is (int) -> int
+            g: Callable[[str], int] = lambda x: x  # Debug: Expected int but found str.
+            h: Callable[[int, int], int] = lambda x: x  # Debug: Expected (int, int) -> int but found (x: Unknown) -> Unknown.
+            reveal_type(lambda y: y)  # Debug: Type is (y: Unknown) -> Unknown
+        "#},
+    );
+}
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use pycavalry::{
+    error_check_file_with_config, Config, Language, UnknownFileTypeDiag, UnsupportedLanguageDiag,
+};
+
+mod common;
+use common::*;
+
+fn check(name: &str, content: &str, language: Option<Language>) -> pycavalry::Info {
+    let config = Config {
+        language,
+        ..Default::default()
+    };
+    error_check_file_with_config(name.into(), content.into(), Arc::new(config)).unwrap()
+}
+
+#[test]
+fn test_language_detection() {
+    let info = check("notes.txt", "a: int = ''", None);
+    assert_errors(&info, vec![UnknownFileTypeDiag::new(r(0..0)).into()]);
+
+    let info = check("page.html.jinja", "{{ a }}", None);
+    assert_errors(
+        &info,
+        vec![UnsupportedLanguageDiag::new(Language::Jinja, r(0..0)).into()],
+    );
+
+    let info = check("script", "#!/usr/bin/env python3\na: int = 1", None);
+    assert_errors(&info, vec![]);
+}
+
+#[test]
+fn test_language_flag_overrides_extension() {
+    let info = check("script.txt", "a: int = 1", Some(Language::Python));
+    assert_errors(&info, vec![]);
+
+    let info = check("template.py", "{{ a }}", Some(Language::Jinja));
+    assert_errors(
+        &info,
+        vec![UnsupportedLanguageDiag::new(Language::Jinja, r(0..0)).into()],
+    );
+}
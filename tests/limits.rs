@@ -0,0 +1,21 @@
+use std::sync::Arc;
+
+use pycavalry::{error_check_file_with_config, Config, FileTooLargeDiag};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_file_over_size_limit_is_skipped() {
+    let config = Config {
+        max_file_size: Some(4),
+        ..Default::default()
+    };
+    let info = error_check_file_with_config(
+        "test_file_over_size_limit_is_skipped.py".into(),
+        "a: int = 'not checked'".into(),
+        Arc::new(config),
+    )
+    .unwrap();
+    assert_errors(&info, vec![FileTooLargeDiag::new(22, 4, r(0..0)).into()]);
+}
@@ -0,0 +1,36 @@
+use indoc::indoc;
+use pycavalry::{error_check_file, error_check_file_lossy};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_unsupported_syntax_is_reported() {
+    run_with_expectations(
+        "test_unsupported_syntax_is_reported.py",
+        indoc! {r#"
+            a = [x for x in (1, 2)]  # Debug: List comprehension is not supported yet.
+            a.b = 3  # Debug: Assignment to an attribute is not supported yet.
+            with a:  # Debug: With statement is not supported yet.
+                pass
+            from . import b  # Debug: Relative import is not supported yet.
+            reveal_type(99999999999999999999)  # Debug: Type is int
+        "#},
+    );
+}
+
+#[test]
+fn test_lossy_reports_parse_errors() {
+    let info = error_check_file_lossy("test_lossy_reports_parse_errors.py".into(), "a = (".into());
+    assert_eq!(info.reporter.len(), 1);
+}
+
+#[test]
+fn test_checking_continues_after_parse_errors() {
+    let content = "a: int = ''\nb = 1 +\nc: str = 1\n";
+    let info = error_check_file("test_checking_continues.py".into(), content.into()).unwrap();
+    let errors = info.reporter.errors();
+    let codes: Vec<_> = errors.lock().unwrap().iter().map(|e| e.code()).collect();
+    assert_eq!(codes.len(), 3);
+    assert_eq!(codes.iter().filter(|c| **c == Some("PCV003")).count(), 2);
+}
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use pycavalry::{
+    error_check_bytes_with_config, error_check_file_with_config, Config, Info, PathRule,
+};
+
+fn check(name: &str, rules: &[&str]) -> Info {
+    let config = Config {
+        path_rules: rules.iter().map(|rule| rule.parse().unwrap()).collect(),
+        ..Default::default()
+    };
+    let content = "class A: ...\nA().b\nx: int = 'a'\n";
+    error_check_file_with_config(name.into(), content.into(), Arc::new(config)).unwrap()
+}
+
+#[test]
+fn test_path_rules_ignore_codes_in_matching_files() {
+    let info = check("tests/test_a.py", &["tests/**:PCV003"]);
+    let errors = info.reporter.errors();
+    let codes: Vec<_> = errors.lock().unwrap().iter().map(|e| e.code()).collect();
+    assert_eq!(codes, vec![Some("PCV027")]);
+    assert_eq!(check("src/a.py", &["tests/**:PCV003"]).reporter.len(), 2);
+    assert_eq!(
+        check("generated/models.py", &["generated"]).reporter.len(),
+        0
+    );
+}
+
+#[test]
+fn test_path_rules_ignore_decode_problems() {
+    let config = Config {
+        path_rules: vec!["generated/**".parse().unwrap()],
+        ..Default::default()
+    };
+    let bytes = b"name = 'J\xF3n'\n".to_vec();
+    let info = error_check_bytes_with_config("generated/models.py".into(), bytes, Arc::new(config))
+        .unwrap();
+    assert_eq!(info.reporter.len(), 0);
+}
+
+#[test]
+fn test_path_rule_parsing() {
+    assert_eq!(
+        "tests/**:PCV003, PCV027".parse(),
+        Ok(PathRule {
+            glob: "tests/**".to_owned(),
+            codes: vec!["PCV003".to_owned(), "PCV027".to_owned()],
+        })
+    );
+    assert!("tests/**:missing".parse::<PathRule>().is_err());
+}
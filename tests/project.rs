@@ -0,0 +1,123 @@
+use std::fs;
+
+use pycavalry::{file_list, python_files, testing::TempDir, Config};
+
+#[test]
+fn test_python_files_walks_directories() {
+    let dir = TempDir::new("project");
+    fs::create_dir_all(dir.join("pkg/sub")).unwrap();
+    fs::create_dir_all(dir.join(".venv")).unwrap();
+    for file in [
+        "main.py",
+        "pkg/b.py",
+        "pkg/a.pyi",
+        "pkg/sub/c.py",
+        "pkg/data.json",
+        ".venv/d.py",
+    ] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+
+    let config = Config::default();
+    let files = python_files(
+        &[dir.join("pkg"), dir.join("main.py"), dir.join("pkg/b.py")],
+        &config,
+    );
+    let found: Vec<_> = files
+        .unwrap()
+        .iter()
+        .map(|f| f.strip_prefix(&dir).unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(
+        found,
+        vec!["pkg/a.pyi", "pkg/b.py", "pkg/sub/c.py", "main.py"]
+    );
+    assert_eq!(
+        python_files(&[dir.to_path_buf()], &config).unwrap().len(),
+        4
+    );
+}
+
+#[test]
+fn test_python_files_skips_ignored() {
+    let dir = TempDir::new("ignored");
+    for sub in ["src/gen", "src/keep", "env/lib", "build", "tests/fixtures"] {
+        fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    for file in [
+        "src/main.py",
+        "src/main_pb2.py",
+        "src/gen/api.py",
+        "src/keep/api.py",
+        "env/lib/site.py",
+        "build/lib.py",
+        "tests/fixtures/broken.py",
+        "tests/test_main.py",
+    ] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+    fs::write(dir.join("env/pyvenv.cfg"), "").unwrap();
+    fs::write(dir.join(".gitignore"), "# generated\n*_pb2.py\n").unwrap();
+    fs::write(dir.join("src/.gitignore"), "gen/\n").unwrap();
+
+    let find = |config: &Config| -> Vec<String> {
+        python_files(&[dir.to_path_buf()], config)
+            .unwrap()
+            .iter()
+            .map(|f| f.strip_prefix(&dir).unwrap().to_string_lossy().into_owned())
+            .collect()
+    };
+    let mut config = Config {
+        respect_gitignore: true,
+        exclude: vec!["tests/fixtures".to_owned()],
+        ..Default::default()
+    };
+    assert_eq!(
+        find(&config),
+        vec!["src/keep/api.py", "src/main.py", "tests/test_main.py"]
+    );
+
+    config.respect_gitignore = false;
+    assert_eq!(
+        find(&config),
+        vec![
+            "src/gen/api.py",
+            "src/keep/api.py",
+            "src/main.py",
+            "src/main_pb2.py",
+            "tests/test_main.py"
+        ]
+    );
+}
+
+#[test]
+fn test_python_files_gitignore_cant_include_excluded() {
+    let dir = TempDir::new("excluded");
+    fs::create_dir_all(dir.join("tests/fixtures")).unwrap();
+    fs::write(dir.join("tests/fixtures/broken.py"), "").unwrap();
+    fs::write(dir.join("tests/test_main.py"), "").unwrap();
+    fs::write(dir.join("tests/.gitignore"), "!fixtures/\n!*.py\n").unwrap();
+
+    let config = Config {
+        respect_gitignore: true,
+        exclude: vec!["tests/fixtures".to_owned()],
+        ..Default::default()
+    };
+    let files = python_files(&[dir.to_path_buf()], &config).unwrap();
+    assert_eq!(files, vec![dir.join("tests/test_main.py")]);
+}
+
+#[test]
+fn test_file_list_skips_blank_lines_and_deleted_files() {
+    let dir = TempDir::new("file_list");
+    fs::write(dir.join("a.py"), "").unwrap();
+    fs::write(dir.join("b.py"), "").unwrap();
+
+    let list = format!(
+        "{}\n\n{}\r\n{}\n",
+        dir.join("a.py").display(),
+        dir.join("deleted.py").display(),
+        dir.join("b.py").display()
+    );
+    assert_eq!(file_list(&list), vec![dir.join("a.py"), dir.join("b.py")]);
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use indoc::indoc;
+use pycavalry::{error_check_file_with_config, Config, ReassignDiag};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_reassign_import_and_constant() {
+    let content = indoc! {r#"
+        from sys import version_info
+        MAX = 1
+        version_info = (3, 12)
+        MAX = 2
+        def f() -> None:
+            MAX = 3
+        count = 1
+        count = 2
+    "#};
+    let config = Config {
+        warn_reassign: true,
+        ..Default::default()
+    };
+    let info = error_check_file_with_config(
+        "test_reassign_import_and_constant.py".into(),
+        content.into(),
+        Arc::new(config),
+    )
+    .unwrap();
+    assert_errors(
+        &info,
+        vec![
+            ReassignDiag::new(
+                sym("version_info"),
+                Some(sym("sys")),
+                Some(r(16..28)),
+                r(37..49),
+            )
+            .into(),
+            ReassignDiag::new(sym("MAX"), None, Some(r(29..32)), r(60..63)).into(),
+        ],
+    );
+}
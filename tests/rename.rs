@@ -0,0 +1,37 @@
+use indoc::indoc;
+use pycavalry::{apply_edits, error_check_file, rename_edits};
+use ruff_text_size::TextSize;
+
+#[test]
+fn test_rename_respects_scopes() {
+    let content = indoc! {r#"
+        value = 1
+        def f(value: int) -> int:
+            return value
+        other = value
+    "#};
+    let info = error_check_file("test_rename_respects_scopes.py".into(), content.into()).unwrap();
+
+    let edits = rename_edits(&info, TextSize::from(0), "renamed");
+    assert_eq!(
+        apply_edits(content, &edits),
+        indoc! {r#"
+            renamed = 1
+            def f(value: int) -> int:
+                return value
+            other = renamed
+        "#}
+    );
+
+    let param = content.find("value: int").unwrap();
+    let edits = rename_edits(&info, TextSize::try_from(param).unwrap(), "arg");
+    assert_eq!(
+        apply_edits(content, &edits),
+        indoc! {r#"
+            value = 1
+            def f(arg: int) -> int:
+                return arg
+            other = value
+        "#}
+    );
+}
@@ -0,0 +1,33 @@
+use indoc::indoc;
+use pycavalry::{error_check_file, semantic_tokens, TokenKind};
+
+#[test]
+fn test_semantic_tokens() {
+    let content = indoc! {r#"
+        import sys
+        class C:
+            pass
+        def f(arg: C):
+            return arg
+        v = sys.version_info
+    "#};
+    let info = error_check_file("test_semantic_tokens.py".into(), content.into()).unwrap();
+    let tokens: Vec<(&str, TokenKind)> = semantic_tokens(&info)
+        .into_iter()
+        .map(|t| (&content[t.range], t.kind))
+        .collect();
+    assert_eq!(
+        tokens,
+        vec![
+            ("sys", TokenKind::Module),
+            ("C", TokenKind::Class),
+            ("f", TokenKind::Function),
+            ("arg", TokenKind::Parameter),
+            ("C", TokenKind::Class),
+            ("arg", TokenKind::Parameter),
+            ("v", TokenKind::Variable),
+            ("sys", TokenKind::Module),
+            ("version_info", TokenKind::Variable),
+        ]
+    );
+}
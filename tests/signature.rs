@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use indoc::indoc;
+use pycavalry::{error_check_file, signature_at, ParameterHelp, SignatureHelp, Symbol, Type};
+use ruff_text_size::TextSize;
+
+#[test]
+fn test_signature_help_in_call() {
+    let content = indoc! {r#"
+        def add(a: int, b: int = 2) -> int:
+            """Add two numbers."""
+            return a
+        add(1, 3)
+    "#};
+    let info = error_check_file("test_signature_help_in_call.py".into(), content.into()).unwrap();
+    let offset = content.find("3)").unwrap() as u32;
+    let help = signature_at(&info, TextSize::from(offset)).unwrap();
+    assert_eq!(
+        help,
+        SignatureHelp {
+            label: "add(a: int, b: int = 2) -> int".to_owned(),
+            parameters: vec![
+                ParameterHelp {
+                    name: Symbol::new("a"),
+                    typ: Type::Int,
+                    default: None,
+                },
+                ParameterHelp {
+                    name: Symbol::new("b"),
+                    typ: Type::Int,
+                    default: Some(Arc::new("2".to_owned())),
+                },
+            ],
+            active_parameter: 1,
+            docstring: Some(Arc::new("Add two numbers.".to_owned())),
+        }
+    );
+    assert_eq!(signature_at(&info, TextSize::from(0)), None);
+}
@@ -34,11 +34,460 @@ fn test_synth_ann_assign_1() {
         },
         vec![
             RevealTypeDiag::new(Type::Int, r(54..55)).into(),
-            CantReassignLockedDiag::new(Type::Int, ann("Literal[5]"), ars("a"), r(57..74)).into(),
+            CantReassignLockedDiag::new(
+                Type::Int,
+                ann("Literal[5]"),
+                sym("a"),
+                Some(r(34..37)),
+                r(57..74),
+            )
+            .into(),
             RevealTypeDiag::new(Type::Int, r(87..88)).into(),
             ExpectedButGotDiag::new(Type::Int, ann("Literal['f']"), r(99..102)).into(),
-            CantReassignLockedDiag::new(Type::Int, Type::Int, ars("a"), r(90..102)).into(),
+            CantReassignLockedDiag::new(
+                Type::Int,
+                Type::Int,
+                sym("a"),
+                Some(r(34..37)),
+                r(90..102),
+            )
+            .into(),
             RevealTypeDiag::new(Type::Int, r(115..116)).into(),
         ],
     );
 }
+
+#[test]
+fn test_bool_is_int() {
+    run_with_expectations(
+        "test_bool_is_int.py",
+        indoc! {r#"
+            a: int = True
+            b: float = False
+            c: bool = 1  # Debug: Expected bool but found Literal[1].
+        "#},
+    );
+}
+
+#[test]
+fn test_subclass_instances() {
+    run_with_expectations(
+        "test_subclass_instances.py",
+        indoc! {r#"
+            class Base:
+                pass
+            class Child(Base):
+                pass
+            class Number(int):
+                pass
+            def f(obj: Base) -> None:
+                pass
+            reveal_type(Child())  # Debug: Type is Child
+            f(Child())
+            f(Base)  # Debug: Expected Base but found type[Base].
+            n: int = Number()
+            b: Base = Number()  # Debug: Expected Base but found Number.
+        "#},
+    );
+}
+
+#[test]
+fn test_type_annotation() {
+    run_with_expectations(
+        "test_type_annotation.py",
+        indoc! {r#"
+            class Base:
+                pass
+            class Child(Base):
+                pass
+            def make(cls: type[Base]) -> Base:
+                return cls()
+            reveal_type(make)  # Debug: Type is (cls: type[Base]) -> Base
+            make(Child)
+            make(Child())  # Debug: Expected type[Base] but found Child.
+            x: type[int]  # Debug: Expected a class in type, found int
+        "#},
+    );
+}
+
+#[test]
+fn test_assert_never() {
+    run_with_expectations(
+        "test_assert_never.py",
+        indoc! {r#"
+            from typing import assert_never
+            def unreachable(x: Never) -> Never:
+                return assert_never(x)
+            def handle(x: int) -> None:
+                assert_never(x)  # Debug: Expected Never but found int.
+        "#},
+    );
+}
+
+#[test]
+fn test_duplicate_definitions() {
+    run_with_expectations(
+        "test_duplicate_definitions.py",
+        indoc! {r#"
+            class User:
+                name: str
+                name: int  # Debug: Duplicate field "name".
+                def greet(self) -> None:
+                    pass
+                def greet(self) -> None:  # Debug: Duplicate method "greet".
+                    pass
+            d = {
+                "a": 1,
+                2: 2,
+                "a": 3,  # Debug: Duplicate key "a".
+                2: 4,  # Debug: Duplicate key "2".
+            }
+        "#},
+    );
+}
+
+#[test]
+fn test_call_footguns() {
+    run_with_expectations(
+        "test_call_footguns.py",
+        indoc! {r#"
+            def add(item: int, items: Any = []) -> Any:  # Debug: Mutable list used as a default value.
+                return items
+            def f(a: int, b: int) -> int:
+                return a
+            f(b=1, *(2,))  # Debug: Unpacked arguments after a keyword argument are passed before it.
+        "#},
+    );
+}
+
+#[test]
+fn test_special_methods() {
+    run_with_expectations(
+        "test_special_methods.py",
+        indoc! {r#"
+            class Point:
+                def __init__(self, x: int) -> int:  # Debug: __init__ must return None.
+                    if x:
+                        return x  # Debug: __init__ must return None.
+                    return None
+                def __new__():  # Debug: __new__ must take the class as its first parameter.
+                    pass
+        "#},
+    );
+}
+
+#[test]
+fn test_exception_variable_scope() {
+    run_with_expectations(
+        "test_exception_variable_scope.py",
+        indoc! {r#"
+            try:
+                x = 1
+            except (KeyError, IndexError) as e:
+                reveal_type(e)  # Debug: Type is Union[IndexError, KeyError]
+            except ValueError as e:
+                err: Exception = e
+            e  # Debug: Name "e" not found in scope.
+        "#},
+    );
+}
+
+#[test]
+fn test_try_merges_handlers() {
+    run_with_expectations(
+        "test_try_merges_handlers.py",
+        indoc! {r#"
+            def f() -> None:
+                try:
+                    d = 1
+                    only_in_body = 1
+                except ValueError:
+                    d = "s"
+                    only_in_body  # Debug: Name "only_in_body" not found in scope.
+                except KeyError:
+                    return
+                reveal_type(d)  # Debug: Type is Literal[1, "s"]
+        "#},
+    );
+}
+
+#[test]
+fn test_loops() {
+    run_with_expectations(
+        "test_loops.py",
+        indoc! {r#"
+            for i in (1, "a"):
+                reveal_type(i)  # Debug: Type is Literal[1, "a"]
+                break
+            else:
+                done = True
+            n: int = 0
+            for n in ("a",):  # Debug: Expected int but found Literal["a"].
+                continue
+            x = 0
+            while __debug__:
+                x = 1
+            else:
+                reveal_type(x)  # Debug: Type is Literal[0, 1]
+        "#},
+    );
+}
+
+#[test]
+fn test_loop_else_runs_without_break() {
+    run_with_expectations(
+        "test_loop_else_runs_without_break.py",
+        indoc! {r#"
+            def f(flag: bool, items: tuple[int, ...]) -> None:
+                while flag:
+                    b = 1
+                    if flag:
+                        break
+                else:
+                    b = "x"
+                reveal_type(b)  # Debug: Type is Literal[1, "x"]
+                for item in items:
+                    c = item
+                    break
+                else:
+                    c = None
+                reveal_type(c)  # Debug: Type is Optional[int]
+        "#},
+    );
+}
+
+#[test]
+fn test_branches_merge_variables() {
+    run_with_expectations(
+        "test_branches_merge_variables.py",
+        indoc! {r#"
+            def f(flag: bool, other: bool) -> None:
+                if flag:
+                    a = 1
+                elif other:
+                    a = "a"
+                else:
+                    a = None
+                reveal_type(a)  # Debug: Type is Optional[Literal[1, "a"]]
+                b = 1
+                if flag:
+                    b = 2
+                reveal_type(b)  # Debug: Type is Literal[1, 2]
+                c = 1
+                if flag:
+                    c = "c"
+                else:
+                    return
+                reveal_type(c)  # Debug: Type is Literal["c"]
+                d = 0
+                while flag:
+                    d = "d"
+                reveal_type(d)  # Debug: Type is Literal[0, "d"]
+        "#},
+    );
+}
+
+#[test]
+fn test_dead_branches_are_skipped() {
+    run_with_expectations(
+        "test_dead_branches_are_skipped.py",
+        indoc! {r#"
+            DEBUG = False
+            if DEBUG:
+                a: int = "not checked"
+            elif 1:
+                b = 1
+            else:
+                c: int = "not checked either"
+            reveal_type(b)  # Debug: Type is Literal[1]
+        "#},
+    );
+}
+
+#[test]
+fn test_constant_folding() {
+    run_with_expectations(
+        "test_constant_folding.py",
+        indoc! {r#"
+            import sys
+            N = 2 * 2
+            x: Literal[4] = N
+            y: Literal[-1] = 3 - N
+            reveal_type("a" + "b")  # Debug: Type is Literal["ab"]
+            reveal_type(-7 // 2)  # Debug: Type is Literal[-4]
+            reveal_type(N / 2)  # Debug: Type is float
+            reveal_type(2 ** 3)  # Debug: Type is Literal[8]
+            reveal_type(2 ** -1)  # Debug: Type is float
+            reveal_type(1 < N <= 4)  # Debug: Type is Literal[True]
+            if 1 + 1 == 2:
+                a = 1
+            else:
+                a: int = "not checked"
+            if sys.version_info >= (3, 8):
+                b = 1
+            if not sys.version_info < (3, 8):
+                c = 1
+            reveal_type(b)  # Debug: Type is Literal[1]
+            z = "a" - 1  # Debug: Unsupported operand types for -: Literal["a"] and Literal[1].
+        "#},
+    );
+}
+
+#[test]
+fn test_variadic_parameters() {
+    run_with_expectations(
+        "test_variadic_parameters.py",
+        indoc! {r#"
+            def f(a: int, *args: int, key: str, flag: bool = False, **kwargs: float) -> int:
+                reveal_type(args)  # Debug: Type is tuple[int, ...]
+                reveal_type(kwargs)  # Debug: Type is dict[str, float]
+                return a
+            reveal_type(f)  # Debug: Type is (a: int, *args: int, key: str, flag: bool, **kwargs: float) -> int
+            f(1, 2, 3, key="k", other=1.5)
+            f(1, *(2, 3), key="k")
+            f(1, "2", key="k")  # Debug: Expected int but found Literal["2"].
+            f(1)  # Debug: Keyword argument "key" is required.
+            f(1, a=2, key="k")  # Debug: Keyword argument "a" is given more than once.
+            f(1, key=2)  # Debug: Expected str but found Literal[2].
+            def g(a: int) -> int:
+                return a
+            g(b=1)  # Debug: Keyword argument "b" is not a parameter of the function. # Debug: expected 1 args, got 1 args
+            g(a=1)
+            print("a", sep=", ")
+        "#},
+    );
+}
+
+#[test]
+fn test_default_parameters() {
+    run_with_expectations(
+        "test_default_parameters.py",
+        indoc! {r#"
+            def f(a: int, b: int = 0, c=1.5) -> int:
+                return a
+            reveal_type(f)  # Debug: Type is (a: int, b: int, c: float) -> int
+            f(1)
+            f(1, 2)
+            f(1, c=2.5)
+            f(1, 2, 3.5)
+            f()  # Debug: expected 1 to 3 args, got 0 args
+            f(1, 2, 3.5, 4)  # Debug: expected 1 to 3 args, got 4 args
+            f(1, "2")  # Debug: Expected int but found Literal["2"].
+            def g(n=0, x=None, *, k="k") -> int:
+                reveal_type(k)  # Debug: Type is str
+                return n
+            reveal_type(g)  # Debug: Type is (n: int, x: Unknown, *, k: str) -> int
+            g(1, 1)
+        "#},
+    );
+}
+
+#[test]
+fn test_positional_and_keyword_only_parameters() {
+    run_with_expectations(
+        "test_positional_and_keyword_only_parameters.py",
+        indoc! {r#"
+            def f(a: int, /, b: int, *, c: int) -> int:
+                return a
+            reveal_type(f)  # Debug: Type is (a: int, /, b: int, *, c: int) -> int
+            f(1, 2, c=3)
+            f(1, b=2, c=3)
+            f(a=1, b=2, c=3)  # Debug: Parameter "a" is positional only and can't be passed by keyword.
+            f(1, 2, 3, c=4)  # Debug: Expected at most 2 positional arguments but got 3.
+            def g(a: int, /, **kwargs: int) -> int:
+                return a
+            g(1, a=2)
+            class Point:
+                def move(self, dx: int, /) -> int: ...
+            reveal_type(Point().move)  # Debug: Type is (dx: int, /) -> int
+        "#},
+    );
+}
+
+#[test]
+fn test_binary_operations() {
+    run_with_expectations(
+        "test_binary_operations.py",
+        indoc! {r#"
+            def f(i: int, x: float, s: str, b: bool) -> None:
+                reveal_type(i + i)  # Debug: Type is int
+                reveal_type(i * x)  # Debug: Type is float
+                reveal_type(b & b)  # Debug: Type is bool
+                reveal_type(s + s)  # Debug: Type is str
+                reveal_type(s * i)  # Debug: Type is str
+                reveal_type(2.5 + 1)  # Debug: Type is float
+                reveal_type(b"a" + b"b")  # Debug: Type is Literal[b"ab"]
+                reveal_type("ab" * 2)  # Debug: Type is Literal["abab"]
+                s + i  # Debug: Unsupported operand types for +: str and int.
+                b"a" + "b"  # Debug: Unsupported operand types for +: Literal[b"a"] and Literal["b"].
+            class Vector:
+                def __add__(self, other: Vector) -> int:
+                    return 1
+                def __rmul__(self, other: int) -> str:
+                    return ""
+            def g(v: Vector) -> None:
+                reveal_type(v + v)  # Debug: Type is int
+                reveal_type(2 * v)  # Debug: Type is str
+                v - v  # Debug: Unsupported operand types for -: Vector and Vector.
+        "#},
+    );
+}
+
+#[test]
+fn test_boolean_operations() {
+    run_with_expectations(
+        "test_boolean_operations.py",
+        indoc! {r#"
+            from typing import Optional
+            def f(name: Optional[str], n: int, flag: bool) -> None:
+                reveal_type(name or "anonymous")  # Debug: Type is str
+                reveal_type(name and len(name))  # Debug: Type is Optional[Union[int, str]]
+                reveal_type(n < 3 or flag)  # Debug: Type is bool
+                reveal_type(0 or n)  # Debug: Type is int
+                reveal_type(1 or n)  # Debug: Type is Literal[1]
+                reveal_type(None and n)  # Debug: Type is None
+                reveal_type(n == 1 and "one")  # Debug: Type is Union[Literal["one"], bool]
+        "#},
+    );
+}
+
+#[test]
+fn test_unary_operations() {
+    run_with_expectations(
+        "test_unary_operations.py",
+        indoc! {r#"
+            def f(i: int, x: float, s: str) -> None:
+                reveal_type(not s)  # Debug: Type is bool
+                reveal_type(not "")  # Debug: Type is Literal[True]
+                reveal_type(-i)  # Debug: Type is int
+                reveal_type(~i)  # Debug: Type is int
+                reveal_type(-x)  # Debug: Type is float
+                reveal_type(-2)  # Debug: Type is Literal[-2]
+                reveal_type(-1.5)  # Debug: Type is Literal[-1.5]
+                ~x  # Debug: Unsupported operand type for ~: float.
+                -s  # Debug: Unsupported operand type for -: str.
+            class Money:
+                def __neg__(self) -> int:
+                    return 1
+            def g(m: Money) -> None:
+                reveal_type(-m)  # Debug: Type is int
+                +m  # Debug: Unsupported operand type for +: Money.
+        "#},
+    );
+}
+
+#[test]
+fn test_conditional_expressions() {
+    run_with_expectations(
+        "test_conditional_expressions.py",
+        indoc! {r#"
+            def f(flag: bool, name: str) -> None:
+                reveal_type(1 if flag else "a")  # Debug: Type is Literal[1, "a"]
+                reveal_type(name if flag else None)  # Debug: Type is Optional[str]
+                reveal_type(1 if True else "a")  # Debug: Type is Literal[1]
+                reveal_type(undefined if "" else 2)  # Debug: Type is Literal[2]
+                a: int = 1 if flag else "a"  # Debug: Expected int but found Literal["a"].
+                b: list[float] = [1] if flag else []
+        "#},
+    );
+}
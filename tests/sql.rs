@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use indoc::indoc;
+use pycavalry::{
+    error_check_file_with_config, Config, PlaceholderValidator, SqlParameterTypeDiag,
+    SqlPlaceholderCountDiag,
+};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_sql_placeholders() {
+    let content = indoc! {r#"
+        def execute(sql: str, params: Any) -> None:
+            pass
+        execute("SELECT * FROM t WHERE a = ? AND b = '?'", (1,))
+        execute("SELECT * FROM t WHERE a = %s AND b = %s", (1,))
+        execute("INSERT INTO t VALUES (?)", ((1, 2),))
+    "#};
+    let config = Config {
+        sql_validators: vec![Arc::new(PlaceholderValidator)],
+        ..Default::default()
+    };
+    let info = error_check_file_with_config(
+        "test_sql_placeholders.py".into(),
+        content.into(),
+        Arc::new(config),
+    )
+    .unwrap();
+    // The parameters of the second and third calls
+    let count_start = content.rfind("(1,)").unwrap() as u32;
+    let type_start = content.find("((1, 2),)").unwrap() as u32;
+    assert_errors(
+        &info,
+        vec![
+            SqlPlaceholderCountDiag::new(2, 1, r(count_start..count_start + 4)).into(),
+            SqlParameterTypeDiag::new(
+                ann("tuple[Literal[1], Literal[2]]"),
+                r(type_start..type_start + 9),
+            )
+            .into(),
+        ],
+    );
+}
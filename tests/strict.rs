@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use indoc::indoc;
+use pycavalry::{error_check_file_with_config, Config, UntypedDefDiag};
+
+mod common;
+use common::*;
+
+fn strict() -> Arc<Config> {
+    Arc::new(Config {
+        strict: true,
+        strict_exempt_decorators: vec!["fixture".to_owned()],
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_strict_untyped_defs() {
+    let content = indoc! {r#"
+        def f(a, b: int):
+            return b
+        @pytest.fixture
+        def g(a):
+            return a
+    "#};
+    let info = error_check_file_with_config(
+        "test_strict_untyped_defs.py".into(),
+        content.into(),
+        strict(),
+    )
+    .unwrap();
+    assert_errors(
+        &info,
+        vec![
+            UntypedDefDiag::new(sym("f"), Some(sym("a")), r(6..7)).into(),
+            UntypedDefDiag::new(sym("f"), None, r(4..5)).into(),
+        ],
+    );
+}
+
+#[test]
+fn test_strict_file_opt_out() {
+    let content = indoc! {r#"
+        # pycavalry: no-strict
+        def f(a):
+            return a
+    "#};
+    let info = error_check_file_with_config(
+        "test_strict_file_opt_out.py".into(),
+        content.into(),
+        strict(),
+    )
+    .unwrap();
+    assert_errors(&info, vec![]);
+}
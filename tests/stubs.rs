@@ -0,0 +1,40 @@
+use std::{fs, sync::Arc};
+
+use indoc::indoc;
+use pycavalry::{error_check_file_with_config, testing::TempDir, Config};
+
+#[test]
+fn test_stub_paths() {
+    let dir = TempDir::new("stub_paths");
+    fs::create_dir_all(dir.join("stubs")).unwrap();
+    fs::write(
+        dir.join("stubs/builtins.pyi"),
+        "def shout(message: str) -> str: ...\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("stubs/mylib.pyi"),
+        "def parse(text: str) -> int:\n    \"\"\"Parse an int.\"\"\"\n    ...\n",
+    )
+    .unwrap();
+    // Stubs win over the source of a module
+    fs::write(dir.join("mylib.py"), "def parse(text):\n    return text\n").unwrap();
+
+    let content = indoc! {r#"
+        from mylib import parse
+        reveal_type(parse)
+        shout(parse("1"))
+    "#};
+    let config = Config {
+        stub_paths: vec![dir.join("stubs")],
+        ..Default::default()
+    };
+    let info = error_check_file_with_config(dir.join("main.py"), content.into(), Arc::new(config))
+        .unwrap();
+    let errors = info.reporter.errors();
+    let messages: Vec<String> = errors.lock().unwrap().iter().map(|e| e.message()).collect();
+    assert_eq!(
+        messages,
+        vec!["Type is (text: str) -> int", "Expected str but found int."]
+    );
+}
@@ -0,0 +1,33 @@
+use indoc::indoc;
+
+mod common;
+use common::*;
+
+#[test]
+fn test_no_type_check() {
+    run_with_expectations(
+        "test_no_type_check.py",
+        indoc! {r#"
+            from typing import no_type_check
+            @no_type_check
+            def f(a: int) -> str:
+                b: int = "not checked"
+                return a
+            reveal_type(f)  # Debug: Type is (a: Unknown) -> Unknown
+            f("anything")
+        "#},
+    );
+}
+
+#[test]
+fn test_disable_codes_in_function() {
+    run_with_expectations(
+        "test_disable_codes_in_function.py",
+        indoc! {r#"
+            def f() -> None:  # pycavalry: disable=PCV001
+                x = missing
+                a: int = "still reported"  # Debug: Expected int but found Literal["still reported"].
+            missing  # Debug: Name "missing" not found in scope.
+        "#},
+    );
+}
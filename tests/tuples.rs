@@ -0,0 +1,114 @@
+use indoc::indoc;
+use pycavalry::RevealTypeDiag;
+
+mod common;
+use common::*;
+
+#[test]
+fn test_tuple_starred_splice() {
+    run_with_errors(
+        "test_tuple_starred_splice.py",
+        "reveal_type((1, *(2, \"a\"), 3))",
+        vec![RevealTypeDiag::new(
+            ann("tuple[Literal[1], Literal[2], Literal[\"a\"], Literal[3]]"),
+            r(12..29),
+        )
+        .into()],
+    );
+}
+
+#[test]
+fn test_variadic_tuples() {
+    run_with_expectations(
+        "test_variadic_tuples.py",
+        indoc! {r#"
+            def f(xs: tuple[int, ...], pair: tuple[int, str]) -> None:
+                reveal_type(xs)  # Debug: Type is tuple[int, ...]
+                reveal_type(xs[5])  # Debug: Type is int
+                reveal_type(pair[-1])  # Debug: Type is str
+                reveal_type(pair[2])  # Debug: Index 2 is out of range for tuple[int, str]. # Debug: Type is Unknown
+                reveal_type((1, *xs))  # Debug: Type is tuple[int, ...]
+                for x in xs:
+                    reveal_type(x)  # Debug: Type is int
+                ys: tuple[int, ...] = (1, 2, 3)
+                zs: tuple[int, ...] = (1, "a")  # Debug: Expected tuple[int, ...] but found tuple[Literal[1], Literal["a"]].
+                fixed: tuple[int, int] = xs  # Debug: Expected tuple[int, int] but found tuple[int, ...].
+            bad: tuple[..., int] = ()  # Debug: ... is only allowed as the second argument of tuple
+        "#},
+    );
+}
+
+#[test]
+fn test_subscripts_of_containers() {
+    run_with_expectations(
+        "test_subscripts_of_containers.py",
+        indoc! {r#"
+            def f(ages: dict[str, int], names: list[str], s: str, t: tuple[int, ...]) -> None:
+                reveal_type(ages["a"])  # Debug: Type is int
+                reveal_type(names[0])  # Debug: Type is str
+                reveal_type(names[1:])  # Debug: Type is list[str]
+                reveal_type(s[0])  # Debug: Type is str
+                reveal_type(s[:2])  # Debug: Type is str
+                reveal_type(t[3])  # Debug: Type is int
+                reveal_type("abc"[-1])  # Debug: Type is Literal["c"]
+                ages[0]  # Debug: dict[str, int] can't be indexed with Literal[0].
+                names["first"]  # Debug: list[str] can't be indexed with Literal["first"].
+                t["a"]  # Debug: tuple[int, ...] can't be indexed with Literal["a"].
+            class Grid:
+                def __getitem__(self, key: int) -> str:
+                    return ""
+            def g(grid: Grid) -> None:
+                reveal_type(grid[1])  # Debug: Type is str
+                grid["a"]  # Debug: Grid can't be indexed with Literal["a"].
+        "#},
+    );
+}
+
+#[test]
+fn test_container_displays() {
+    run_with_expectations(
+        "test_container_displays.py",
+        indoc! {r#"
+            reveal_type([1, 2])  # Debug: Type is list[int]
+            reveal_type([1, "a", *(None,)])  # Debug: Type is list[Optional[Union[int, str]]]
+            reveal_type({"a"})  # Debug: Type is set[str]
+            reveal_type({"a": 1, **{"b": 2.0}})  # Debug: Type is dict[str, float]
+            reveal_type([])  # Debug: Type is list[Unknown]
+            floats: list[float] = [1, 2.5]
+            names: set[str] = {"a", 1}  # Debug: Expected str but found Literal[1].
+            ages: dict[str, int] = {"a": 1, "b": ""}  # Debug: Expected int but found Literal[""].
+            def f(ints: list[int]) -> None:
+                x: list[str] = ints  # Debug: Expected list[str] but found list[int].
+                y: list[float] = [*ints]
+        "#},
+    );
+}
+
+#[test]
+fn test_mutating_container_methods() {
+    run_with_expectations(
+        "test_mutating_container_methods.py",
+        indoc! {r#"
+            def f(nums: list[int], ages: dict[str, int], seen: set[int]) -> None:
+                nums.append(1)
+                nums.append("x")  # Debug: Expected int but found Literal["x"].
+                nums.extend((1, 2))
+                nums.insert(0, 1.5)  # Debug: Expected int but found Literal[1.5].
+                reveal_type(nums.pop())  # Debug: Type is int
+                nums.sort(reverse=True)
+                ages.update({"a": 1})
+                ages.update(b="2")  # Debug: Expected int but found Literal["2"].
+                reveal_type(ages.setdefault("c", 3))  # Debug: Type is int
+                ages.setdefault(1, 3)  # Debug: Expected str but found Literal[1].
+                reveal_type(ages.setdefault("d"))  # Debug: Type is Optional[int]
+                reveal_type(ages.get("a"))  # Debug: Type is Optional[int]
+                reveal_type(ages.get("a", 0))  # Debug: Type is int
+                reveal_type(ages.get("a", ""))  # Debug: Type is Union[Literal[""], int]
+                reveal_type(ages.pop("a"))  # Debug: Type is int
+                reveal_type(ages.pop("a", None))  # Debug: Type is Optional[int]
+                seen.add(None)  # Debug: Expected int but found None.
+                seen.update([1], {2})
+                nums.push(1)  # Debug: Unknown attribute "push" for list[int] # Debug: Unknown not callable
+        "#},
+    );
+}
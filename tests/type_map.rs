@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use pycavalry::{error_check_file, error_check_file_with_config, type_map, Config};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_type_map_lists_every_expression() {
+    let config = Config {
+        record_types: true,
+        ..Default::default()
+    };
+    let info = error_check_file_with_config(
+        "test_type_map.py".into(),
+        "a = 1\nb = (a, 'x')\n".into(),
+        Arc::new(config),
+    )
+    .unwrap();
+    let types: Vec<_> = type_map(&info)
+        .into_iter()
+        .map(|t| (t.range, t.typ.to_string()))
+        .collect();
+    assert_eq!(
+        types,
+        vec![
+            (r(4..5), "Literal[1]".to_owned()),
+            (r(10..18), "tuple[Literal[1], Literal[\"x\"]]".to_owned()),
+            (r(11..12), "Literal[1]".to_owned()),
+            (r(14..17), "Literal[\"x\"]".to_owned()),
+        ]
+    );
+
+    let info = error_check_file("test_type_map.py".into(), "a = 1\n".into()).unwrap();
+    assert!(type_map(&info).is_empty());
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use indoc::indoc;
+use pycavalry::{error_check_file_with_config, Config, UnusedParameterDiag};
+
+mod common;
+use common::*;
+
+#[test]
+fn test_unused_parameters() {
+    let content = indoc! {r#"
+        def f(used: int, unused: int, _ignored: int, self: int) -> int:
+            inner = lambda: used
+            return 1
+    "#};
+    let config = Config {
+        warn_unused_parameters: true,
+        ..Default::default()
+    };
+    let info = error_check_file_with_config(
+        "test_unused_parameters.py".into(),
+        content.into(),
+        Arc::new(config),
+    )
+    .unwrap();
+    assert_errors(
+        &info,
+        vec![UnusedParameterDiag::new(sym("unused"), sym("f"), r(17..23)).into()],
+    );
+}